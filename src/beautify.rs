@@ -0,0 +1,264 @@
+use serde_json::Value;
+
+/// Lines this long only appear in minified code; hand-written files are left alone.
+const MINIFIED_LINE_LENGTH: usize = 300;
+
+/// Keywords after which a `/` opens a regex, as none of them can end an operand.
+const REGEX_KEYWORDS: &[&str] = &[
+    "case",
+    "delete",
+    "do",
+    "else",
+    "in",
+    "instanceof",
+    "new",
+    "of",
+    "return",
+    "throw",
+    "typeof",
+    "void",
+    "yield",
+    "await",
+];
+
+fn is_identifier(x: char) -> bool {
+    x.is_alphanumeric() || x == '_' || x == '$'
+}
+
+/// Re-indents brace-structured code (CSS or JavaScript) by breaking lines after `{`,
+/// `}` and top-level `;`. Strings, comments and, for JavaScript, template and regex
+/// literals are copied untouched, and existing line breaks are kept so automatic
+/// semicolon insertion still sees them.
+struct CodeFormatter {
+    js: bool,
+    result: String,
+    indent: usize,
+    parens: usize,
+    line_start: bool,
+    /// Last character written outside whitespace, strings and comments.
+    last: Option<char>,
+}
+
+impl CodeFormatter {
+    fn new(code: &str, js: bool) -> CodeFormatter {
+        CodeFormatter {
+            js,
+            result: String::with_capacity(code.len() * 5 / 4),
+            indent: 0,
+            parens: 0,
+            line_start: true,
+            last: None,
+        }
+    }
+
+    fn push(&mut self, x: char) {
+        if self.line_start {
+            for _ in 0..self.indent {
+                self.result.push_str("  ");
+            }
+            self.line_start = false;
+        }
+        self.result.push(x);
+    }
+
+    fn newline(&mut self) {
+        if !self.line_start {
+            let trimmed = self.result.trim_end_matches([' ', '\t']).len();
+            self.result.truncate(trimmed);
+            self.result.push('\n');
+            self.line_start = true;
+        }
+    }
+
+    /// Copies a literal up to and including its unescaped `close` character.
+    fn copy_until(&mut self, chars: &mut std::iter::Peekable<std::str::Chars>, close: char) {
+        let mut in_class = false;
+        while let Some(x) = chars.next() {
+            self.push(x);
+            match x {
+                '\\' => {
+                    if let Some(x) = chars.next() {
+                        self.push(x);
+                    }
+                }
+                // A `/` inside a regex character class does not end the regex.
+                '[' if close == '/' => in_class = true,
+                ']' if close == '/' => in_class = false,
+                x if x == close && !in_class => return,
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether a `/` here opens a regex rather than divides: after an operator or
+    /// punctuation, or after a keyword such as `return` that cannot end an operand.
+    fn starts_regex(&self) -> bool {
+        let Some(last) = self.last else {
+            return true;
+        };
+        if !is_identifier(last) {
+            return "(,=:[!&|?{};+-*%<>~^".contains(last);
+        }
+        let code = self.result.trim_end();
+        let word = &code[code.trim_end_matches(is_identifier).len()..];
+        // `x.return` is a property, not the keyword.
+        let property = code[..code.len() - word.len()].ends_with('.');
+        !property && REGEX_KEYWORDS.contains(&word)
+    }
+
+    fn format(mut self, code: &str) -> String {
+        let mut chars = code.chars().peekable();
+        while let Some(x) = chars.next() {
+            match x {
+                '"' | '\'' => {
+                    self.push(x);
+                    self.copy_until(&mut chars, x);
+                    self.last = Some(x);
+                }
+                '`' if self.js => {
+                    self.push(x);
+                    self.copy_until(&mut chars, x);
+                    self.last = Some(x);
+                }
+                '/' if chars.peek() == Some(&'*') => {
+                    self.push(x);
+                    let mut previous = ' ';
+                    for x in chars.by_ref() {
+                        self.push(x);
+                        if previous == '*' && x == '/' {
+                            break;
+                        }
+                        previous = x;
+                    }
+                }
+                '/' if self.js && chars.peek() == Some(&'/') => {
+                    self.push(x);
+                    while let Some(x) = chars.next_if(|x| *x != '\n') {
+                        self.push(x);
+                    }
+                }
+                '/' if self.js && self.starts_regex() => {
+                    self.push(x);
+                    self.copy_until(&mut chars, '/');
+                    // A `/` right after a regex is a division.
+                    self.last = Some('/');
+                }
+                '\n' => self.newline(),
+                x if x.is_whitespace() => {
+                    if !self.line_start && !self.result.ends_with(' ') {
+                        self.push(' ');
+                    }
+                }
+                '{' => {
+                    self.push(x);
+                    self.indent += 1;
+                    self.newline();
+                    self.last = Some(x);
+                }
+                '}' => {
+                    self.indent = self.indent.saturating_sub(1);
+                    self.newline();
+                    self.push(x);
+                    self.last = Some(x);
+                    while chars.next_if(|x| *x == ' ' || *x == '\t').is_some() {}
+                    if !chars.peek().is_some_and(|x| ",;).".contains(*x)) {
+                        self.newline();
+                    }
+                }
+                ';' => {
+                    self.push(x);
+                    self.last = Some(x);
+                    if self.parens == 0 {
+                        self.newline();
+                    }
+                }
+                '(' => {
+                    self.parens += 1;
+                    self.push(x);
+                    self.last = Some(x);
+                }
+                ')' => {
+                    self.parens = self.parens.saturating_sub(1);
+                    self.push(x);
+                    self.last = Some(x);
+                }
+                x => {
+                    self.push(x);
+                    self.last = Some(x);
+                }
+            }
+        }
+        self.newline();
+        self.result
+    }
+}
+
+fn format_code(code: &str, js: bool) -> Option<String> {
+    if !code.lines().any(|x| x.len() >= MINIFIED_LINE_LENGTH) {
+        return None;
+    }
+    Some(CodeFormatter::new(code, js).format(code))
+}
+
+/// Pretty-prints a JSON body, or re-indents minified CSS and JavaScript. Returns `None`
+/// for other types, bodies that do not parse and code that is not minified.
+pub fn beautify(essence: &str, bytes: &[u8]) -> Option<Vec<u8>> {
+    match essence {
+        x if x == "application/json" || x.ends_with("+json") => {
+            let value = serde_json::from_slice::<Value>(bytes).ok()?;
+            let mut result = serde_json::to_vec_pretty(&value).ok()?;
+            result.push(b'\n');
+            Some(result)
+        }
+        "text/css" => format_code(std::str::from_utf8(bytes).ok()?, false).map(String::into_bytes),
+        "application/javascript" | "text/javascript" | "application/x-javascript" => {
+            format_code(std::str::from_utf8(bytes).ok()?, true).map(String::into_bytes)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_js(code: &str) -> String {
+        CodeFormatter::new(code, true).format(code)
+    }
+
+    #[test]
+    fn copies_a_regex_after_a_keyword_untouched() {
+        assert_eq!(
+            format_js("function f(x){return /{/.test(x)}"),
+            "function f(x){\n  return /{/.test(x)\n}\n"
+        );
+        assert_eq!(
+            format_js("if(typeof /;/==\"object\"){a()}"),
+            "if(typeof /;/==\"object\"){\n  a()\n}\n"
+        );
+        assert_eq!(
+            format_js("for(x of /}/.exec(s));"),
+            "for(x of /}/.exec(s));\n"
+        );
+    }
+
+    #[test]
+    fn reads_a_slash_after_an_operand_as_division() {
+        assert_eq!(format_js("n=a/2;m=b/3"), "n=a/2;\nm=b/3\n");
+        assert_eq!(format_js("n=x.return/2;m=1"), "n=x.return/2;\nm=1\n");
+        assert_eq!(format_js("n=(a)/2/{b:1}.b"), "n=(a)/2/{\n  b:1\n}.b\n");
+    }
+
+    #[test]
+    fn keeps_braces_in_strings_and_comments() {
+        assert_eq!(
+            format_js("a(\"{\");/* } */b('}')"),
+            "a(\"{\");\n/* } */b('}')\n"
+        );
+    }
+
+    #[test]
+    fn leaves_code_that_is_not_minified_alone() {
+        assert_eq!(beautify("application/javascript", b"a();\nb();\n"), None);
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+};
+
+use extract_har::{
+    har::{self, HarLogEntry},
+    import,
+};
+use serde_json::Value;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+use crate::redact;
+
+/// Something that went wrong during a run, kept for the error bundle.
+pub struct Failure {
+    /// Position of the entry in the HAR, if the failure concerns one.
+    pub index: Option<usize>,
+    pub message: String,
+}
+
+/// Replaces every body-like string with a note about its length, so that the bundle
+/// can be attached publicly without leaking captured content.
+fn strip_bodies(value: &mut Value) {
+    fn strip(value: Option<&mut Value>) {
+        if let Some(value) = value {
+            if let Some(text) = value.as_str() {
+                *value = Value::String(format!("<stripped {} chars>", text.len()));
+            }
+        }
+    }
+    if let Some(content) = value.pointer_mut("/response/content") {
+        strip(content.get_mut("text"));
+    }
+    if let Some(post_data) = value.pointer_mut("/request/postData") {
+        strip(post_data.get_mut("text"));
+        if let Some(params) = post_data.get_mut("params").and_then(|x| x.as_array_mut()) {
+            params.iter_mut().for_each(|x| strip(x.get_mut("value")));
+        }
+    }
+    if let Some(messages) = value
+        .get_mut("_webSocketMessages")
+        .and_then(|x| x.as_array_mut())
+    {
+        messages.iter_mut().for_each(|x| strip(x.get_mut("data")));
+    }
+}
+
+/// Packs the failure log, the run configuration and the offending entries (bodies
+/// stripped, secrets masked as by `redact`) into a zip archive.
+pub fn write(
+    bundle: &Path,
+    input_file_path: &Path,
+    config: &str,
+    failures: &[Failure],
+) -> zip::result::ZipResult<()> {
+    let mut log = failures
+        .iter()
+        .map(|x| match x.index {
+            Some(index) => format!("entry {index}: {}\n", x.message),
+            None => format!("{}\n", x.message),
+        })
+        .collect::<String>();
+    // Read as leniently as possible, since the document may be why the run failed.
+    let document = match import::read(input_file_path) {
+        Some(document) => document.ok(),
+        None => fs::read(input_file_path)
+            .ok()
+            .and_then(|x| har::read_json(&x).ok()),
+    };
+    let entries = document
+        .as_ref()
+        .and_then(|x| x.pointer("/log/entries"))
+        .and_then(|x| x.as_array());
+    let mut indices = failures.iter().filter_map(|x| x.index).collect::<Vec<_>>();
+    // When the document as a whole did not match the model, find out which entries
+    // are to blame.
+    if failures.iter().any(|x| x.index.is_none()) {
+        for (index, entry) in entries.into_iter().flatten().enumerate() {
+            if let Err(err) = serde_json::from_value::<HarLogEntry>(entry.clone()) {
+                log.push_str(&format!("entry {index}: {err}\n"));
+                indices.push(index);
+            }
+        }
+    }
+    indices.sort();
+    indices.dedup();
+
+    let options = SimpleFileOptions::default();
+    let mut zip = ZipWriter::new(File::create(bundle)?);
+    zip.start_file("failures.log", options)?;
+    zip.write_all(log.as_bytes())?;
+    zip.start_file("config.txt", options)?;
+    zip.write_all(config.as_bytes())?;
+    for index in indices {
+        if let Some(entry) = entries.and_then(|x| x.get(index)) {
+            let mut entry = entry.clone();
+            strip_bodies(&mut entry);
+            redact::mask_secrets(&mut entry);
+            zip.start_file(format!("entries/{index}.json"), options)?;
+            zip.write_all(&serde_json::to_vec_pretty(&entry).unwrap())?;
+        }
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use serde_json::json;
+    use zip::ZipArchive;
+
+    use super::*;
+
+    #[test]
+    fn bundles_entries_of_a_document_with_a_byte_order_mark() {
+        let dir = std::env::temp_dir().join(format!("extract_har-bundle-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let entry = json!({
+            "request": {
+                "method": "GET",
+                "url": "https://a.com/?access_token=abc&page=2",
+                "headers": [{ "name": "Authorization", "value": "Bearer abc" }],
+                "cookies": [{ "name": "sid", "value": "abc" }],
+                "queryString": [{ "name": "access_token", "value": "abc" }],
+            },
+            "response": {
+                "status": 200,
+                "headers": [{ "name": "Set-Cookie", "value": "sid=abc" }],
+                "content": { "mimeType": "text/html", "text": "abc" },
+            },
+        });
+        let document = json!({ "log": { "entries": [entry] } });
+        let input = dir.join("input.har");
+        fs::write(&input, format!("\u{FEFF}{document} trailing")).unwrap();
+        let bundle = dir.join("bundle.zip");
+        let failure = Failure {
+            index: Some(0),
+            message: "failed".to_string(),
+        };
+        write(&bundle, &input, "config", &[failure]).unwrap();
+
+        let mut archive = ZipArchive::new(File::open(&bundle).unwrap()).unwrap();
+        let mut bundled = String::new();
+        let mut file = archive.by_name("entries/0.json").unwrap();
+        file.read_to_string(&mut bundled).unwrap();
+        drop(file);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!bundled.contains("abc"), "{bundled}");
+        assert!(bundled.contains("<stripped 3 chars>"));
+        assert!(bundled.contains("page=2"));
+    }
+}
@@ -0,0 +1,351 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read, Write},
+    net::TcpStream,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::DateTime;
+use clap::Args;
+use serde_json::{json, Value};
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+use url::Url;
+
+use crate::extract::{self, ExtractArgs};
+
+#[derive(Args)]
+pub struct CaptureArgs {
+    /// Host Chrome's remote debugging server listens on
+    #[arg(long, default_value = "localhost")]
+    host: String,
+    /// Chrome's `--remote-debugging-port`
+    #[arg(long, default_value_t = 9222)]
+    port: u16,
+    /// DevTools endpoint such as `ws://localhost:9222`, or a page's
+    /// `webSocketDebuggerUrl` to attach to that page directly
+    #[arg(long, conflicts_with_all = ["host", "port"])]
+    cdp: Option<Url>,
+    /// Attach to the first page whose URL or title contains this text
+    #[arg(long)]
+    target: Option<String>,
+    /// Stop after this many seconds instead of waiting for Ctrl+C
+    #[arg(long)]
+    duration: Option<u64>,
+    /// HAR file to write the recorded traffic to, or a folder (existing or ending with
+    /// `/`) to extract the recorded bodies into without keeping a HAR
+    #[arg(short, long, default_value = "capture.har")]
+    output: PathBuf,
+    /// Extract the recorded capture into this folder once recording stops
+    #[arg(long)]
+    extract_to: Option<String>,
+}
+
+/// Fetches a JSON document from Chrome's DevTools HTTP endpoint.
+fn http_get_json(host: &str, port: u16, path: &str) -> io::Result<Value> {
+    let mut stream = TcpStream::connect((host, port))?;
+    write!(
+        stream,
+        "GET {path} HTTP/1.1\r\nHost: {host}:{port}\r\nConnection: close\r\n\r\n"
+    )?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    let body = response
+        .windows(4)
+        .position(|x| x == b"\r\n\r\n")
+        .map(|x| &response[x + 4..])
+        .unwrap_or_default();
+    serde_json::from_slice(body).map_err(io::Error::other)
+}
+
+fn har_headers(headers: &Value) -> Vec<Value> {
+    headers
+        .as_object()
+        .into_iter()
+        .flatten()
+        .flat_map(|(name, value)| {
+            // CDP joins repeated headers with newlines.
+            value
+                .as_str()
+                .unwrap_or_default()
+                .split('\n')
+                .map(|x| json!({ "name": name, "value": x }))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn har_request(request: &Value) -> Value {
+    let url = request["url"].as_str().unwrap_or_default();
+    let query = url::Url::parse(url)
+        .map(|x| {
+            x.query_pairs()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut result = json!({
+        "method": request["method"],
+        "url": url,
+        "httpVersion": "HTTP/1.1",
+        "headers": har_headers(&request["headers"]),
+        "queryString": query,
+        "cookies": [],
+        "headersSize": -1,
+        "bodySize": request["postData"].as_str().map(|x| x.len()).unwrap_or(0),
+    });
+    if let Some(post_data) = request["postData"].as_str() {
+        let mime_type = request["headers"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            .and_then(|(_, value)| value.as_str())
+            .unwrap_or_default();
+        result["postData"] = json!({ "mimeType": mime_type, "text": post_data });
+    }
+    result
+}
+
+fn har_response(response: &Value, redirect_url: &str) -> Value {
+    json!({
+        "status": response["status"],
+        "statusText": response["statusText"],
+        "httpVersion": response["protocol"].as_str().unwrap_or("HTTP/1.1"),
+        "headers": har_headers(&response["headers"]),
+        "cookies": [],
+        "content": {
+            "size": 0,
+            "mimeType": response["mimeType"].as_str().unwrap_or_default(),
+        },
+        "redirectURL": redirect_url,
+        "headersSize": -1,
+        "bodySize": -1,
+    })
+}
+
+/// Turns DevTools `Network.*` events into HAR entries.
+#[derive(Default)]
+struct Recorder {
+    entries: Vec<Value>,
+    /// Start `timestamp` (monotonic seconds) of every entry, used for `time`.
+    started: Vec<f64>,
+    by_request_id: HashMap<String, usize>,
+    next_id: u64,
+    /// Outstanding `Network.getResponseBody` calls by command id.
+    body_requests: HashMap<u64, usize>,
+}
+
+impl Recorder {
+    fn command(&mut self, method: &str, params: Value) -> (u64, String) {
+        self.next_id += 1;
+        let command = json!({ "id": self.next_id, "method": method, "params": params });
+        (self.next_id, command.to_string())
+    }
+
+    /// Handles one message from Chrome, returning a command to send back if needed.
+    fn handle(&mut self, message: &Value) -> Option<String> {
+        if let Some(id) = message["id"].as_u64() {
+            let index = self.body_requests.remove(&id)?;
+            let result = &message["result"];
+            if let Some(body) = result["body"].as_str() {
+                let content = &mut self.entries[index]["response"]["content"];
+                let base64 = result["base64Encoded"].as_bool().unwrap_or(false);
+                content["text"] = Value::from(body);
+                if base64 {
+                    content["encoding"] = Value::from("base64");
+                }
+                content["size"] = Value::from(if base64 {
+                    body.len() * 3 / 4
+                } else {
+                    body.len()
+                });
+            }
+            return None;
+        }
+        let params = &message["params"];
+        let request_id = params["requestId"].as_str().unwrap_or_default().to_string();
+        match message["method"].as_str().unwrap_or_default() {
+            "Network.requestWillBeSent" => {
+                // A redirect reuses the request id; its response closes the previous hop.
+                if let Some(redirect) = params.get("redirectResponse") {
+                    if let Some(&index) = self.by_request_id.get(&request_id) {
+                        let url = params["request"]["url"].as_str().unwrap_or_default();
+                        self.entries[index]["response"] = har_response(redirect, url);
+                    }
+                }
+                let wall_time = params["wallTime"].as_f64().unwrap_or_default();
+                let started = DateTime::from_timestamp_millis((wall_time * 1000.0) as i64)
+                    .unwrap_or_default()
+                    .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+                self.by_request_id.insert(request_id, self.entries.len());
+                self.started
+                    .push(params["timestamp"].as_f64().unwrap_or_default());
+                self.entries.push(json!({
+                    "startedDateTime": started,
+                    "time": 0,
+                    "request": har_request(&params["request"]),
+                    "response": har_response(&json!({ "status": 0 }), ""),
+                    "cache": {},
+                    "timings": { "send": 0, "wait": 0, "receive": 0 },
+                }));
+            }
+            "Network.responseReceived" => {
+                let &index = self.by_request_id.get(&request_id)?;
+                self.entries[index]["response"] = har_response(&params["response"], "");
+            }
+            "Network.loadingFinished" => {
+                let &index = self.by_request_id.get(&request_id)?;
+                let elapsed =
+                    params["timestamp"].as_f64().unwrap_or_default() - self.started[index];
+                let time = (elapsed * 1000.0).max(0.0);
+                self.entries[index]["time"] = Value::from(time);
+                self.entries[index]["timings"]["wait"] = Value::from(time);
+                let (id, command) = self.command(
+                    "Network.getResponseBody",
+                    json!({ "requestId": request_id }),
+                );
+                self.body_requests.insert(id, index);
+                return Some(command);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn into_har(self) -> Value {
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": "extract_har", "version": env!("CARGO_PKG_VERSION") },
+                "pages": [],
+                "entries": self.entries,
+            }
+        })
+    }
+}
+
+fn find_target(args: &CaptureArgs) -> String {
+    let (host, port) = match &args.cdp {
+        Some(cdp) if cdp.path().starts_with("/devtools/") => return cdp.to_string(),
+        Some(cdp) => (
+            cdp.host_str().unwrap_or("localhost").to_string(),
+            cdp.port_or_known_default().unwrap_or(9222),
+        ),
+        None => (args.host.clone(), args.port),
+    };
+    let targets = http_get_json(&host, port, "/json/list")
+        .unwrap_or_else(|err| pexit!(CannotReachDevTools, host, port, err));
+    targets
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|x| x["type"] == "page")
+        .find(|x| {
+            args.target.as_ref().is_none_or(|target| {
+                [&x["url"], &x["title"]]
+                    .iter()
+                    .any(|field| field.as_str().unwrap_or_default().contains(target.as_str()))
+            })
+        })
+        .and_then(|x| x["webSocketDebuggerUrl"].as_str())
+        .map(|x| x.to_string())
+        .unwrap_or_else(|| pexit!(NoDevToolsTarget))
+}
+
+fn record(
+    socket: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+    deadline: Option<Instant>,
+) -> Recorder {
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = stop.clone();
+    let _ = ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst));
+    if let MaybeTlsStream::Plain(stream) = socket.get_mut() {
+        let _ = stream.set_read_timeout(Some(Duration::from_millis(200)));
+    }
+    let mut recorder = Recorder::default();
+    let (_, enable) = recorder.command("Network.enable", json!({}));
+    socket
+        .send(Message::Text(enable.into()))
+        .unwrap_or_else(|err| pexit!(DevToolsConnectionFailed, err));
+    eprintln!("{}", tr!(CaptureStarted));
+    let mut stopped_at = None::<Instant>;
+    loop {
+        let stopping = stop.load(Ordering::SeqCst) || deadline.is_some_and(|x| Instant::now() >= x);
+        if stopping && stopped_at.is_none() {
+            stopped_at = Some(Instant::now());
+        }
+        // After stopping, give outstanding body requests a moment to come back.
+        if let Some(stopped_at) = stopped_at {
+            if recorder.body_requests.is_empty() || stopped_at.elapsed() > Duration::from_secs(3) {
+                break;
+            }
+        }
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let Ok(message) = serde_json::from_str::<Value>(text.as_str()) else {
+                    continue;
+                };
+                if let Some(command) = recorder.handle(&message) {
+                    if socket.send(Message::Text(command.into())).is_err() {
+                        break;
+                    }
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => break,
+        }
+    }
+    recorder
+}
+
+pub fn run(args: CaptureArgs) {
+    let target = find_target(&args);
+    let (mut socket, _) =
+        tungstenite::connect(&target).unwrap_or_else(|err| pexit!(DevToolsConnectionFailed, err));
+    let deadline = args
+        .duration
+        .map(|x| Instant::now() + Duration::from_secs(x));
+    let recorder = record(&mut socket, deadline);
+    let count = recorder.entries.len();
+    let har = serde_json::to_vec_pretty(&recorder.into_har()).unwrap();
+    let to_folder = args.output.is_dir() || args.output.to_string_lossy().ends_with(['/', '\\']);
+    // Extracting needs a HAR on disk, so a folder output goes through a temporary one.
+    let har_file = if to_folder {
+        std::env::temp_dir().join(format!("extract_har-capture-{}.har", std::process::id()))
+    } else {
+        args.output.clone()
+    };
+    fs::write(&har_file, har).unwrap_or_else(|_| {
+        pexit!(CannotWriteFile, har_file.to_string_lossy());
+    });
+    let output_dir = if to_folder {
+        Some(args.output.to_string_lossy().into_owned())
+    } else {
+        eprintln!("{}", tr!(CaptureWritten, count, har_file.to_string_lossy()));
+        args.extract_to
+    };
+    let status = output_dir.map_or(Ok(0), |output_dir| {
+        let input_har = har_file.to_string_lossy().into_owned();
+        extract::run(ExtractArgs::for_input(input_har, Some(output_dir)))
+    });
+    if to_folder {
+        let _ = fs::remove_file(&har_file);
+    }
+    match status {
+        Ok(0) => {}
+        Ok(status) => std::process::exit(status),
+        Err(err) => err.exit(),
+    }
+}
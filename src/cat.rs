@@ -0,0 +1,54 @@
+use std::io::{self, Write};
+
+use clap::Args;
+use extract_har::decompress;
+
+use crate::input;
+
+#[derive(Args)]
+pub struct CatArgs {
+    input_har: String,
+    /// Entry with this URL, or else the first whose URL contains it
+    #[arg(long, conflicts_with = "index", required_unless_present = "index")]
+    url: Option<String>,
+    /// Entry at this position, as printed by `list`
+    #[arg(long)]
+    index: Option<usize>,
+    /// Write the body exactly as stored, even if a `Content-Encoding` is still applied
+    #[arg(long)]
+    no_decompress: bool,
+}
+
+pub fn run(args: CatArgs) {
+    let session = input::open_session(&args.input_har);
+    let entries = session.entries();
+    let entry = match (&args.index, &args.url) {
+        (Some(index), _) => session.har().log.position_of(*index).map(|x| &entries[x]),
+        (None, Some(url)) => entries
+            .iter()
+            .find(|x| x.request.url.as_str() == url)
+            .or_else(|| {
+                entries
+                    .iter()
+                    .find(|x| x.request.url.as_str().contains(url))
+            }),
+        (None, None) => None,
+    };
+    let Some(entry) = entry else {
+        pexit!(EntryNotFound);
+    };
+    let content = &entry.response.content;
+    let mut body = content
+        .decoded()
+        .unwrap_or_else(|| pexit!(CannotDecodeBody, entry.request.url));
+    if !args.no_decompress {
+        if let Some(decompressed) =
+            decompress::decompress(entry.response.header("content-encoding"), &body)
+        {
+            body = decompressed;
+        }
+    }
+    let mut stdout = io::stdout().lock();
+    // A closed pipe (`| head`) is not an error worth reporting.
+    let _ = stdout.write_all(&body).and_then(|_| stdout.flush());
+}
@@ -0,0 +1,60 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use clap::{Args, Command};
+use clap_complete::Shell;
+
+#[derive(Args)]
+pub struct CompletionsArgs {
+    #[arg(value_enum)]
+    shell: Shell,
+    /// Write the script into this folder, named the way the shell looks for it,
+    /// instead of printing it
+    #[arg(long, short)]
+    output_dir: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct ManArgs {
+    /// Write a page for the program and one for each subcommand into this folder
+    /// instead of printing the program's page
+    #[arg(long, short)]
+    output_dir: Option<PathBuf>,
+}
+
+fn create_dir(dir: &PathBuf) {
+    fs::create_dir_all(dir).unwrap_or_else(|_| pexit!(CannotCreateDirs, dir.to_string_lossy()));
+}
+
+pub fn run_completions(args: CompletionsArgs, mut command: Command) {
+    let name = command.get_name().to_string();
+    let Some(dir) = args.output_dir else {
+        // Generating straight to stdout panics when the reader goes away early.
+        let mut script = Vec::new();
+        clap_complete::generate(args.shell, &mut command, name, &mut script);
+        io::stdout()
+            .write_all(&script)
+            .unwrap_or_else(|err| pexit!(CannotWriteFile, err));
+        return;
+    };
+    create_dir(&dir);
+    let path = clap_complete::generate_to(args.shell, &mut command, name, &dir)
+        .unwrap_or_else(|err| pexit!(CannotWriteFile, format!("{}: {err}", dir.to_string_lossy())));
+    eprintln!("{}", tr!(GeneratedFiles, path.to_string_lossy()));
+}
+
+pub fn run_man(args: ManArgs, command: Command) {
+    let Some(dir) = args.output_dir else {
+        clap_mangen::Man::new(command)
+            .render(&mut io::stdout())
+            .unwrap_or_else(|err| pexit!(CannotWriteFile, err));
+        return;
+    };
+    create_dir(&dir);
+    clap_mangen::generate_to(command, &dir)
+        .unwrap_or_else(|err| pexit!(CannotWriteFile, format!("{}: {err}", dir.to_string_lossy())));
+    eprintln!("{}", tr!(GeneratedFiles, dir.to_string_lossy()));
+}
@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+
+use chrono::DateTime;
+
+use crate::har::{HarCookie, HarLogEntry};
+
+/// Renders every cookie seen in the capture as a Netscape cookie jar, as read by
+/// `curl -b` and `wget --load-cookies`. Later occurrences of a cookie win.
+pub fn netscape_jar(entries: &[HarLogEntry]) -> String {
+    let mut jar = BTreeMap::<(String, String, String), &HarCookie>::new();
+    for entry in entries {
+        let host = entry.request.url.host_str().unwrap_or_default();
+        let cookies = entry.request.cookies.iter().chain(&entry.response.cookies);
+        for cookie in cookies {
+            let domain = cookie
+                .domain
+                .clone()
+                .filter(|x| !x.is_empty())
+                .unwrap_or_else(|| host.to_string());
+            let path = cookie.path.clone().unwrap_or_else(|| "/".to_string());
+            jar.insert((domain, path, cookie.name.clone()), cookie);
+        }
+    }
+    let mut result = String::from("# Netscape HTTP Cookie File\n");
+    for ((domain, path, name), cookie) in jar {
+        let expires = cookie
+            .expires
+            .as_deref()
+            .and_then(|x| DateTime::parse_from_rfc3339(x).ok())
+            .map(|x| x.timestamp().max(0))
+            .unwrap_or(0);
+        result.push_str(&format!(
+            "{}{domain}\t{}\t{path}\t{}\t{expires}\t{name}\t{}\n",
+            if cookie.http_only == Some(true) {
+                "#HttpOnly_"
+            } else {
+                ""
+            },
+            if domain.starts_with('.') {
+                "TRUE"
+            } else {
+                "FALSE"
+            },
+            if cookie.secure == Some(true) {
+                "TRUE"
+            } else {
+                "FALSE"
+            },
+            cookie.value
+        ));
+    }
+    result
+}
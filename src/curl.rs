@@ -0,0 +1,99 @@
+use clap::Args;
+use extract_har::har::HarLogEntry;
+
+use crate::input;
+
+#[derive(Args)]
+pub struct ToCurlArgs {
+    input_har: String,
+    /// Only emit requests whose URL contains this text (repeatable, any may match)
+    #[arg(long)]
+    filter: Vec<String>,
+}
+
+/// Quotes a value for POSIX shells.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn curl_command(entry: &HarLogEntry) -> String {
+    let request = &entry.request;
+    let mut args = vec!["curl".to_string()];
+    if !request.method.is_empty() && request.method != "GET" {
+        args.push(format!("-X {}", shell_quote(&request.method)));
+    }
+    args.push(shell_quote(request.url.as_str()));
+    let mut has_cookie_header = false;
+    for header in &request.headers {
+        let name = header.name.to_lowercase();
+        // HTTP/2 pseudo-headers and the length are recomputed by curl itself.
+        if name.starts_with(':') || name == "content-length" {
+            continue;
+        }
+        if name == "accept-encoding" {
+            args.push("--compressed".to_string());
+        }
+        has_cookie_header |= name == "cookie";
+        args.push(format!(
+            "-H {}",
+            shell_quote(&format!("{}: {}", header.name, header.value))
+        ));
+    }
+    if !has_cookie_header && !request.cookies.is_empty() {
+        let cookies = request
+            .cookies
+            .iter()
+            .map(|x| format!("{}={}", x.name, x.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        args.push(format!("-b {}", shell_quote(&cookies)));
+    }
+    if let Some(post_data) = &request.post_data {
+        if !post_data.text.is_empty() {
+            args.push(format!("--data-raw {}", shell_quote(&post_data.text)));
+        }
+    }
+    args.join(" \\\n  ")
+}
+
+pub fn run(args: ToCurlArgs) {
+    let session = input::open_session(&args.input_har);
+    println!("#!/bin/sh");
+    for entry in session.entries() {
+        let url = entry.request.url.as_str();
+        if !args.filter.is_empty() && !args.filter.iter().any(|x| url.contains(x.as_str())) {
+            continue;
+        }
+        println!();
+        println!("{}", curl_command(entry));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(method: &str) -> HarLogEntry {
+        serde_json::from_value(serde_json::json!({
+            "request": { "method": method, "url": "https://a.com/" },
+            "response": { "status": 200, "content": { "mimeType": "text/html" } },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn quotes_single_quotes() {
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn leaves_get_implicit() {
+        assert_eq!(curl_command(&entry("GET")), "curl \\\n  'https://a.com/'");
+    }
+
+    #[test]
+    fn quotes_the_method() {
+        let command = curl_command(&entry("POST; touch PWNED;"));
+        assert!(command.starts_with("curl \\\n  -X 'POST; touch PWNED;' \\\n"));
+    }
+}
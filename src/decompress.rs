@@ -0,0 +1,143 @@
+use std::{
+    io::Read,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Default of [`set_limit`]: far more than real bodies decompress to, but little enough
+/// that a compression bomb in an untrusted capture cannot use up the memory of every
+/// decoding thread.
+pub const DEFAULT_LIMIT: u64 = 1 << 30;
+
+static LIMIT: AtomicU64 = AtomicU64::new(DEFAULT_LIMIT);
+
+/// Sets how many bytes a body may decompress to; larger bodies are left compressed.
+pub fn set_limit(bytes: u64) {
+    LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+pub fn limit() -> u64 {
+    LIMIT.load(Ordering::Relaxed)
+}
+
+/// A body that decompresses to more than the [`limit`].
+#[derive(Debug, PartialEq)]
+pub struct TooLarge;
+
+fn read_all(reader: impl Read, limit: u64) -> Result<Option<Vec<u8>>, TooLarge> {
+    let mut result = Vec::new();
+    // One byte more than allowed tells a body at the limit from a larger one.
+    if reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut result)
+        .is_err()
+    {
+        return Ok(None);
+    }
+    if result.len() as u64 > limit {
+        return Err(TooLarge);
+    }
+    Ok(Some(result))
+}
+
+/// Undoes a `Content-Encoding` that was left applied to a stored body.
+///
+/// Browsers usually store the decoded body while keeping the header, so `None` is
+/// returned whenever the bytes do not actually decode. Gzip is also recognized by its
+/// magic bytes when the header is missing. Bodies that would decompress to more than
+/// the [`limit`] give `None` too; [`try_decompress`] tells them apart.
+pub fn decompress(content_encoding: Option<&str>, bytes: &[u8]) -> Option<Vec<u8>> {
+    try_decompress(content_encoding, bytes).ok().flatten()
+}
+
+/// Like [`decompress`], but with an error for bodies over the [`limit`].
+pub fn try_decompress(
+    content_encoding: Option<&str>,
+    bytes: &[u8],
+) -> Result<Option<Vec<u8>>, TooLarge> {
+    decompress_within(content_encoding, bytes, limit())
+}
+
+fn decompress_within(
+    content_encoding: Option<&str>,
+    bytes: &[u8],
+    limit: u64,
+) -> Result<Option<Vec<u8>>, TooLarge> {
+    let encoding = content_encoding.unwrap_or_default().trim().to_lowercase();
+    match encoding.as_str() {
+        "gzip" | "x-gzip" | "" if bytes.starts_with(GZIP_MAGIC) => {
+            read_all(GzDecoder::new(bytes), limit)
+        }
+        // `deflate` is meant to be zlib-wrapped, but raw deflate is common in the wild.
+        "deflate" => match read_all(ZlibDecoder::new(bytes), limit)? {
+            Some(result) => Ok(Some(result)),
+            None => read_all(DeflateDecoder::new(bytes), limit),
+        },
+        "br" => read_all(brotli::Decompressor::new(bytes, 4096), limit),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{
+        write::{DeflateEncoder, GzEncoder, ZlibEncoder},
+        Compression,
+    };
+
+    use super::*;
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn decompresses_gzip() {
+        let body = gzip(b"hello");
+        assert_eq!(decompress(Some("gzip"), &body).unwrap(), b"hello");
+        // Recognized by its magic bytes without the header.
+        assert_eq!(decompress(None, &body).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn decompresses_zlib_and_raw_deflate() {
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(b"zlib").unwrap();
+        let zlib = zlib.finish().unwrap();
+        assert_eq!(decompress(Some("deflate"), &zlib).unwrap(), b"zlib");
+        let mut raw = DeflateEncoder::new(Vec::new(), Compression::default());
+        raw.write_all(b"raw").unwrap();
+        let raw = raw.finish().unwrap();
+        assert_eq!(decompress(Some("Deflate "), &raw).unwrap(), b"raw");
+    }
+
+    #[test]
+    fn leaves_decoded_bodies_alone() {
+        assert_eq!(decompress(Some("gzip"), b"plain"), None);
+        assert_eq!(decompress(Some("br"), b"plain"), None);
+        assert_eq!(decompress(Some("zstd"), &gzip(b"x")), None);
+    }
+
+    #[test]
+    fn allows_bodies_at_the_limit() {
+        let body = gzip(&[0; 100]);
+        assert_eq!(
+            decompress_within(Some("gzip"), &body, 100),
+            Ok(Some(vec![0; 100]))
+        );
+    }
+
+    #[test]
+    fn refuses_bodies_over_the_limit() {
+        let body = gzip(&[0; 101]);
+        assert_eq!(decompress_within(Some("gzip"), &body, 100), Err(TooLarge));
+        assert_eq!(decompress_within(None, &body, 100), Err(TooLarge));
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use clap::Args;
+use extract_har::{
+    har::{HarHeader, HarLogEntry},
+    manifest::sha256_hex,
+};
+use serde::Serialize;
+
+use crate::input;
+
+#[derive(Args)]
+pub struct DiffArgs {
+    old_har: String,
+    new_har: String,
+    /// Compare only statuses and headers, never looking at bodies
+    #[arg(long)]
+    headers_only: bool,
+    /// Print the differences as a JSON document
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Change {
+    Status {
+        old: i32,
+        new: i32,
+    },
+    Header {
+        side: &'static str,
+        name: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    Body {
+        old_size: u64,
+        new_size: u64,
+        old_sha256: String,
+        new_sha256: String,
+    },
+}
+
+#[derive(PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Presence {
+    Removed,
+    Added,
+    Changed,
+}
+
+#[derive(Serialize)]
+struct EntryDiff<'a> {
+    presence: Presence,
+    method: &'a str,
+    url: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    changes: Vec<Change>,
+}
+
+#[derive(Serialize)]
+struct Summary {
+    removed: usize,
+    added: usize,
+    changed: usize,
+    /// Change of the total decoded response size, new minus old; left out with
+    /// `--headers-only`, which ignores bodies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size_delta: Option<i64>,
+}
+
+/// Headers grouped by lowercased name, repeated headers joined in capture order.
+fn header_map(headers: &[HarHeader]) -> BTreeMap<String, String> {
+    let mut map = BTreeMap::<String, String>::new();
+    for header in headers {
+        map.entry(header.name.to_lowercase())
+            .and_modify(|value| {
+                value.push_str(", ");
+                value.push_str(&header.value);
+            })
+            .or_insert_with(|| header.value.clone());
+    }
+    map
+}
+
+fn diff_headers(
+    side: &'static str,
+    old: &[HarHeader],
+    new: &[HarHeader],
+    changes: &mut Vec<Change>,
+) {
+    let old = header_map(old);
+    let mut new = header_map(new);
+    for (name, old_value) in old {
+        let new_value = new.remove(&name);
+        if new_value.as_ref() != Some(&old_value) {
+            changes.push(Change::Header {
+                side,
+                name,
+                old: Some(old_value),
+                new: new_value,
+            });
+        }
+    }
+    for (name, new_value) in new {
+        changes.push(Change::Header {
+            side,
+            name,
+            old: None,
+            new: Some(new_value),
+        });
+    }
+}
+
+fn diff_entries(old: &HarLogEntry, new: &HarLogEntry, headers_only: bool) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if old.response.status != new.response.status {
+        changes.push(Change::Status {
+            old: old.response.status,
+            new: new.response.status,
+        });
+    }
+    diff_headers(
+        "request",
+        &old.request.headers,
+        &new.request.headers,
+        &mut changes,
+    );
+    diff_headers(
+        "response",
+        &old.response.headers,
+        &new.response.headers,
+        &mut changes,
+    );
+    if !headers_only {
+        let old_body = old.response.content.decoded().unwrap_or_default();
+        let new_body = new.response.content.decoded().unwrap_or_default();
+        if old_body != new_body {
+            changes.push(Change::Body {
+                old_size: old_body.len() as u64,
+                new_size: new_body.len() as u64,
+                old_sha256: sha256_hex(&old_body),
+                new_sha256: sha256_hex(&new_body),
+            });
+        }
+    }
+    changes
+}
+
+fn print_change(change: &Change) {
+    match change {
+        Change::Status { old, new } => println!("  ~ status: {old} -> {new}"),
+        Change::Header {
+            side,
+            name,
+            old: Some(old),
+            new: Some(new),
+        } => println!("  ~ {side} {name}: {old} -> {new}"),
+        Change::Header {
+            side,
+            name,
+            old: Some(old),
+            new: None,
+        } => println!("  - {side} {name}: {old}"),
+        Change::Header {
+            side, name, new, ..
+        } => println!("  + {side} {name}: {}", new.as_deref().unwrap_or_default()),
+        Change::Body {
+            old_size,
+            new_size,
+            old_sha256,
+            new_sha256,
+        } => println!(
+            "  ~ body: {old_size} -> {new_size} bytes ({:+}), sha256 {} -> {}",
+            *new_size as i64 - *old_size as i64,
+            &old_sha256[..12],
+            &new_sha256[..12]
+        ),
+    }
+}
+
+fn print_entry(entry: &EntryDiff) {
+    let sign = match entry.presence {
+        Presence::Removed => '-',
+        Presence::Added => '+',
+        Presence::Changed => '~',
+    };
+    println!("{sign} {} {}", entry.method, entry.url);
+    entry.changes.iter().for_each(print_change);
+}
+
+fn entry_key(entry: &HarLogEntry) -> String {
+    format!("{} {}", entry.request.method, entry.request.url)
+}
+
+fn entry_diff(presence: Presence, entry: &HarLogEntry, changes: Vec<Change>) -> EntryDiff<'_> {
+    EntryDiff {
+        presence,
+        method: &entry.request.method,
+        url: entry.request.url.as_str(),
+        changes,
+    }
+}
+
+pub fn run(args: DiffArgs) {
+    let old = input::open_session(&args.old_har);
+    let new = input::open_session(&args.new_har);
+
+    // Repeated requests to the same URL are paired up in the order they were made.
+    let mut new_by_key = HashMap::<String, VecDeque<&HarLogEntry>>::new();
+    for entry in new.entries() {
+        new_by_key
+            .entry(entry_key(entry))
+            .or_default()
+            .push_back(entry);
+    }
+    let mut diffs = Vec::new();
+    for old_entry in old.entries() {
+        match new_by_key
+            .get_mut(&entry_key(old_entry))
+            .and_then(|x| x.pop_front())
+        {
+            Some(new_entry) => {
+                let changes = diff_entries(old_entry, new_entry, args.headers_only);
+                if !changes.is_empty() {
+                    diffs.push(entry_diff(Presence::Changed, old_entry, changes));
+                }
+            }
+            None => diffs.push(entry_diff(Presence::Removed, old_entry, Vec::new())),
+        }
+    }
+    for new_entry in new.entries() {
+        if let Some(remaining) = new_by_key.get_mut(&entry_key(new_entry)) {
+            if remaining
+                .front()
+                .is_some_and(|x| std::ptr::eq(*x, new_entry))
+            {
+                remaining.pop_front();
+                diffs.push(entry_diff(Presence::Added, new_entry, Vec::new()));
+            }
+        }
+    }
+    let total_size = |entries: &[HarLogEntry]| {
+        entries
+            .iter()
+            .map(|x| x.response.content.body_size())
+            .sum::<u64>() as i64
+    };
+    let count = |presence: Presence| diffs.iter().filter(|x| x.presence == presence).count();
+    let summary = Summary {
+        removed: count(Presence::Removed),
+        added: count(Presence::Added),
+        changed: count(Presence::Changed),
+        size_delta: (!args.headers_only)
+            .then(|| total_size(new.entries()) - total_size(old.entries())),
+    };
+    if args.json {
+        let document = serde_json::json!({ "entries": diffs, "summary": summary });
+        println!("{}", serde_json::to_string_pretty(&document).unwrap());
+        return;
+    }
+    diffs.iter().for_each(print_entry);
+    let (removed, added, changed) = (summary.removed, summary.added, summary.changed);
+    match summary.size_delta {
+        Some(size_delta) => println!(
+            "{}",
+            tr!(
+                DiffSummary,
+                removed,
+                added,
+                changed,
+                format!("{size_delta:+}")
+            )
+        ),
+        None => println!("{}", tr!(DiffSummaryHeadersOnly, removed, added, changed)),
+    }
+}
@@ -0,0 +1,8 @@
+//! Exit statuses scripts can rely on; anything else that goes wrong exits with 1.
+
+/// The capture could not be read as a HAR or JSON document.
+pub const PARSE_ERROR: i32 = 2;
+/// Some files were written, but others could not be.
+pub const PARTIAL_FAILURE: i32 = 3;
+/// No entry passed the filters, so nothing was written.
+pub const NOTHING_MATCHED: i32 = 4;
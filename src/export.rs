@@ -0,0 +1,329 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, ValueEnum};
+use extract_har::har::{Har, HarLogEntry};
+use serde_json::{json, Map, Value};
+
+use crate::{input, mhtml::Page};
+
+#[derive(Args)]
+pub struct ExportArgs {
+    input_har: String,
+    #[arg(long, value_enum, default_value_t = ExportFormat::Postman)]
+    format: ExportFormat,
+    /// Write to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Page of `log.pages` to save with `mhtml` and `html`; defaults to the first one
+    #[arg(long)]
+    page: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// Postman collection v2.1
+    Postman,
+    /// OpenAPI 3 skeleton inferred from the observed traffic
+    Openapi,
+    /// A page and its subresources as an MHTML archive
+    Mhtml,
+    /// A page as a single HTML file with its subresources inlined as `data:` URIs
+    Html,
+    /// SQLite database with tables for entries, headers, cookies and bodies
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+/// Whether a path segment looks like an identifier rather than a fixed route part.
+fn is_identifier(segment: &str) -> bool {
+    let is_hex = segment.len() >= 16 && segment.chars().all(|x| x.is_ascii_hexdigit());
+    let is_uuid = segment.len() == 36 && segment.chars().all(|x| x.is_ascii_hexdigit() || x == '-');
+    let is_number = !segment.is_empty() && segment.chars().all(|x| x.is_ascii_digit());
+    is_hex || is_uuid || is_number
+}
+
+/// A request path with identifier-like segments replaced by named parameters.
+struct Route {
+    /// `(name, observed value)` when the segment is a parameter.
+    segments: Vec<(String, Option<String>)>,
+}
+
+impl Route {
+    fn of(entry: &HarLogEntry) -> Route {
+        let mut count = 0;
+        let segments = entry
+            .request
+            .url
+            .path_segments()
+            .map(|x| x.collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|segment| {
+                if is_identifier(segment) {
+                    count += 1;
+                    let name = if count == 1 {
+                        "id".to_string()
+                    } else {
+                        format!("id{count}")
+                    };
+                    (name, Some(segment.to_string()))
+                } else {
+                    (segment.to_string(), None)
+                }
+            })
+            .collect();
+        Route { segments }
+    }
+
+    fn pattern(&self, open: &str, close: &str) -> String {
+        let mut result = String::new();
+        for (name, value) in &self.segments {
+            result.push('/');
+            if value.is_some() {
+                result.push_str(&format!("{open}{name}{close}"));
+            } else {
+                result.push_str(name);
+            }
+        }
+        result
+    }
+}
+
+/// One representative request per host, method and route, in capture order.
+fn group_requests(har: &Har) -> BTreeMap<String, Vec<(&HarLogEntry, Route)>> {
+    let mut groups = BTreeMap::<String, Vec<(&HarLogEntry, Route)>>::new();
+    for entry in &har.log.entries {
+        let host = entry.request.url.host_str().unwrap_or("-").to_string();
+        let route = Route::of(entry);
+        let requests = groups.entry(host).or_default();
+        let pattern = route.pattern(":", "");
+        if !requests
+            .iter()
+            .any(|(x, r)| x.request.method == entry.request.method && r.pattern(":", "") == pattern)
+        {
+            requests.push((entry, route));
+        }
+    }
+    groups
+}
+
+fn postman_item(entry: &HarLogEntry, route: &Route) -> Value {
+    let request = &entry.request;
+    let url = &request.url;
+    let path = route.pattern(":", "");
+    let query = url
+        .query_pairs()
+        .map(|(key, value)| json!({ "key": key, "value": value }))
+        .collect::<Vec<_>>();
+    let variables = route
+        .segments
+        .iter()
+        .filter_map(|(name, value)| value.as_ref().map(|x| json!({ "key": name, "value": x })))
+        .collect::<Vec<_>>();
+    let headers = request
+        .headers
+        .iter()
+        .filter(|x| !x.name.starts_with(':') && !x.name.eq_ignore_ascii_case("content-length"))
+        .map(|x| json!({ "key": x.name, "value": x.value }))
+        .collect::<Vec<_>>();
+    let mut raw = format!(
+        "{}://{}{path}",
+        url.scheme(),
+        url.host_str().unwrap_or_default()
+    );
+    if let Some(query) = url.query() {
+        raw.push('?');
+        raw.push_str(query);
+    }
+    let mut postman_request = json!({
+        "method": request.method,
+        "header": headers,
+        "url": {
+            "raw": raw,
+            "protocol": url.scheme(),
+            "host": url.host_str().unwrap_or_default().split('.').collect::<Vec<_>>(),
+            "path": path.trim_start_matches('/').split('/').collect::<Vec<_>>(),
+            "query": query,
+            "variable": variables,
+        },
+    });
+    if let Some(post_data) = &request.post_data {
+        postman_request["body"] = json!({ "mode": "raw", "raw": post_data.text });
+    }
+    json!({
+        "name": format!("{} {path}", request.method),
+        "request": postman_request,
+    })
+}
+
+fn postman_collection(har: &Har, name: &str) -> Value {
+    let folders = group_requests(har)
+        .into_iter()
+        .map(|(host, requests)| {
+            let items = requests
+                .iter()
+                .map(|(entry, route)| postman_item(entry, route))
+                .collect::<Vec<_>>();
+            json!({ "name": host, "item": items })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "info": {
+            "name": name,
+            "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json",
+        },
+        "item": folders,
+    })
+}
+
+/// A JSON schema describing the shape of an observed value.
+fn infer_schema(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({ "nullable": true }),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(x) if x.is_i64() || x.is_u64() => json!({ "type": "integer" }),
+        Value::Number(_) => json!({ "type": "number" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(items) => match items.first() {
+            Some(first) => json!({ "type": "array", "items": infer_schema(first) }),
+            None => json!({ "type": "array", "items": {} }),
+        },
+        Value::Object(fields) => {
+            let properties = fields
+                .iter()
+                .map(|(key, value)| (key.clone(), infer_schema(value)))
+                .collect::<Map<_, _>>();
+            json!({ "type": "object", "properties": properties })
+        }
+    }
+}
+
+fn media_content(mime_type: &str, body: Option<&str>) -> Value {
+    let essence = mime_type.split(';').next().unwrap_or("").trim();
+    let schema = body
+        .filter(|_| essence.ends_with("json"))
+        .and_then(|x| serde_json::from_str::<Value>(x).ok())
+        .map(|x| infer_schema(&x))
+        .unwrap_or_else(|| json!({}));
+    json!({ essence: { "schema": schema } })
+}
+
+fn openapi_operation(entry: &HarLogEntry, route: &Route) -> Value {
+    let mut parameters = route
+        .segments
+        .iter()
+        .filter(|(_, value)| value.is_some())
+        .map(|(name, _)| {
+            json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } })
+        })
+        .collect::<Vec<_>>();
+    for (key, _) in entry.request.url.query_pairs() {
+        parameters.push(json!({ "name": key, "in": "query", "schema": { "type": "string" } }));
+    }
+    let response = &entry.response;
+    let mut operation = json!({
+        "summary": format!("{} {}", entry.request.method, route.pattern("{", "}")),
+        "parameters": parameters,
+        "responses": {
+            response.status.to_string(): {
+                "description": "Observed response",
+                "content": media_content(
+                    &response.content.mime_type,
+                    response.content.decoded().and_then(|x| String::from_utf8(x).ok()).as_deref(),
+                ),
+            },
+        },
+    });
+    if let Some(post_data) = &entry.request.post_data {
+        operation["requestBody"] = json!({
+            "content": media_content(&post_data.mime_type, Some(&post_data.text)),
+        });
+    }
+    operation
+}
+
+fn openapi_document(har: &Har, name: &str) -> Value {
+    let mut servers = Vec::new();
+    let mut paths = Map::new();
+    for (host, requests) in group_requests(har) {
+        if let Some((entry, _)) = requests.first() {
+            servers.push(json!({ "url": format!("{}://{host}", entry.request.url.scheme()) }));
+        }
+        for (entry, route) in &requests {
+            let path = paths
+                .entry(route.pattern("{", "}"))
+                .or_insert_with(|| json!({}));
+            let method = entry.request.method.to_lowercase();
+            if path.get(&method).is_none() {
+                path[method] = openapi_operation(entry, route);
+            }
+        }
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": name, "version": "1.0.0" },
+        "servers": servers,
+        "paths": paths,
+    })
+}
+
+pub fn write_bytes(output: Option<&Path>, bytes: &[u8]) {
+    match output {
+        Some(output) => fs::write(output, bytes).unwrap_or_else(|_| {
+            pexit!(CannotWriteFile, output.to_string_lossy());
+        }),
+        None => {
+            let mut stdout = io::stdout().lock();
+            let _ = stdout.write_all(bytes).and_then(|_| stdout.flush());
+        }
+    }
+}
+
+pub fn run(args: ExportArgs) {
+    // Exports write files, so they get the capture itself rather than a read-only
+    // session.
+    let har = input::load(&input::resolve_input(&args.input_har));
+    let name = input::resolve_input(&args.input_har)
+        .file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let document = match args.format {
+        ExportFormat::Postman => postman_collection(&har, &name),
+        ExportFormat::Openapi => openapi_document(&har, &name),
+        ExportFormat::Mhtml | ExportFormat::Html => {
+            let Some(page) = Page::select(&har, args.page.as_deref()) else {
+                pexit!(PageNotFound);
+            };
+            let bytes = match args.format {
+                ExportFormat::Mhtml => page.mhtml(),
+                _ => page.single_file(),
+            };
+            write_bytes(args.output.as_deref(), &bytes);
+            return;
+        }
+        #[cfg(feature = "sqlite")]
+        ExportFormat::Sqlite => {
+            let Some(output) = args.output else {
+                pexit!(OutputFileRequired);
+            };
+            // Replace an earlier export instead of failing on its existing tables.
+            let _ = fs::remove_file(&output);
+            if let Err(err) = crate::sqlite::write(&har, &output) {
+                pexit!(CannotWriteDatabase, output.to_string_lossy(), err);
+            }
+            return;
+        }
+    };
+    let json = serde_json::to_string_pretty(&document).unwrap();
+    match args.output {
+        Some(output) => fs::write(&output, json).unwrap_or_else(|_| {
+            pexit!(CannotWriteFile, output.to_string_lossy());
+        }),
+        None => println!("{json}"),
+    }
+}
@@ -0,0 +1,3315 @@
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fs,
+    io::{self, IsTerminal},
+    ops::RangeInclusive,
+    panic,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TryRecvError},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use chrono::{DateTime, FixedOffset};
+use clap::{Args, ValueEnum};
+use encoding_rs::Encoding;
+use extract_har::{
+    beautify, cookies, decompress, extraction,
+    har::{
+        self, Har, HarCookie, HarHeader, HarLogEntry, HarPage, HarPostData, HarTimings,
+        SkippedEntry,
+    },
+    hooks::{EntryHandler, ExecHandler},
+    import,
+    manifest::{self, Manifest, ManifestFile},
+    metadata, multipart,
+    playlist::{self, DashSegments},
+    progress::{Event, Observer, StreamObserver},
+    sink::{self, DirSink, OutsideRoot, Sink},
+    sourcemap,
+    winpath::{self, CaseFolder},
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use url::Url;
+
+use crate::{
+    bundle::{self, Failure},
+    exit_code,
+    filter::{self, TimeWindow},
+    input::{self, LoadError},
+    redact::wildcard_match,
+    report::Report,
+    stats::human_size,
+};
+
+#[derive(Args, Clone, Debug, Default)]
+pub struct ExtractArgs {
+    #[cfg_attr(not(feature = "watch"), arg(required = true))]
+    #[cfg_attr(feature = "watch", arg(required_unless_present = "watch"))]
+    input_har: Option<String>,
+    output_dir: Option<String>,
+    #[arg(long, default_value = None)]
+    output_domain: Option<String>,
+    #[arg(long, default_value = None)]
+    output_path: Option<String>,
+    #[arg(long, default_value_t = 0)]
+    output_path_depth: i32,
+    /// Mirror at most the first N folders of each URL path, with or without
+    /// `--output-path`; 0 puts files directly in their domain folder or the output folder
+    #[arg(long, conflicts_with_all = ["output_path_depth", "flatten"])]
+    max_depth: Option<usize>,
+    /// Put every file directly in its domain folder, adding `~2`, `~3`... to names that
+    /// files of different URLs share
+    #[arg(long, conflicts_with = "output_path")]
+    flatten: bool,
+    /// Upload the extracted files to `s3://bucket/prefix`, or to a bucket of an
+    /// S3-compatible service given as `https://host/bucket/prefix`, with credentials
+    /// from the `AWS_*` variables. The output folder, when given, keeps a local copy
+    #[cfg(feature = "s3")]
+    #[arg(long)]
+    output_url: Option<String>,
+    /// Also extract request bodies (`postData`), splitting multipart bodies into parts
+    #[arg(long)]
+    requests: bool,
+    /// Dump WebSocket frames to `<name>.ws.ndjson`, binary frames also as raw files
+    #[arg(long)]
+    websockets: bool,
+    /// Write the query, variables and response of each GraphQL operation sent in a
+    /// request body to `graphql/<operation>.*`
+    #[arg(long)]
+    graphql: bool,
+    /// Stream newline-delimited JSON progress events to a Unix socket or named pipe
+    #[arg(long)]
+    progress_socket: Option<PathBuf>,
+    /// Write a self-contained HTML page with a summary, the files extracted (with
+    /// thumbnails of images) and the entries skipped or failed, for sharing the result
+    #[arg(long, value_name = "FILE")]
+    report: Option<PathBuf>,
+    /// Order in which entries are processed
+    #[arg(long, alias = "sort", value_enum, default_value_t = Order::Har)]
+    order: Order,
+    /// Extract one entry per URL, which with polling or repeated navigation comes up
+    /// dozens of times, preferring responses with a body
+    #[arg(long, value_enum)]
+    unique_urls: Option<UniqueUrls>,
+    /// Only extract the entries at these positions in the HAR, as numbered by `list`,
+    /// e.g. `10-50,103,200-`
+    #[arg(
+        long = "entries",
+        value_name = "RANGES",
+        value_delimiter = ',',
+        value_parser = filter::parse_index_range
+    )]
+    entry_ranges: Vec<RangeInclusive<usize>>,
+    /// Stop writing files once they add up to this size, e.g. `500mb` or `2gb`; later
+    /// files that still fit in what is left are written
+    #[arg(long, value_parser = filter::parse_size)]
+    max_total_size: Option<u64>,
+    /// Extract at most this many responses that pass the filters
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Skip this many responses that pass the filters before extracting
+    #[arg(long, default_value_t = 0)]
+    offset: usize,
+    /// Only extract entries started at or after this RFC 3339 time
+    #[arg(long, value_parser = filter::parse_time)]
+    after: Option<DateTime<FixedOffset>>,
+    /// Only extract entries started before this RFC 3339 time
+    #[arg(long, value_parser = filter::parse_time)]
+    before: Option<DateTime<FixedOffset>>,
+    /// Only extract entries started in this last stretch of the capture, e.g. `90s`,
+    /// `5m` or `1h`
+    #[arg(long, value_parser = filter::parse_duration)]
+    last: Option<chrono::Duration>,
+    /// Write `<file>.headers.json` with headers, cookies and status next to each asset
+    #[arg(long)]
+    headers_sidecar: bool,
+    /// Write `<file>.meta.json` with the request, response headers, timings and server
+    /// address next to each asset
+    #[arg(long, value_enum)]
+    sidecar: Option<SidecarFormat>,
+    /// Export every cookie in the capture to `cookies.txt` in the given format
+    #[arg(long, value_enum)]
+    cookies: Option<CookieFormat>,
+    /// Write a `SHA256SUMS` list of every file in the output folder, to be checked later
+    /// with `verify` or `sha256sum -c`
+    #[arg(long)]
+    checksums: bool,
+    /// Run a command for each extracted file, such as `--exec 'clamscan {path}'`, with
+    /// `{path}`, `{url}` and `{index}` replaced. The command is not run through a shell;
+    /// one that fails fails the entry (repeatable)
+    #[arg(long)]
+    exec: Vec<String>,
+    /// On failures, pack the failure log, this configuration and the offending entries
+    /// (bodies stripped) into a zip archive for bug reports
+    #[arg(long)]
+    error_bundle: Option<PathBuf>,
+    /// Do not print a line per extracted file
+    #[arg(short, long, overrides_with = "verbose")]
+    quiet: bool,
+    /// Also print why entries were skipped
+    #[arg(short, long, overrides_with = "quiet")]
+    verbose: bool,
+    /// Set each file's modification time to the response's `Last-Modified` header, or
+    /// to when the request was made
+    #[arg(long)]
+    preserve_times: bool,
+    /// Put files into one subfolder per group
+    #[arg(long, value_enum)]
+    group_by: Option<GroupBy>,
+    /// What the folders of `--group-by page` are named after
+    #[arg(long, value_enum, default_value_t = PageName::Title)]
+    page_name: PageName,
+    /// Write bodies stored with their `Content-Encoding` still applied as they are,
+    /// instead of decompressing them
+    #[arg(long)]
+    no_decompress: bool,
+    /// Rewrite every file, even those the manifest of an earlier run lists as unchanged,
+    /// and extract into folders that hold files of other programs without asking
+    #[arg(long)]
+    force: bool,
+    /// Download the bodies the exporter left out, reusing the captured request headers
+    /// and cookies
+    #[cfg(feature = "fetch")]
+    #[arg(long)]
+    fetch_missing: bool,
+    /// Number of downloads running at once with `--fetch-missing`
+    #[cfg(feature = "fetch")]
+    #[arg(long, default_value_t = 4)]
+    fetch_concurrency: usize,
+    /// Seconds after which a download started by `--fetch-missing` is abandoned
+    #[cfg(feature = "fetch")]
+    #[arg(long, default_value_t = 30)]
+    fetch_timeout: u64,
+    /// Name extracted responses after the SHA-256 of their content instead of the URL
+    #[arg(long)]
+    name_by_hash: bool,
+    /// How query strings show up in file names, so `/img?id=1` and `/img?id=2` can
+    /// stay distinct
+    #[arg(long = "qs", value_enum, default_value_t = QueryNames::Drop)]
+    query_names: QueryNames,
+    /// Name files after the `filename` of a `Content-Disposition` header when the
+    /// response has one, rather than after the URL
+    #[arg(long)]
+    use_content_disposition: bool,
+    /// Percent-decode file and folder names taken from the URL, so `%D1%84%D0%BE.jpg`
+    /// is written as `фо.jpg`; names that are not valid UTF-8 stay escaped
+    #[arg(long)]
+    decode_names: bool,
+    /// Extract responses only to requests with these methods (comma-separated)
+    #[arg(long = "method", value_delimiter = ',', default_value = "GET")]
+    methods: Vec<String>,
+    /// Extract only entries from this host, `*.example.com` matches its subdomains
+    /// (repeatable)
+    #[arg(long = "domain")]
+    domains: Vec<String>,
+    /// Skip entries from this host, with the same wildcards as `--domain` (repeatable)
+    #[arg(long = "exclude-domain")]
+    exclude_domains: Vec<String>,
+    /// Pick the entries to extract in a terminal UI
+    #[cfg(feature = "interactive")]
+    #[arg(long)]
+    interactive: bool,
+    /// Pretty-print JSON bodies and re-indent minified CSS and JavaScript
+    #[arg(long)]
+    beautify: bool,
+    /// Rebuild the original sources embedded in source maps (`.map` responses and inline
+    /// `sourceMappingURL`s) under `src/`
+    #[arg(long)]
+    unpack_sourcemaps: bool,
+    /// Join the segments of HLS (`.m3u8`) and DASH (`.mpd`) streams, in playlist order,
+    /// into one media file per stream under `streams/`
+    #[arg(long)]
+    assemble_streams: bool,
+    /// Re-encode images of one format into another, e.g. `webp=png,gif=png`
+    #[cfg(feature = "images")]
+    #[arg(long, value_delimiter = ',', value_parser = crate::images::parse_conversion)]
+    convert: Vec<crate::images::Conversion>,
+    /// Remove EXIF, XMP and ICC data, such as where and with what camera a photo was
+    /// taken, from extracted JPEG, PNG and WebP images
+    #[arg(long)]
+    strip_metadata: bool,
+    /// Write previews of the extracted images and an `index.html` gallery to this folder
+    #[cfg(feature = "images")]
+    #[arg(long)]
+    thumbnails: Option<PathBuf>,
+    /// Largest width and height of the previews, in pixels
+    #[cfg(feature = "images")]
+    #[arg(long, default_value_t = 256, requires = "thumbnails")]
+    thumb_size: u32,
+    /// Store the target of a redirect chain under the originally requested URL and
+    /// skip the redirects themselves
+    #[arg(long)]
+    collapse_redirects: bool,
+    /// Extract these kinds of files instead of the default images (comma-separated;
+    /// repeatable). `--mime-type` adds to them
+    #[arg(long = "preset", value_enum, value_delimiter = ',')]
+    presets: Vec<Preset>,
+    /// Also extract responses of this type, saved with this extension
+    /// (`image/avif=.avif`; repeatable)
+    #[arg(long = "mime-type", value_parser = parse_mime_type)]
+    extra_mime_types: Vec<(String, String)>,
+    /// Flush every written file and its folder to disk, so even a power loss leaves no
+    /// truncated files behind
+    #[arg(long)]
+    durable: bool,
+    /// Save progress to this file every few seconds, so `--resume` can continue the
+    /// run if it is killed; removed once the run finishes
+    #[arg(long, value_name = "FILE", conflicts_with = "resume")]
+    checkpoint: Option<PathBuf>,
+    /// Continue the run that saved this checkpoint after the last file it finished,
+    /// with the same input and options, saving further progress to the same file
+    #[arg(long, value_name = "FILE")]
+    resume: Option<PathBuf>,
+    /// Decode the contents of `data:` URLs into files instead of skipping them
+    #[arg(long)]
+    include_data_urls: bool,
+    /// Make paths valid on Windows: rename reserved names such as `CON`, replace
+    /// trailing dots and spaces, keep names differing only in case apart and shorten
+    /// names beyond 260 characters. Always on when running on Windows
+    #[arg(long)]
+    windows_paths: bool,
+    /// On Windows, write paths beyond 260 characters as `\\?\` extended paths instead
+    /// of shortening file names
+    #[arg(long)]
+    long_paths: bool,
+    /// Prefix file names with the entry's zero-padded position in load order, e.g.
+    /// `0042_app.js`
+    #[arg(long)]
+    number_files: bool,
+    /// Map the capture into memory and parse it with SIMD instructions, which pays off
+    /// on captures of a gigabyte or more
+    #[cfg(feature = "fast")]
+    #[arg(long)]
+    fast: bool,
+    /// Convert text bodies declaring another `charset`, such as `windows-1251`, to
+    /// UTF-8, and report text bodies that are not valid in their charset
+    #[arg(long = "transcode-utf8")]
+    transcode: bool,
+    /// Also extract `text/event-stream` and NDJSON responses, split into one JSON line
+    /// per event or record in `<name>.ndjson`
+    #[arg(long)]
+    split_streams: bool,
+    /// Threads decoding bodies, and as many writing them; 1 does everything in turn
+    #[arg(long, default_value_t = thread::available_parallelism().map_or(1, usize::from))]
+    jobs: usize,
+    /// Format of the per-entry lines printed to stdout
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+    /// Print a last line to stdout with the counts and the exit status
+    #[arg(long, value_enum)]
+    summary: Option<SummaryFormat>,
+    /// Extract every HAR file that appears in this folder from now on, each into
+    /// `<name>_extract` next to it, until interrupted
+    #[cfg(feature = "watch")]
+    #[arg(long, conflicts_with_all = ["input_har", "output_dir"])]
+    watch: Option<PathBuf>,
+}
+
+impl ExtractArgs {
+    /// These settings applied to another capture.
+    #[cfg(feature = "watch")]
+    pub fn with_input(&self, input_har: String) -> ExtractArgs {
+        ExtractArgs {
+            input_har: Some(input_har),
+            watch: None,
+            ..self.clone()
+        }
+    }
+
+    /// Default extraction settings for a HAR produced by another command.
+    #[cfg(feature = "cdp")]
+    pub fn for_input(input_har: String, output_dir: Option<String>) -> ExtractArgs {
+        use clap::FromArgMatches;
+        // Parsed rather than built so every option gets its command-line default.
+        let command = ExtractArgs::augment_args(clap::Command::new("extract_har"));
+        let args = ["extract_har".to_string(), "--".to_string(), input_har]
+            .into_iter()
+            .chain(output_dir);
+        let matches = command.get_matches_from(args);
+        ExtractArgs::from_arg_matches(&matches).unwrap()
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum CookieFormat {
+    /// Netscape cookie jar, as used by curl and wget
+    Netscape,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SidecarFormat {
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SummaryFormat {
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum GroupBy {
+    /// The page of `log.pages` the entry belongs to, named as `--page-name` says
+    Page,
+    /// The kind of response: `images`, `fonts`, `scripts`, `styles`, `media` or `other`
+    Type,
+}
+
+/// How the folders of `--group-by page` are named.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum PageName {
+    /// The page title, or the host and path of its URL when the exporter used the URL
+    /// as the title
+    #[default]
+    Title,
+    /// The id the exporter gave the page, such as `page_1`
+    Id,
+    /// The host and path of the page's URL
+    Url,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Preset {
+    /// PNG, JPEG, GIF, WebP, AVIF, SVG, BMP and icons
+    Images,
+    /// WOFF, WOFF2, TrueType, OpenType and EOT
+    Fonts,
+    /// MP4, WebM, MP3, M4A, AAC, Ogg, WAV and MPEG-TS segments
+    Media,
+    /// JavaScript, WebAssembly and style sheets
+    Scripts,
+    /// HTML, PDF, plain text, CSV, XML and office documents
+    Documents,
+    /// JSON, NDJSON and Protocol Buffers responses
+    Api,
+}
+
+impl Preset {
+    /// MIME types of the preset with the extension their files are saved with.
+    pub fn mime_types(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Preset::Images => &[
+                ("image/png", ".png"),
+                ("image/jpeg", ".jpg"),
+                ("image/gif", ".gif"),
+                ("image/webp", ".webp"),
+                ("image/avif", ".avif"),
+                ("image/svg+xml", ".svg"),
+                ("image/bmp", ".bmp"),
+                ("image/x-icon", ".ico"),
+                ("image/vnd.microsoft.icon", ".ico"),
+            ],
+            Preset::Fonts => &[
+                ("font/woff", ".woff"),
+                ("font/woff2", ".woff2"),
+                ("font/ttf", ".ttf"),
+                ("font/otf", ".otf"),
+                ("application/font-woff", ".woff"),
+                ("application/font-woff2", ".woff2"),
+                ("application/x-font-woff", ".woff"),
+                ("application/x-font-ttf", ".ttf"),
+                ("application/x-font-otf", ".otf"),
+                ("application/vnd.ms-fontobject", ".eot"),
+            ],
+            Preset::Media => &[
+                ("video/mp4", ".mp4"),
+                ("video/webm", ".webm"),
+                ("video/mp2t", ".ts"),
+                ("audio/mpeg", ".mp3"),
+                ("audio/mp4", ".m4a"),
+                ("audio/x-m4a", ".m4a"),
+                ("audio/aac", ".aac"),
+                ("audio/ogg", ".ogg"),
+                ("audio/wav", ".wav"),
+                ("audio/webm", ".weba"),
+            ],
+            Preset::Scripts => &[
+                ("text/javascript", ".js"),
+                ("application/javascript", ".js"),
+                ("application/x-javascript", ".js"),
+                ("application/wasm", ".wasm"),
+                ("text/css", ".css"),
+            ],
+            Preset::Documents => &[
+                ("text/html", ".html"),
+                ("application/xhtml+xml", ".xhtml"),
+                ("application/pdf", ".pdf"),
+                ("text/plain", ".txt"),
+                ("text/csv", ".csv"),
+                ("text/xml", ".xml"),
+                ("application/xml", ".xml"),
+                ("application/msword", ".doc"),
+                (
+                    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+                    ".docx",
+                ),
+                ("application/vnd.ms-excel", ".xls"),
+                (
+                    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+                    ".xlsx",
+                ),
+            ],
+            Preset::Api => &[
+                ("application/json", ".json"),
+                ("text/json", ".json"),
+                ("application/ld+json", ".json"),
+                ("application/problem+json", ".json"),
+                ("application/graphql-response+json", ".json"),
+                ("application/x-ndjson", ".ndjson"),
+                ("application/x-protobuf", ".pb"),
+                ("application/protobuf", ".pb"),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum QueryNames {
+    /// Ignore the query string
+    #[default]
+    Drop,
+    /// Append the query string, with unsafe characters replaced
+    Append,
+    /// Append a short hash of the query string
+    Hash,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// One human-readable line per extracted file
+    #[default]
+    Text,
+    /// One JSON object per extracted, skipped or failed entry
+    Json,
+}
+
+#[derive(Serialize)]
+struct HeadersSidecar<'a> {
+    url: &'a str,
+    method: &'a str,
+    status: i32,
+    request_headers: &'a [HarHeader],
+    request_cookies: &'a [HarCookie],
+    response_headers: &'a [HarHeader],
+    response_cookies: &'a [HarCookie],
+}
+
+#[derive(Serialize)]
+struct MetaSidecar<'a> {
+    url: &'a str,
+    method: &'a str,
+    status: i32,
+    started: &'a str,
+    time: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timings: Option<&'a HarTimings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    server_ip_address: Option<&'a str>,
+    request_headers: &'a [HarHeader],
+    response_headers: &'a [HarHeader],
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum Order {
+    /// As recorded in the HAR
+    #[default]
+    Har,
+    /// Largest bodies first
+    #[value(aliases = ["size", "size-desc"])]
+    Largest,
+    /// Smallest bodies first
+    Smallest,
+    /// Images first, then media, fonts, styles, scripts and the rest, each largest
+    /// first, so `--limit` and `--max-total-size` keep the most valuable assets
+    TypePriority,
+    /// By `startedDateTime`
+    Time,
+    /// Alphabetically by URL
+    Url,
+}
+
+impl Order {
+    /// Sorts `(index, entry)` pairs; the sort is stable so ties keep HAR order.
+    fn sort(self, entries: &mut [(usize, HarLogEntry)]) {
+        match self {
+            Order::Har => {}
+            Order::Largest => {
+                entries.sort_by_key(|(_, entry)| Reverse(entry.response.content.body_size()))
+            }
+            Order::Smallest => entries.sort_by_key(|(_, entry)| entry.response.content.body_size()),
+            Order::TypePriority => entries.sort_by_cached_key(|(_, entry)| {
+                let essence = entry.response.content.essence().to_ascii_lowercase();
+                let rank = ["images", "media", "fonts", "styles", "scripts"]
+                    .iter()
+                    .position(|x| *x == type_folder(&essence))
+                    .unwrap_or(5);
+                (rank, Reverse(entry.response.content.body_size()))
+            }),
+            Order::Time => entries.sort_by_key(|(_, entry)| entry.started()),
+            Order::Url => entries.sort_by(|(_, a), (_, b)| a.request.url.cmp(&b.request.url)),
+        }
+    }
+}
+
+/// Which entry stands for the others requesting the same URL.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum UniqueUrls {
+    /// The one recorded first
+    First,
+    /// The one recorded last
+    Last,
+    /// The one with the largest body
+    Largest,
+}
+
+impl UniqueUrls {
+    /// Indexes of the entries kept, one per URL.
+    fn representatives(self, entries: &[(usize, HarLogEntry)]) -> HashSet<usize> {
+        let has_body = |entry: &HarLogEntry| entry.response.content.body_size() > 0;
+        let mut chosen = HashMap::<&str, (usize, &HarLogEntry)>::new();
+        for (index, entry) in entries {
+            let key = &entry.request.url[..url::Position::AfterQuery];
+            let Some(&(current, current_entry)) = chosen.get(key) else {
+                chosen.insert(key, (*index, entry));
+                continue;
+            };
+            let better = match (has_body(entry), has_body(current_entry)) {
+                (true, false) => true,
+                (false, true) => false,
+                _ => match self {
+                    UniqueUrls::First => *index < current,
+                    UniqueUrls::Last => *index > current,
+                    UniqueUrls::Largest => {
+                        let size = entry.response.content.body_size();
+                        let current_size = current_entry.response.content.body_size();
+                        (size, Reverse(*index)) > (current_size, Reverse(current))
+                    }
+                },
+            };
+            if better {
+                chosen.insert(key, (*index, entry));
+            }
+        }
+        chosen.values().map(|(index, _)| *index).collect()
+    }
+}
+
+/// The type and subtype of a MIME type, without parameters, in lower case.
+fn essence(mime_type: &str) -> String {
+    mime_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// Value of a MIME type parameter such as `charset`, without surrounding quotes.
+fn mime_parameter<'a>(mime_type: &'a str, name: &str) -> Option<&'a str> {
+    mime_type.split(';').skip(1).find_map(|x| {
+        let (key, value) = x.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Converts a body in the MIME type's `charset` to UTF-8. Bodies without a charset
+/// are only checked to be UTF-8. Returns an error naming the charset when the body
+/// had malformed bytes, which are replaced in the returned body.
+fn transcode_utf8(mime_type: &str, body: &[u8]) -> (Vec<u8>, Option<String>) {
+    let encoding = mime_parameter(mime_type, "charset")
+        .and_then(|x| Encoding::for_label(x.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    let (text, had_errors) = encoding.decode_without_bom_handling(body);
+    let bytes = match text {
+        // Already UTF-8 and valid, so the body is kept byte for byte.
+        Cow::Borrowed(_) if encoding == encoding_rs::UTF_8 => body.to_vec(),
+        text => text.into_owned().into_bytes(),
+    };
+    (bytes, had_errors.then(|| encoding.name().to_string()))
+}
+
+/// Streaming responses `--split-streams` writes one line per event or record of.
+const STREAM_MIME_TYPES: &[&str] = &[
+    "text/event-stream",
+    "application/x-ndjson",
+    "application/ndjson",
+    "application/jsonl",
+    "application/x-jsonlines",
+];
+
+#[derive(Serialize)]
+struct EventLine {
+    event: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry: Option<u64>,
+    data: String,
+}
+
+/// Parses a Server-Sent Events body into its events. An event cut off by the end of
+/// the capture is kept, unlike in a browser, since it was received all the same.
+fn server_sent_events(text: &str) -> Vec<EventLine> {
+    let mut events = Vec::new();
+    let (mut event, mut id, mut retry, mut data) = (None, None, None, None::<String>);
+    // A trailing empty line dispatches the last event whether or not the body had one.
+    for line in text
+        .split("\r\n")
+        .flat_map(|x| x.split(['\r', '\n']))
+        .chain([""])
+    {
+        if line.is_empty() {
+            if let Some(data) = data.take() {
+                events.push(EventLine {
+                    event: event.take().unwrap_or_else(|| "message".to_string()),
+                    id: id.clone(),
+                    retry: retry.take(),
+                    data,
+                });
+            }
+            event = None;
+            continue;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "" => {}
+            "event" => event = Some(value.to_string()),
+            "data" => match &mut data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => data = Some(value.to_string()),
+            },
+            "id" if !value.contains('\0') => id = Some(value.to_string()),
+            "retry" => retry = value.parse().ok(),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Rewrites an event stream or NDJSON body as one JSON value per line. NDJSON lines
+/// that are not JSON are kept as strings so the result stays valid.
+fn split_stream(essence: &str, body: &[u8]) -> Option<Vec<u8>> {
+    if !STREAM_MIME_TYPES.contains(&essence) {
+        return None;
+    }
+    let text = String::from_utf8_lossy(body);
+    let mut lines = Vec::new();
+    if essence == "text/event-stream" {
+        for event in server_sent_events(&text) {
+            serde_json::to_writer(&mut lines, &event).unwrap();
+            lines.push(b'\n');
+        }
+    } else {
+        for line in text.lines().map(str::trim).filter(|x| !x.is_empty()) {
+            let record = serde_json::from_str::<serde_json::Value>(line)
+                .unwrap_or_else(|_| serde_json::Value::from(line));
+            serde_json::to_writer(&mut lines, &record).unwrap();
+            lines.push(b'\n');
+        }
+    }
+    Some(lines)
+}
+
+pub fn is_textual(mime_type: &str) -> bool {
+    let essence = essence(mime_type);
+    essence.starts_with("text/")
+        || essence.ends_with("json")
+        || essence.ends_with("xml")
+        || essence.ends_with("javascript")
+        || STREAM_MIME_TYPES.contains(&essence.as_str())
+}
+
+fn parse_mime_type(value: &str) -> Result<(String, String), String> {
+    let (mime_type, ext) = value
+        .split_once('=')
+        .filter(|(x, y)| !x.is_empty() && !y.trim_start_matches('.').is_empty())
+        .ok_or("expected a MIME type and extension such as image/avif=.avif")?;
+    let ext = ext.trim_start_matches('.');
+    Ok((mime_type.to_string(), format!(".{ext}")))
+}
+
+/// Why an extraction could not run: the message to print and the exit status to end
+/// with, as listed in [`exit_code`].
+#[derive(Debug)]
+pub struct RunError {
+    pub status: i32,
+    pub message: String,
+}
+
+impl RunError {
+    /// An error ending with status 1.
+    pub fn new(message: String) -> RunError {
+        RunError { status: 1, message }
+    }
+
+    /// Prints the message and exits, for the command line.
+    pub fn exit(self) -> ! {
+        eprintln!("{}", self.message);
+        std::process::exit(self.status);
+    }
+}
+
+/// Extracts the capture and returns the exit status, as listed in [`exit_code`].
+pub fn run(args: ExtractArgs) -> Result<i32, RunError> {
+    #[cfg(feature = "watch")]
+    if let Some(folder) = &args.watch {
+        crate::watch::run(folder, &args)?;
+        return Ok(0);
+    }
+    let config = format!("{args:#?}");
+    let ExtractArgs {
+        input_har,
+        output_dir,
+        output_domain,
+        output_path,
+        output_path_depth,
+        max_depth,
+        flatten,
+        #[cfg(feature = "s3")]
+        output_url,
+        requests,
+        websockets,
+        graphql,
+        progress_socket,
+        report,
+        order,
+        unique_urls,
+        entry_ranges,
+        max_total_size,
+        limit,
+        offset,
+        after,
+        before,
+        last,
+        headers_sidecar,
+        sidecar,
+        cookies,
+        checksums,
+        exec,
+        error_bundle,
+        quiet,
+        verbose,
+        preserve_times,
+        group_by,
+        page_name,
+        no_decompress,
+        force,
+        #[cfg(feature = "fetch")]
+        fetch_missing,
+        #[cfg(feature = "fetch")]
+        fetch_concurrency,
+        #[cfg(feature = "fetch")]
+        fetch_timeout,
+        name_by_hash,
+        query_names,
+        use_content_disposition,
+        decode_names,
+        methods,
+        domains,
+        exclude_domains,
+        #[cfg(feature = "interactive")]
+        interactive,
+        beautify,
+        unpack_sourcemaps,
+        assemble_streams,
+        #[cfg(feature = "images")]
+        convert,
+        strip_metadata,
+        #[cfg(feature = "images")]
+        thumbnails,
+        #[cfg(feature = "images")]
+        thumb_size,
+        collapse_redirects,
+        presets,
+        extra_mime_types,
+        durable,
+        checkpoint,
+        resume,
+        include_data_urls,
+        windows_paths,
+        long_paths,
+        number_files,
+        #[cfg(feature = "fast")]
+        fast,
+        transcode,
+        split_streams,
+        jobs,
+        log_format,
+        summary,
+        #[cfg(feature = "watch")]
+        watch,
+    } = args;
+    #[cfg(feature = "watch")]
+    debug_assert!(watch.is_none());
+    let input_har = input_har.unwrap();
+    let input_file_path = input::try_resolve_input(&input_har).map_err(RunError::new)?;
+    // An `s3://` output folder is taken as the upload target.
+    #[cfg(feature = "s3")]
+    let (output_dir, output_url) = match output_dir {
+        Some(x) if x.starts_with("s3://") => (None, Some(x)),
+        x => (x, output_url),
+    };
+    #[cfg(feature = "s3")]
+    let upload_target = output_url
+        .map(|x| {
+            crate::upload::Target::parse(&x)
+                .map_err(|err| RunError::new(tr!(CannotParseOutputUrl, x, err)))
+        })
+        .transpose()?;
+    // Without an output folder, files are staged in a temporary one.
+    #[cfg(feature = "s3")]
+    let staging = (upload_target.is_some() && output_dir.is_none())
+        .then(|| std::env::temp_dir().join(format!("extract_har-{}", std::process::id())));
+    #[cfg(feature = "s3")]
+    let output_dir = output_dir.or_else(|| Some(staging.as_ref()?.to_string_lossy().into_owned()));
+    let folder = if let Some(arg) = output_dir {
+        PathBuf::from_str(&arg).map_err(|_| RunError::new(tr!(CannotParsePath, arg)))?
+    } else {
+        input_file_path.with_file_name({
+            let mut without_ext = input_file_path
+                .with_extension("")
+                .file_name()
+                .unwrap()
+                .to_owned();
+            without_ext.push("_extract");
+            without_ext
+        })
+    };
+    let resumed = match &resume {
+        Some(path) => {
+            let checkpoint = Checkpoint::load(path).map_err(|err| {
+                RunError::new(tr!(CannotReadCheckpoint, path.to_string_lossy(), err))
+            })?;
+            if checkpoint.input != input_file_path.to_string_lossy() {
+                let message = tr!(CheckpointMismatch, path.to_string_lossy(), checkpoint.input);
+                return Err(RunError::new(message));
+            }
+            Some(checkpoint)
+        }
+        None => None,
+    };
+    // A killed run leaves files but no manifest behind.
+    if folder.is_dir() && !force && resumed.is_none() {
+        check_foreign_folder(&folder)?;
+    }
+    if !folder.is_dir() {
+        fs::create_dir_all(&folder)
+            .map_err(|_| RunError::new(tr!(CannotCreateDirs, folder.to_string_lossy())))?;
+    }
+    // Windows returns canonical paths in the `\\?\` form, which lifts the length limit.
+    let long_paths = long_paths && cfg!(windows);
+    let folder = if long_paths {
+        folder.canonicalize().unwrap_or(folder)
+    } else {
+        folder
+    };
+    // Entries are extracted as they are read, unless something needs all of them
+    // before the first file is written.
+    let streamed = matches!(order, Order::Har)
+        && unique_urls.is_none()
+        && last.is_none()
+        && !matches!(group_by, Some(GroupBy::Page))
+        && !matches!(cookies, Some(CookieFormat::Netscape))
+        && !number_files
+        && !collapse_redirects
+        && !unpack_sourcemaps
+        && !assemble_streams
+        && checkpoint.is_none()
+        && resume.is_none()
+        && !import::converts(&input_file_path);
+    #[cfg(feature = "interactive")]
+    let streamed = streamed && !interactive;
+    #[cfg(feature = "fetch")]
+    let streamed = streamed && !fetch_missing;
+    #[cfg(feature = "fast")]
+    let streamed = streamed && !fast;
+    // A streamed capture may only turn out to be malformed once extracted.
+    let load_error = |err| match err {
+        LoadError::Open(err) => {
+            RunError::new(format!("{}: {err}", input_file_path.to_string_lossy()))
+        }
+        LoadError::Parse(err) => {
+            if let Some(bundle) = &error_bundle {
+                let failure = Failure {
+                    index: None,
+                    message: err.to_string(),
+                };
+                write_error_bundle(bundle, &input_file_path, &config, &[failure]);
+            }
+            RunError {
+                status: exit_code::PARSE_ERROR,
+                message: tr!(CannotParseHar, format!("{err:?}")),
+            }
+        }
+    };
+    eprintln!("{}", tr!(LoadingFile));
+    let (har, stream) = if streamed {
+        let stream = input::try_stream(&input_file_path, jobs.max(1) * 2).map_err(&load_error)?;
+        (Har::default(), Some(stream))
+    } else {
+        #[cfg(feature = "fast")]
+        let loaded = if fast {
+            input::try_load_fast(&input_file_path)
+        } else {
+            input::try_load(&input_file_path)
+        };
+        #[cfg(not(feature = "fast"))]
+        let loaded = input::try_load(&input_file_path);
+        (loaded.map_err(&load_error)?, None)
+    };
+    let (streamed_entries, parser) = stream.unzip();
+    eprintln!("{}", tr!(OutputSettings));
+    if flatten {
+        eprintln!("{}", tr!(FlattenedPaths));
+    } else if output_domain.is_none() && output_path.is_none() && max_depth.is_none_or(|x| x == 0) {
+        eprintln!("{}", tr!(NoDirectoryStructure));
+    } else {
+        if output_path.is_some() && output_domain.is_none() {
+            return Err(RunError::new(tr!(OutputDomainRequired)));
+        }
+        if let Some(domain) = &output_domain {
+            eprintln!("{}", tr!(DomainSubfolders, domain));
+        }
+        match (&output_path, max_depth) {
+            (_, Some(0)) => {}
+            (_, Some(depth)) => eprintln!("{}", tr!(MaxDepthSubfolders, depth)),
+            (Some(path), None) => eprintln!(
+                "{}",
+                tr!(
+                    PathSubfolders,
+                    path,
+                    if output_path_depth > 0 {
+                        tr!(First)
+                    } else {
+                        tr!(Last)
+                    },
+                    output_path_depth.abs()
+                )
+            ),
+            (None, None) => {}
+        }
+    }
+    eprintln!("{}", tr!(StartingExtraction));
+    let mut mime_types = if presets.is_empty() {
+        extraction::default_mime_types()
+    } else {
+        presets
+            .iter()
+            .flat_map(|x| x.mime_types())
+            .copied()
+            .collect()
+    };
+    if split_streams {
+        for mime_type in STREAM_MIME_TYPES {
+            mime_types.insert(mime_type, ".ndjson");
+        }
+    }
+    for (mime_type, ext) in &extra_mime_types {
+        mime_types.insert(mime_type, ext);
+    }
+    let mime_type_extensions = mime_types.values().collect::<Vec<_>>();
+    let mut layout = Layout {
+        domain: output_domain.is_some() || flatten,
+        path: !flatten && (output_path.is_some() || max_depth.is_some_and(|x| x > 0)),
+        path_depth: max_depth.map_or(output_path_depth, |x| i32::try_from(x).unwrap_or(i32::MAX)),
+        group: None,
+        query_names,
+        decode_names,
+        flattened: flatten.then(RefCell::default),
+    };
+    let hosts = HostFilter {
+        include: domains,
+        exclude: exclude_domains,
+    };
+    let page_folders = match group_by {
+        Some(GroupBy::Page) => page_folders(&har.log.pages, &har.log.entries, page_name),
+        _ => HashMap::new(),
+    };
+    let mut graphql_calls = HashMap::new();
+    let (manifest, resume_after) = match resumed {
+        Some(checkpoint) => {
+            if checkpoint.entries != har.log.entries.len() {
+                let path = resume
+                    .as_deref()
+                    .map(Path::to_string_lossy)
+                    .unwrap_or_default();
+                return Err(RunError::new(tr!(
+                    CheckpointMismatch,
+                    path,
+                    checkpoint.input
+                )));
+            }
+            (checkpoint.manifest, Some(checkpoint.last_index))
+        }
+        None => (Manifest::load(&folder), None),
+    };
+    let checkpointer = checkpoint.or(resume.clone()).map(|path| Checkpointer {
+        path,
+        input: input_file_path.to_string_lossy().into_owned(),
+        entries: har.log.entries.len(),
+        saved: Instant::now(),
+    });
+    let handlers = exec
+        .iter()
+        .map(|x| -> Result<Box<dyn EntryHandler>, RunError> {
+            let handler =
+                ExecHandler::new(x).map_err(|err| RunError::new(tr!(CannotParseExec, x, err)))?;
+            Ok(Box::new(handler))
+        })
+        .collect::<Result<_, _>>()?;
+    let mut output = Output {
+        sink: DirSink::new(folder).with_durable(durable),
+        observers: Vec::new(),
+        handlers,
+        count_extracted: 0,
+        count_sources: 0,
+        failures: Vec::new(),
+        bar: ProgressBar::hidden(),
+        quiet: quiet || log_format == LogFormat::Json,
+        verbose,
+        modified: None,
+        manifest,
+        force,
+        count_unchanged: 0,
+        count_undecodable: 0,
+        budget: Budget {
+            limit: max_total_size,
+            taken: 0,
+        },
+        count_over_budget: 0,
+        beautify,
+        redirects: Vec::new(),
+        response_entry: None,
+        windows_paths: (windows_paths || cfg!(windows)).then(CaseFolder::default),
+        long_paths,
+        number: None,
+        checkpointer,
+    };
+    if let Some(socket) = progress_socket {
+        let observer = StreamObserver::open(&socket)
+            .map_err(|_| RunError::new(tr!(CannotOpenProgressSocket, socket.to_string_lossy())))?;
+        output.observers.push(Box::new(observer));
+    }
+    if log_format == LogFormat::Json {
+        output.observers.push(Box::new(StreamObserver::stdout()));
+    }
+    if let Some(path) = report {
+        let report = Report::new(path, output.sink.root(), &input_file_path);
+        output.observers.push(Box::new(report));
+    }
+    if let Some(CookieFormat::Netscape) = cookies {
+        let jar = cookies::netscape_jar(&har.log.entries);
+        let jar_file = output.sink.root().join("cookies.txt");
+        match output.write_extra(Path::new("cookies.txt"), jar.as_bytes(), None) {
+            Ok(()) => eprintln!("{}", tr!(CookiesExported, jar_file.to_string_lossy())),
+            Err(err) => output.failures.push(Failure {
+                index: None,
+                message: format!("{}: {err}", jar_file.to_string_lossy()),
+            }),
+        }
+    }
+    // Entries a lenient load left out count, and fail, like any other.
+    let count_total = har.log.entries.len() + har.log.skipped.len();
+    let indices = (0..count_total)
+        .map(|x| har.log.index_of(x))
+        .collect::<Vec<_>>();
+    let mut entries = indices.into_iter().zip(har.log.entries).collect::<Vec<_>>();
+    order.sort(&mut entries);
+    if !entry_ranges.is_empty() {
+        entries.retain(|(index, entry)| {
+            let keep = entry_ranges.iter().any(|x| x.contains(index));
+            if !keep {
+                output.skip(*index, &entry.request.url, "entries");
+            }
+            keep
+        });
+    }
+    if let Some(unique_urls) = unique_urls {
+        let kept = unique_urls.representatives(&entries);
+        entries.retain(|(index, entry)| {
+            let keep = kept.contains(index);
+            if !keep {
+                output.skip(*index, &entry.request.url, "duplicate_url");
+            }
+            keep
+        });
+    }
+    #[cfg(feature = "interactive")]
+    if interactive {
+        let Some(chosen) = crate::tui::select(&entries) else {
+            eprintln!("{}", tr!(SelectionCancelled));
+            return Ok(0);
+        };
+        entries.retain(|(index, _)| chosen.contains(index));
+    }
+    output.notify(&Event::Started {
+        total: (!streamed).then_some(count_total),
+    });
+    for skipped in har.log.skipped {
+        output.unreadable(skipped);
+    }
+    // The bar would garble JSON lines, which are written straight to stdout.
+    if log_format == LogFormat::Text && io::stdout().is_terminal() {
+        // Streamed entries are counted as they come, with no total to show.
+        output.bar = if streamed {
+            ProgressBar::no_length().with_style(
+                ProgressStyle::with_template("{spinner} {pos} [{elapsed_precise}]").unwrap(),
+            )
+        } else {
+            ProgressBar::new(entries.len() as u64).with_style(
+                ProgressStyle::with_template(
+                    "{bar:40} {pos}/{len} [{elapsed_precise}, {eta} left]",
+                )
+                .unwrap(),
+            )
+        };
+    }
+    #[cfg(feature = "images")]
+    let mut gallery = thumbnails
+        .map(|dir| {
+            crate::images::Gallery::new(dir.clone(), thumb_size)
+                .map_err(|_| RunError::new(tr!(CannotCreateDirs, dir.to_string_lossy())))
+        })
+        .transpose()?;
+    let numbers = if number_files {
+        load_order_numbers(&entries)
+    } else {
+        HashMap::new()
+    };
+    let redirect_chains = if collapse_redirects {
+        redirect_chains(&entries)
+    } else {
+        HashMap::new()
+    };
+    if unpack_sourcemaps {
+        unpack_sources(&mut output, &entries, &hosts, !no_decompress);
+    }
+    if assemble_streams {
+        join_streams(&mut output, &entries, &hosts, !no_decompress);
+    }
+    let window = TimeWindow::new(
+        after,
+        before,
+        last,
+        entries.iter().filter_map(|(_, entry)| entry.started()),
+    );
+    // Responses that passed the filters so far, for `--offset` and `--limit`.
+    let mut count_matched = 0;
+    // Entries with the bodies `--fetch-missing` downloaded, outliving the jobs.
+    #[cfg(feature = "fetch")]
+    let fetched;
+    // Entries in the order they are taken through the filters.
+    let mut source: Box<dyn Iterator<Item = Result<_, SkippedEntry>>> = match streamed_entries {
+        Some(streamed) => Box::new(
+            streamed
+                .into_iter()
+                .map(|x| x.map(|(index, entry)| (index, Cow::Owned(entry)))),
+        ),
+        None => Box::new(
+            entries
+                .iter()
+                .map(|(index, entry)| Ok((*index, Cow::Borrowed(entry)))),
+        ),
+    };
+    // Takes entries through the filters until one has a response body to write.
+    let mut next_job = |output: &mut Output| {
+        for entry in source.by_ref() {
+            let (index, entry) = match entry {
+                Ok(entry) => entry,
+                Err(skipped) => {
+                    output.unreadable(skipped);
+                    continue;
+                }
+            };
+            output.bar.inc(1);
+            // Only entries loaded as a whole were filtered by range above.
+            if streamed
+                && !entry_ranges.is_empty()
+                && !entry_ranges.iter().any(|x| x.contains(&index))
+            {
+                output.skip(index, &entry.request.url, "entries");
+                continue;
+            }
+            if !hosts.allows(&entry.request.url) {
+                output.skip(index, &entry.request.url, "domain");
+                continue;
+            }
+            if !window.contains(entry.started()) {
+                output.skip(index, &entry.request.url, "time");
+                continue;
+            }
+            output.number = numbers.get(&index).cloned();
+            if entry.request.url.scheme() == "data" {
+                if include_data_urls {
+                    extract_data_url(output, &mime_types, index, &entry.request.url);
+                } else {
+                    output.skip(index, &entry.request.url, "data_url");
+                }
+                continue;
+            }
+            if collapse_redirects && entry.redirect_target().is_some() {
+                output.skip(index, &entry.request.url, "redirect");
+                continue;
+            }
+            let chain = redirect_chains.get(&index);
+            output.redirects = chain
+                .map(|x| x.iter().map(|x| x.to_string()).collect())
+                .unwrap_or_default();
+            output.modified = preserve_times.then(|| served_at(&entry)).flatten();
+            layout.group = match group_by {
+                Some(GroupBy::Page) => entry
+                    .pageref
+                    .as_ref()
+                    .and_then(|x| page_folders.get(x))
+                    .cloned(),
+                Some(GroupBy::Type) => Some(PathBuf::from(type_folder(&essence(
+                    &entry.response.content.mime_type,
+                )))),
+                None => None,
+            };
+            if websockets && !entry.web_socket_messages.is_empty() {
+                extract_web_socket(output, &layout, index, &entry);
+            }
+            if requests {
+                if let Some(post_data) = &entry.request.post_data {
+                    extract_request_body(output, &layout, index, &entry.request.url, post_data);
+                }
+            }
+            if graphql {
+                extract_graphql(output, &layout, &mut graphql_calls, index, &entry);
+            }
+            if !methods
+                .iter()
+                .any(|x| x.eq_ignore_ascii_case(&entry.request.method))
+            {
+                output.skip(index, &entry.request.url, "method");
+                continue;
+            }
+            let essence = essence(&entry.response.content.mime_type);
+            if let Some(ext) = mime_types.get(essence.as_str()) {
+                count_matched += 1;
+                if count_matched <= offset {
+                    output.skip(index, &entry.request.url, "offset");
+                    continue;
+                }
+                if limit.is_some_and(|x| count_matched > offset + x) {
+                    output.skip(index, &entry.request.url, "limit");
+                    continue;
+                }
+                let located_url = chain.and_then(|x| x.first()).unwrap_or(&entry.request.url);
+                let (path, mut file_name) = layout.locate(located_url);
+                if use_content_disposition {
+                    let disposition = entry.response.header("content-disposition");
+                    if let Some(name) = disposition.and_then(disposition_file_name) {
+                        file_name = name;
+                    }
+                }
+                if !mime_type_extensions
+                    .iter()
+                    .any(|x| file_name.ends_with(x as &str))
+                {
+                    file_name.push_str(ext);
+                }
+                return Some(Job {
+                    index,
+                    entry,
+                    path,
+                    file_name,
+                    ext,
+                    essence,
+                    redirects: output.redirects.clone(),
+                    modified: output.modified,
+                    number: output.number.clone(),
+                });
+            } else {
+                output.skip(index, &entry.request.url, "mime_type");
+            }
+        }
+        None
+    };
+    // Response bodies to decode and write once every entry has been looked at.
+    let mut bodies = Vec::new();
+    if !streamed {
+        while let Some(job) = next_job(&mut output) {
+            bodies.push(job);
+        }
+    }
+    let settings = BodySettings {
+        no_decompress,
+        transcode,
+        split_streams,
+        beautify: output.beautify,
+        name_by_hash,
+        #[cfg(feature = "images")]
+        convert,
+        strip_metadata,
+    };
+    if let Some(last_index) = resume_after {
+        // Files after the last one the checkpoint saw are written again, as they may
+        // have been cut off.
+        let Some(position) = bodies.iter().position(|x| x.index == last_index) else {
+            let path = resume
+                .as_deref()
+                .map(Path::to_string_lossy)
+                .unwrap_or_default();
+            let message = tr!(CheckpointMismatch, path, input_file_path.to_string_lossy());
+            return Err(RunError::new(message));
+        };
+        for job in bodies.drain(..=position) {
+            output.count_unchanged += 1;
+            output.skip(job.index, &job.entry.request.url, "checkpoint");
+        }
+        eprintln!("{}", tr!(Resuming, position + 1));
+    }
+    // Only bodies that are going to be written are downloaded.
+    #[cfg(feature = "fetch")]
+    {
+        fetched = if fetch_missing {
+            let timeout = std::time::Duration::from_secs(fetch_timeout);
+            fetch_bodies(&mut output, &mut bodies, fetch_concurrency, timeout)
+        } else {
+            Vec::new()
+        };
+        let positions = bodies
+            .iter()
+            .enumerate()
+            .map(|(position, job)| (job.index, position))
+            .collect::<HashMap<_, _>>();
+        for (index, entry) in &fetched {
+            bodies[positions[index]].entry = Cow::Borrowed(entry);
+        }
+    }
+    // Streamed entries reach the filters as the writing threads make room for them.
+    let mut bodies = bodies.into_iter();
+    write_bodies(
+        &mut output,
+        move |output| bodies.next().or_else(|| next_job(output)),
+        &settings,
+        jobs,
+        |output, job, out_file, bytes| {
+            let (index, entry) = (job.index, &*job.entry);
+            // Only thumbnails need the bytes.
+            #[cfg(not(feature = "images"))]
+            let _ = bytes;
+            #[cfg(feature = "images")]
+            if let Some(gallery) = &mut gallery {
+                let thumbnail = output.sink.root().join(out_file);
+                if let Err(err) = gallery.add(&thumbnail, &job.essence, bytes) {
+                    output.fail(index, &entry.request.url, tr!(CannotCreateThumbnail, err));
+                }
+            }
+            if headers_sidecar {
+                let headers = HeadersSidecar {
+                    url: entry.request.url.as_str(),
+                    method: &entry.request.method,
+                    status: entry.response.status,
+                    request_headers: &entry.request.headers,
+                    request_cookies: &entry.request.cookies,
+                    response_headers: &entry.response.headers,
+                    response_cookies: &entry.response.cookies,
+                };
+                let url = &entry.request.url;
+                write_sidecar(output, index, url, out_file, ".headers.json", &headers);
+            }
+            if let Some(SidecarFormat::Json) = sidecar {
+                let meta = MetaSidecar {
+                    url: entry.request.url.as_str(),
+                    method: &entry.request.method,
+                    status: entry.response.status,
+                    started: &entry.started_date_time,
+                    time: entry.time,
+                    timings: entry.timings.as_ref(),
+                    server_ip_address: entry.server_ip_address.as_deref(),
+                    request_headers: &entry.request.headers,
+                    response_headers: &entry.response.headers,
+                };
+                let url = &entry.request.url;
+                write_sidecar(output, index, url, out_file, ".meta.json", &meta);
+            }
+        },
+    );
+    drop(source);
+    let count_total = match parser {
+        Some(parser) => match parser.join().unwrap_or_else(|x| panic::resume_unwind(x)) {
+            Ok(count) => count,
+            Err(err) => {
+                output.bar.finish_and_clear();
+                // The files written so far are kept track of, as for a finished run.
+                if output.count_extracted > 0 {
+                    let (root, durable) = (output.sink.root(), output.sink.is_durable());
+                    let _ = output.manifest.save(root, durable);
+                }
+                return Err(load_error(err));
+            }
+        },
+        None => count_total,
+    };
+    output.bar.finish_and_clear();
+    #[cfg(feature = "images")]
+    if let Some(gallery) = &gallery {
+        match gallery.finish() {
+            Ok(()) => eprintln!("{}", tr!(GalleryWritten, gallery.index().to_string_lossy())),
+            Err(err) => output.failures.push(Failure {
+                index: None,
+                message: format!("{}: {err}", gallery.index().to_string_lossy()),
+            }),
+        }
+    }
+    output.notify(&Event::Finished {
+        extracted: output.count_extracted,
+        total: count_total,
+    });
+    if let Err(err) = output
+        .manifest
+        .save(output.sink.root(), output.sink.is_durable())
+    {
+        output.failures.push(Failure {
+            index: None,
+            message: format!("{}: {err}", manifest::FILE_NAME),
+        });
+    }
+    if let Some(checkpointer) = &output.checkpointer {
+        let _ = fs::remove_file(&checkpointer.path);
+    }
+    if checksums {
+        let list = output.manifest.checksums();
+        let path = Path::new(manifest::CHECKSUMS_FILE_NAME);
+        if let Err(err) = output.sink.write(path, list.as_bytes()) {
+            output.failures.push(Failure {
+                index: None,
+                message: format!("{}: {err}", manifest::CHECKSUMS_FILE_NAME),
+            });
+        }
+    }
+    #[cfg(feature = "s3")]
+    if let Some(target) = &upload_target {
+        let types = mime_types
+            .iter()
+            .map(|(mime_type, ext)| (ext.to_string(), mime_type.to_string()))
+            .collect();
+        let (uploaded, failures) =
+            crate::upload::upload_folder(target, output.sink.root(), &types, jobs);
+        eprintln!("{}", tr!(Uploaded, uploaded));
+        output.failures.extend(failures);
+        if let Some(staging) = &staging {
+            let _ = fs::remove_dir_all(staging);
+        }
+    }
+    eprintln!("{}", tr!(Finished, output.count_extracted, count_total));
+    if output.count_unchanged > 0 {
+        eprintln!("{}", tr!(UnchangedCount, output.count_unchanged));
+    }
+    if let Some(max_total_size) = output.budget.limit.filter(|_| output.count_over_budget > 0) {
+        let size = human_size(max_total_size);
+        eprintln!("{}", tr!(OverBudgetCount, output.count_over_budget, size));
+    }
+    if output.count_undecodable > 0 {
+        eprintln!("{}", tr!(UndecodableCount, output.count_undecodable));
+    }
+    if !output.failures.is_empty() {
+        eprintln!("{}", tr!(FailureCount, output.failures.len()));
+        if let Some(bundle) = &error_bundle {
+            write_error_bundle(bundle, &input_file_path, &config, &output.failures);
+        }
+    }
+    let status = if !output.failures.is_empty() {
+        exit_code::PARTIAL_FAILURE
+    } else if output.count_extracted + output.count_unchanged + output.count_sources == 0 {
+        exit_code::NOTHING_MATCHED
+    } else {
+        0
+    };
+    if let Some(SummaryFormat::Json) = summary {
+        let summary = serde_json::json!({
+            "extracted": output.count_extracted,
+            "sources": output.count_sources,
+            "unchanged": output.count_unchanged,
+            "failed": output.failures.len(),
+            "undecodable": output.count_undecodable,
+            "over_budget": output.count_over_budget,
+            "total": count_total,
+            "status": status,
+        });
+        println!("{summary}");
+    }
+    Ok(status)
+}
+
+/// A response body that passed the filters, waiting to be decoded and written.
+struct Job<'a> {
+    index: usize,
+    /// Borrowed from the loaded capture, or owned when entries are streamed.
+    entry: Cow<'a, HarLogEntry>,
+    path: Option<PathBuf>,
+    file_name: String,
+    ext: &'a str,
+    essence: String,
+    redirects: Vec<String>,
+    modified: Option<SystemTime>,
+    number: Option<String>,
+}
+
+/// What the decoding threads do to a body besides decoding it.
+struct BodySettings {
+    no_decompress: bool,
+    transcode: bool,
+    split_streams: bool,
+    beautify: bool,
+    name_by_hash: bool,
+    #[cfg(feature = "images")]
+    convert: Vec<crate::images::Conversion>,
+    strip_metadata: bool,
+}
+
+/// A decoded body with its final file name, and what went wrong on the way that did
+/// not keep it from being written.
+struct Body {
+    bytes: Vec<u8>,
+    file_name: String,
+    problems: Vec<String>,
+}
+
+/// Decodes, decompresses and converts a body. Fails only when the body is not valid
+/// base64, which leaves nothing to write.
+fn decode_body(job: &Job, settings: &BodySettings) -> Result<Body, String> {
+    let content = &job.entry.response.content;
+    let mut file_name = job.file_name.clone();
+    let mut problems = Vec::new();
+    // Exporters store text bodies as is unless they declare base64.
+    let is_base64 = content.encoding.as_deref() == Some("base64");
+    let mut b = if !is_base64 && is_textual(&content.mime_type) {
+        content.text.as_bytes().to_vec()
+    } else {
+        har::decode_base64(&content.text).map_err(|err| tr!(CannotDecodeBody, err))?
+    };
+    if !settings.no_decompress {
+        let content_encoding = job.entry.response.header("content-encoding");
+        match decompress::try_decompress(content_encoding, &b) {
+            Ok(Some(decompressed)) => b = decompressed,
+            Ok(None) => {}
+            Err(decompress::TooLarge) => {
+                let limit = human_size(decompress::limit());
+                problems.push(tr!(DecompressedTooLarge, limit));
+            }
+        }
+    }
+    // Text kept as is in the HAR was already decoded by the exporter.
+    if settings.transcode && is_base64 && is_textual(&content.mime_type) {
+        let (transcoded, error) = transcode_utf8(&content.mime_type, &b);
+        b = transcoded;
+        if let Some(charset) = error {
+            problems.push(tr!(CannotTranscodeBody, charset));
+        }
+    }
+    if settings.split_streams {
+        if let Some(lines) = split_stream(&job.essence, &b) {
+            b = lines;
+        }
+    }
+    if settings.beautify {
+        if let Some(beautified) = beautify::beautify(&job.essence, &b) {
+            b = beautified;
+        }
+    }
+    #[cfg(feature = "images")]
+    let ext = match crate::images::convert(&settings.convert, &job.essence, &b) {
+        Some(Ok((converted, converted_ext))) => {
+            b = converted;
+            if let Some((stem, _)) = file_name.rsplit_once('.') {
+                file_name = stem.to_string();
+            }
+            file_name.push_str(&converted_ext);
+            converted_ext
+        }
+        // The original is still extracted, but the run counts as failed.
+        Some(Err(err)) => {
+            problems.push(tr!(CannotConvertImage, err));
+            job.ext.to_string()
+        }
+        None => job.ext.to_string(),
+    };
+    #[cfg(not(feature = "images"))]
+    let ext = job.ext;
+    if settings.strip_metadata {
+        if let Some(stripped) = metadata::strip_metadata(&b) {
+            b = stripped;
+        }
+    }
+    if settings.name_by_hash {
+        file_name = format!("{}{ext}", &manifest::sha256_hex(&b)[..16]);
+    }
+    Ok(Body {
+        bytes: b,
+        file_name,
+        problems,
+    })
+}
+
+/// Reports what went wrong decoding a body, returning it when there is one to write.
+fn take_body(output: &mut Output, job: &Job, body: Result<Body, String>) -> Option<Body> {
+    let url = &job.entry.request.url;
+    match body {
+        Ok(body) => {
+            for problem in &body.problems {
+                output.fail(job.index, url, problem.clone());
+            }
+            Some(body)
+        }
+        Err(message) => {
+            output.count_undecodable += 1;
+            output.fail(job.index, url, message);
+            None
+        }
+    }
+}
+
+/// A file's bytes, given back by the writer thread, and how writing it went.
+type Stored = (Vec<u8>, Result<(), StoreError>);
+
+/// A job given back by a decoding thread with its body.
+type Decoded<'a> = (Job<'a>, Result<Body, String>);
+
+/// A file handed to the writer threads, which send it back once it is on disk.
+struct Store {
+    path: PathBuf,
+    bytes: Vec<u8>,
+    modified: Option<SystemTime>,
+    done: SyncSender<Stored>,
+}
+
+/// A file on its way to disk, reported once its writer thread is done with it.
+struct Pending<'a> {
+    job: Job<'a>,
+    /// The path asked for, before [`Output::plan`] numbers or sanitizes it.
+    name: PathBuf,
+    out_file: PathBuf,
+    /// `None` for a file that is already up to date, which is not written again.
+    sha256: Option<String>,
+    done: Receiver<Stored>,
+}
+
+/// Decodes and writes the response bodies of the jobs `next` gives until it gives
+/// `None`, calling `finish` with each file written or already up to date.
+///
+/// With more than one thread, bodies are decoded on `threads` threads and written on
+/// as many others, while this thread takes jobs from `next`, names the files and
+/// reports them in the order of the jobs. Bounded queues between the stages keep at
+/// most a few jobs and bodies per thread in memory; `next` is only asked for another
+/// job once there is room for it.
+fn write_bodies<'a>(
+    output: &mut Output,
+    mut next: impl FnMut(&mut Output) -> Option<Job<'a>>,
+    settings: &BodySettings,
+    threads: usize,
+    mut finish: impl FnMut(&mut Output, &Job, &Path, &[u8]),
+) {
+    if threads <= 1 {
+        while let Some(job) = next(output) {
+            let job = &job;
+            if let Some(body) = take_body(output, job, decode_body(job, settings)) {
+                output.number = job.number.clone();
+                output.redirects = job.redirects.clone();
+                output.modified = job.modified;
+                output.response_entry = Some(job.index);
+                let url = &job.entry.request.url;
+                let (path, name) = (job.path.as_deref(), &body.file_name);
+                let written = output.write(job.index, url, path, name, &body.bytes);
+                // Files `next` writes for the following entry are not its response.
+                output.response_entry = None;
+                if let Some(out_file) = written {
+                    finish(output, job, &out_file, &body.bytes);
+                }
+            }
+            output.finished_job(job.index);
+        }
+        return;
+    }
+    let bound = threads * 2;
+    let sink = output.sink.clone();
+    // Each job goes to the decoding threads with a channel to send it back on with its
+    // body; this thread keeps the other ends in job order.
+    let (job_tx, job_rx) = mpsc::sync_channel::<(Job, SyncSender<Decoded>)>(bound);
+    let job_rx = Mutex::new(job_rx);
+    let (store_tx, store_rx) = mpsc::sync_channel::<Store>(bound);
+    let store_rx = Mutex::new(store_rx);
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let job_rx = &job_rx;
+            scope.spawn(move || loop {
+                let Ok((job, body_tx)) = job_rx.lock().unwrap().recv() else {
+                    break;
+                };
+                let body = decode_body(&job, settings);
+                let _ = body_tx.send((job, body));
+            });
+        }
+        for _ in 0..threads {
+            let (sink, store_rx) = (&sink, &store_rx);
+            scope.spawn(move || loop {
+                let Ok(store) = store_rx.lock().unwrap().recv() else {
+                    break;
+                };
+                let result = store_file(sink, &store.path, &store.bytes, store.modified);
+                let _ = store.done.send((store.bytes, result));
+            });
+        }
+        let mut pending = VecDeque::<Pending>::new();
+        let mut complete = |output: &mut Output, pending: Pending, (bytes, stored): Stored| {
+            let job = pending.job;
+            let out_file = match pending.sha256 {
+                Some(sha256) => {
+                    let url = &job.entry.request.url;
+                    let written = Written {
+                        out_file: pending.out_file,
+                        size: bytes.len(),
+                        sha256,
+                        redirects: job.redirects.clone(),
+                        entry: Some(job.index),
+                    };
+                    match output.commit(job.index, url, written, stored) {
+                        Some(out_file) => out_file,
+                        None => return,
+                    }
+                }
+                None => pending.out_file,
+            };
+            finish(output, &job, &out_file, &bytes);
+        };
+        // Reports files that are done, first waiting until at most `keep` are in flight.
+        let mut report = |output: &mut Output, pending: &mut VecDeque<Pending>, keep| {
+            while let Some(front) = pending.front() {
+                let stored = if pending.len() > keep {
+                    front.done.recv().ok()
+                } else {
+                    match front.done.try_recv() {
+                        Ok(stored) => Some(stored),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => None,
+                    }
+                };
+                let front = pending.pop_front().unwrap();
+                let index = front.job.index;
+                if let Some(stored) = stored {
+                    complete(output, front, stored);
+                }
+                output.finished_job(index);
+            }
+        };
+        let mut order = VecDeque::<Receiver<Decoded>>::new();
+        let mut exhausted = false;
+        loop {
+            // Fewer than `bound` jobs are queued, so sending one never waits.
+            while !exhausted && order.len() < bound {
+                let Some(job) = next(output) else {
+                    exhausted = true;
+                    break;
+                };
+                let (body_tx, body_rx) = mpsc::sync_channel(1);
+                if job_tx.send((job, body_tx)).is_err() {
+                    break;
+                }
+                order.push_back(body_rx);
+            }
+            let Some(body_rx) = order.pop_front() else {
+                break;
+            };
+            let Ok((job, body)) = body_rx.recv() else {
+                continue;
+            };
+            // Files before this one are reported first, to keep messages in order.
+            if !matches!(&body, Ok(body) if body.problems.is_empty()) {
+                report(output, &mut pending, 0);
+            }
+            let Some(body) = take_body(output, &job, body) else {
+                output.finished_job(job.index);
+                continue;
+            };
+            output.number = job.number.clone();
+            let url = &job.entry.request.url;
+            let (path, name) = (job.path.as_deref(), &body.file_name);
+            // A file written again waits for the earlier write, as it would in turn.
+            let requested = path.unwrap_or(Path::new("")).join(name);
+            if pending.iter().any(|x| x.name == requested) {
+                report(output, &mut pending, 0);
+            }
+            let (done_tx, done_rx) = mpsc::sync_channel(1);
+            let (out_file, sha256) = match output.plan(job.index, url, path, name, &body.bytes) {
+                Plan::Unchanged(out_file) => {
+                    let _ = done_tx.send((body.bytes, Ok(())));
+                    (out_file, None)
+                }
+                Plan::OverBudget => {
+                    report(output, &mut pending, 0);
+                    output.finished_job(job.index);
+                    continue;
+                }
+                Plan::Write(out_file, sha256) => {
+                    let store = Store {
+                        path: out_file.clone(),
+                        bytes: body.bytes,
+                        modified: job.modified,
+                        done: done_tx,
+                    };
+                    if store_tx.send(store).is_err() {
+                        break;
+                    }
+                    (out_file, Some(sha256))
+                }
+            };
+            pending.push_back(Pending {
+                job,
+                name: requested,
+                out_file,
+                sha256,
+                done: done_rx,
+            });
+            report(output, &mut pending, bound);
+        }
+        drop(job_tx);
+        drop(store_tx);
+        report(output, &mut pending, 0);
+    });
+}
+
+/// Writes `value` as pretty JSON to a file named after `out_file` with `suffix` added.
+fn write_sidecar(
+    output: &mut Output,
+    index: usize,
+    url: &Url,
+    out_file: &Path,
+    suffix: &str,
+    value: &impl Serialize,
+) {
+    let mut sidecar_file = out_file.as_os_str().to_os_string();
+    sidecar_file.push(suffix);
+    let json = serde_json::to_vec_pretty(value).unwrap();
+    if let Err(err) = output.write_extra(Path::new(&sidecar_file), &json, Some(url)) {
+        let message = format!("{}: {err}", sidecar_file.to_string_lossy());
+        output.fail(index, url, message);
+    }
+}
+
+/// Downloads the missing bodies of the files about to be written into copies of
+/// their entries, returned with their indices. Files whose bodies could not be
+/// fetched fail and are taken out of `bodies`.
+#[cfg(feature = "fetch")]
+fn fetch_bodies(
+    output: &mut Output,
+    bodies: &mut Vec<Job>,
+    concurrency: usize,
+    timeout: std::time::Duration,
+) -> Vec<(usize, HarLogEntry)> {
+    let mut fetched = bodies
+        .iter()
+        .filter(|x| crate::fetch::is_missing(&x.entry))
+        .map(|x| (x.index, x.entry.clone().into_owned()))
+        .collect::<Vec<_>>();
+    if fetched.is_empty() {
+        return fetched;
+    }
+    let line = tr!(FetchingMissing, fetched.len());
+    output.bar.suspend(|| eprintln!("{line}"));
+    let missing = fetched.iter_mut().map(|(index, entry)| (*index, entry));
+    let errors = crate::fetch::fetch_missing(missing, concurrency, timeout);
+    let mut failed = HashSet::new();
+    for (index, err) in errors {
+        if let Some(job) = bodies.iter().find(|x| x.index == index) {
+            output.fail(index, &job.entry.request.url, tr!(CannotFetchBody, err));
+        }
+        failed.insert(index);
+    }
+    bodies.retain(|x| !failed.contains(&x.index));
+    fetched.retain(|(index, _)| !failed.contains(index));
+    fetched
+}
+
+pub fn percent_decode(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        // `from_str_radix` alone would also take a sign, as in `%+1`.
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|x| x.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|x| std::str::from_utf8(x).ok())
+            .and_then(|x| u8::from_str_radix(x, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(x)) => {
+                result.push(x);
+                i += 3;
+            }
+            (x, _) => {
+                result.push(x);
+                i += 1;
+            }
+        }
+    }
+    result
+}
+
+/// Splits a `data:` URL into its media type (`text/plain` when omitted) and content.
+fn decode_data_url(url: &Url) -> Option<(String, Vec<u8>)> {
+    let (header, data) = url.path().split_once(',')?;
+    let (media_type, base64) = match header.strip_suffix(";base64") {
+        Some(media_type) => (media_type, true),
+        None => (header, false),
+    };
+    let media_type = match media_type.split(';').next().unwrap_or_default().trim() {
+        "" => "text/plain".to_string(),
+        x => x.to_ascii_lowercase(),
+    };
+    let data = if base64 {
+        har::decode_base64(percent_decode(data)).ok()?
+    } else {
+        percent_decode(data)
+    };
+    Some((media_type, data))
+}
+
+/// Writes an inline `data:` resource as `_data/<hash><ext>`, named after its content
+/// since the URL carries no name.
+fn extract_data_url(
+    output: &mut Output,
+    mime_types: &HashMap<&str, &str>,
+    index: usize,
+    url: &Url,
+) {
+    let Some((media_type, data)) = decode_data_url(url) else {
+        output.count_undecodable += 1;
+        output.fail(index, url, tr!(CannotDecodeBody, "data:"));
+        return;
+    };
+    let Some(ext) = mime_types.get(media_type.as_str()) else {
+        output.skip(index, url, "mime_type");
+        return;
+    };
+    let file_name = format!("{}{ext}", &manifest::sha256_hex(&data)[..16]);
+    output.write(index, url, Some(Path::new("_data")), &file_name, &data);
+}
+
+/// Writes the sources embedded in every source map of the capture under `src/`.
+fn unpack_sources(
+    output: &mut Output,
+    entries: &[(usize, HarLogEntry)],
+    hosts: &HostFilter,
+    decompress: bool,
+) {
+    let mut count_maps = 0;
+    for (index, entry) in entries {
+        let url = &entry.request.url;
+        if !hosts.allows(url) {
+            continue;
+        }
+        let Some(mut body) = entry.response.content.decoded() else {
+            continue;
+        };
+        if decompress {
+            if let Some(decompressed) =
+                decompress::decompress(entry.response.header("content-encoding"), &body)
+            {
+                body = decompressed;
+            }
+        }
+        let map = if url.path().ends_with(".map") {
+            Some(body)
+        } else if ["css", "javascript"]
+            .iter()
+            .any(|x| entry.response.content.essence().ends_with(x))
+        {
+            std::str::from_utf8(&body)
+                .ok()
+                .and_then(sourcemap::mapping_url)
+                .and_then(sourcemap::decode_inline)
+        } else {
+            None
+        };
+        let Some(sources) = map.as_deref().and_then(sourcemap::embedded_sources) else {
+            continue;
+        };
+        count_maps += 1;
+        for (path, content) in sources {
+            let Some(file_name) = path.file_name().map(|x| x.to_string_lossy()) else {
+                continue;
+            };
+            let folder = Path::new("src").join(path.parent().unwrap_or(Path::new("")));
+            let count_extracted = output.count_extracted;
+            if output
+                .write(*index, url, Some(&folder), &file_name, content.as_bytes())
+                .is_some()
+            {
+                output.count_sources += 1;
+            }
+            output.count_extracted = count_extracted;
+        }
+    }
+    output
+        .bar
+        .suspend(|| eprintln!("{}", tr!(SourcesUnpacked, output.count_sources, count_maps)));
+}
+
+/// A stream found in the capture, with its segments in playback order.
+struct MediaStream<'a> {
+    /// The playlist or manifest entry, which the joined file is recorded under.
+    index: usize,
+    url: &'a Url,
+    name: String,
+    ext: &'static str,
+    init: Option<Url>,
+    /// By sequence number, as live playlists list a moving window of segments.
+    segments: BTreeMap<u64, Url>,
+}
+
+/// Position in `streams` of the stream of this playlist and representation, added
+/// when first seen.
+fn add_stream<'a>(
+    streams: &mut Vec<MediaStream<'a>>,
+    positions: &mut HashMap<(String, String), usize>,
+    index: usize,
+    url: &'a Url,
+    id: &str,
+    ext: &'static str,
+) -> usize {
+    let key = (url[..url::Position::AfterQuery].to_string(), id.to_string());
+    *positions.entry(key).or_insert_with(|| {
+        let stem = url
+            .path_segments()
+            .and_then(|mut x| x.next_back())
+            .map(|x| x.rsplit_once('.').map_or(x, |y| y.0))
+            .filter(|x| !x.is_empty())
+            .unwrap_or("stream");
+        let name = match id {
+            "" => stem.to_string(),
+            id => format!("{stem}_{id}"),
+        };
+        streams.push(MediaStream {
+            index,
+            url,
+            name: winpath::sanitize_component(&name),
+            ext,
+            init: None,
+            segments: BTreeMap::new(),
+        });
+        streams.len() - 1
+    })
+}
+
+/// Writes one file per HLS or DASH stream of the capture under `streams/`, made of its
+/// captured segments in playlist order.
+fn join_streams(
+    output: &mut Output,
+    entries: &[(usize, HarLogEntry)],
+    hosts: &HostFilter,
+    decompress: bool,
+) {
+    let body = |entry: &HarLogEntry| {
+        let body = entry.response.content.decoded()?;
+        if !decompress {
+            return Some(body);
+        }
+        let encoding = entry.response.header("content-encoding");
+        Some(decompress::decompress(encoding, &body).unwrap_or(body))
+    };
+    let key = |url: &Url| url[..url::Position::AfterQuery].to_string();
+    // The last successful response for each URL, as segments may have been retried.
+    let responses = entries
+        .iter()
+        .filter(|(_, x)| (200..300).contains(&x.response.status))
+        .map(|(_, x)| (key(&x.request.url), x))
+        .collect::<HashMap<_, _>>();
+    let mut streams = Vec::<MediaStream>::new();
+    // Live playlists and manifests are loaded again and again; their reloads add to
+    // the same stream.
+    let mut positions = HashMap::<(String, String), usize>::new();
+    let mut encrypted = Vec::new();
+    for (index, entry) in entries {
+        let url = &entry.request.url;
+        let essence = entry.response.content.essence().to_ascii_lowercase();
+        let is_hls = essence.ends_with("mpegurl") || url.path().ends_with(".m3u8");
+        let is_dash = essence == "application/dash+xml" || url.path().ends_with(".mpd");
+        if !(is_hls || is_dash) || !hosts.allows(url) {
+            continue;
+        }
+        let Some(text) = body(entry).and_then(|x| String::from_utf8(x).ok()) else {
+            continue;
+        };
+        if is_hls {
+            let Some(playlist) = playlist::parse_hls(url, &text) else {
+                continue;
+            };
+            if playlist.encrypted {
+                encrypted.push((*index, url));
+                continue;
+            }
+            let ext = match playlist.segments.first().map(Url::path) {
+                _ if playlist.init.is_some() => ".mp4",
+                Some(x) if x.ends_with(".m4s") || x.ends_with(".mp4") => ".mp4",
+                Some(x) if x.ends_with(".aac") => ".aac",
+                _ => ".ts",
+            };
+            let position = add_stream(&mut streams, &mut positions, *index, url, "", ext);
+            let found = &mut streams[position];
+            found.init = playlist.init.or(found.init.take());
+            for (i, segment) in playlist.segments.into_iter().enumerate() {
+                found.segments.insert(playlist.sequence + i as u64, segment);
+            }
+        } else {
+            for representation in playlist::parse_dash(url, &text) {
+                let ext = representation.extension();
+                let id = &representation.id;
+                let position = add_stream(&mut streams, &mut positions, *index, url, id, ext);
+                let found = &mut streams[position];
+                found.init = representation.init.or(found.init.take());
+                match representation.segments {
+                    DashSegments::List(segments) => {
+                        for (i, segment) in segments.into_iter().enumerate() {
+                            found.segments.insert(i as u64, segment);
+                        }
+                    }
+                    DashSegments::Template(pattern) => {
+                        for entry in responses.values() {
+                            let url = &entry.request.url;
+                            let number = pattern
+                                .captures(url.as_str())
+                                .and_then(|x| x[1].parse().ok());
+                            if let Some(number) = number {
+                                found.segments.insert(number, url.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    encrypted.dedup_by_key(|(_, x)| key(x));
+    for (index, url) in encrypted {
+        output.skip(index, url, "encrypted");
+        output
+            .bar
+            .suspend(|| eprintln!("{}", tr!(StreamEncrypted, url)));
+    }
+    let mut count_streams = 0;
+    let mut names = HashSet::new();
+    for found in &streams {
+        let mut bytes = Vec::new();
+        let mut missing = 0;
+        let parts = found.init.iter().chain(found.segments.values());
+        for part in parts {
+            match responses.get(&key(part)).and_then(|x| body(x)) {
+                Some(body) => bytes.extend(body),
+                None => missing += 1,
+            }
+        }
+        let total = found.segments.len() + found.init.iter().count();
+        if missing == total {
+            output.skip(found.index, found.url, "no segments");
+            continue;
+        }
+        let mut name = found.name.clone();
+        let mut n = 1;
+        while !names.insert(name.clone()) {
+            n += 1;
+            name = format!("{}~{n}", found.name);
+        }
+        let file_name = format!("{name}{}", found.ext);
+        let folder = Path::new("streams");
+        if output
+            .write(found.index, found.url, Some(folder), &file_name, &bytes)
+            .is_some()
+        {
+            count_streams += 1;
+        }
+        if missing > 0 {
+            let line = tr!(StreamIncomplete, file_name, missing, total);
+            output.bar.suspend(|| eprintln!("{line}"));
+        }
+    }
+    output
+        .bar
+        .suspend(|| eprintln!("{}", tr!(StreamsAssembled, count_streams)));
+}
+
+/// For each entry reached through redirects, the URLs that led to it, starting with
+/// the one originally requested.
+fn redirect_chains(entries: &[(usize, HarLogEntry)]) -> HashMap<usize, Vec<Url>> {
+    let redirected_from = entries
+        .iter()
+        .filter_map(|(_, entry)| Some((entry.redirect_target()?, &entry.request.url)))
+        .rev()
+        .collect::<HashMap<_, _>>();
+    entries
+        .iter()
+        .filter(|(_, entry)| entry.redirect_target().is_none())
+        .filter_map(|(index, entry)| {
+            let mut chain = Vec::new();
+            let mut url = &entry.request.url;
+            while let Some(from) = redirected_from.get(url) {
+                // A redirect loop ends the chain where it first repeats.
+                if *from == &entry.request.url || chain.contains(*from) {
+                    break;
+                }
+                chain.push((*from).clone());
+                url = from;
+            }
+            chain.reverse();
+            (!chain.is_empty()).then_some((*index, chain))
+        })
+        .collect()
+}
+
+/// Zero-padded position of each entry when sorted by `startedDateTime`, to prefix
+/// file names with. Entries started at the same time keep their relative order.
+fn load_order_numbers(entries: &[(usize, HarLogEntry)]) -> HashMap<usize, String> {
+    let mut started = entries
+        .iter()
+        .map(|(index, entry)| (entry.started(), *index))
+        .collect::<Vec<_>>();
+    started.sort();
+    let width = started.len().to_string().len().max(4);
+    started
+        .into_iter()
+        .enumerate()
+        .map(|(position, (_, index))| (index, format!("{:0width$}", position + 1)))
+        .collect()
+}
+
+/// When the response was served, preferring the resource's own `Last-Modified`.
+fn served_at(entry: &HarLogEntry) -> Option<SystemTime> {
+    entry
+        .response
+        .header("last-modified")
+        .and_then(|x| DateTime::parse_from_rfc2822(x).ok())
+        .or_else(|| entry.started())
+        .map(SystemTime::from)
+}
+
+fn write_error_bundle(bundle: &Path, input_file_path: &Path, config: &str, failures: &[Failure]) {
+    match bundle::write(bundle, input_file_path, config, failures) {
+        Ok(()) => eprintln!("{}", tr!(ErrorBundleWritten, bundle.to_string_lossy())),
+        Err(err) => eprintln!(
+            "{}",
+            tr!(CannotWriteErrorBundle, bundle.to_string_lossy(), err)
+        ),
+    }
+}
+
+/// `text` made into a folder name: characters that file systems reject replaced,
+/// at most 100 characters long, and without leading or trailing dots and spaces.
+fn folder_name(text: &str) -> String {
+    let name = text
+        .nfc()
+        .map(|x| {
+            if x.is_control() || r#"/\:*?"<>|"#.contains(x) {
+                '_'
+            } else {
+                x
+            }
+        })
+        .take(100)
+        .collect::<String>();
+    name.trim().trim_matches('.').to_string()
+}
+
+/// A page URL as a folder name, such as `example.com_docs_intro`.
+fn url_folder_name(url: &Url) -> String {
+    let name = format!("{}{}", url.host_str().unwrap_or_default(), url.path());
+    let name = String::from_utf8(percent_decode(&name)).unwrap_or(name);
+    folder_name(name.trim_end_matches('/'))
+}
+
+/// Folder names for pages, from their titles, ids or URLs as `naming` asks. Pages
+/// sharing a name get their id appended.
+fn page_folders(
+    pages: &[HarPage],
+    entries: &[HarLogEntry],
+    naming: PageName,
+) -> HashMap<String, PathBuf> {
+    // Exporters without a title for the page often put its URL there.
+    let title_url = |page: &HarPage| Url::parse(&page.title).ok().filter(|x| x.has_host());
+    let mut first_urls = HashMap::new();
+    for entry in entries {
+        if let Some(pageref) = &entry.pageref {
+            first_urls
+                .entry(pageref.as_str())
+                .or_insert(&entry.request.url);
+        }
+    }
+    let name = |page: &HarPage| {
+        let name = match naming {
+            PageName::Title => match title_url(page) {
+                Some(url) => url_folder_name(&url),
+                None => folder_name(&page.title),
+            },
+            PageName::Id => folder_name(&page.id),
+            PageName::Url => title_url(page)
+                .as_ref()
+                .or(first_urls.get(page.id.as_str()).copied())
+                .map(url_folder_name)
+                .unwrap_or_default(),
+        };
+        if name.is_empty() {
+            page.id.clone()
+        } else {
+            name
+        }
+    };
+    let mut counts = HashMap::<String, usize>::new();
+    pages
+        .iter()
+        .for_each(|x| *counts.entry(name(x)).or_default() += 1);
+    pages
+        .iter()
+        .map(|page| {
+            let mut folder = name(page);
+            if counts[&folder] > 1 && folder != page.id {
+                folder = format!("{folder} ({})", page.id);
+            }
+            (page.id.clone(), PathBuf::from(folder))
+        })
+        .collect()
+}
+
+/// Asks before extracting into a folder that has files but no manifest, so mistyping
+/// the output folder as `~` does not spray thousands of files into it. Without a
+/// terminal to ask on, the extraction is refused.
+fn check_foreign_folder(folder: &Path) -> Result<(), RunError> {
+    let has_files = fs::read_dir(folder).is_ok_and(|mut x| x.next().is_some());
+    if !has_files || Manifest::exists(folder) {
+        return Ok(());
+    }
+    if io::stdin().is_terminal() && io::stderr().is_terminal() {
+        eprint!("{} ", tr!(ConfirmForeignFolder, folder.to_string_lossy()));
+        let mut answer = String::new();
+        let _ = io::stdin().read_line(&mut answer);
+        let answer = answer.trim().to_lowercase();
+        if ["y", "yes", "д", "да"].contains(&answer.as_str()) {
+            return Ok(());
+        }
+    }
+    Err(RunError::new(tr!(ForeignFolder, folder.to_string_lossy())))
+}
+
+/// Folder grouping responses of a MIME type with the other assets of the same kind.
+fn type_folder(essence: &str) -> &'static str {
+    let (kind, subtype) = essence.split_once('/').unwrap_or((essence, ""));
+    match (kind, subtype) {
+        ("image", _) => "images",
+        ("font", _) | ("application", "vnd.ms-fontobject") => "fonts",
+        ("application", x) if x.starts_with("font-") || x.starts_with("x-font-") => "fonts",
+        ("text" | "application", "javascript" | "x-javascript" | "ecmascript")
+        | ("application", "wasm") => "scripts",
+        ("text", "css") => "styles",
+        ("audio" | "video", _) => "media",
+        _ => "other",
+    }
+}
+
+/// Host allow and deny lists; an empty allow list allows every host.
+struct HostFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl HostFilter {
+    fn allows(&self, url: &Url) -> bool {
+        let host = url.host_str().unwrap_or_default();
+        (self.include.is_empty() || self.include.iter().any(|x| wildcard_match(x, host)))
+            && !self.exclude.iter().any(|x| wildcard_match(x, host))
+    }
+}
+
+/// Domain folder of URLs without a host, such as `file:` and `about:` URLs.
+pub const NO_HOST_FOLDER: &str = "_no_host";
+
+/// Which parts of the URL are mirrored as subfolders of the output folder.
+struct Layout {
+    domain: bool,
+    path: bool,
+    /// How many folders of the URL path are mirrored: the first ones if positive, the
+    /// last ones if negative and all of them if 0.
+    path_depth: i32,
+    /// Folder of the current entry's group, above the URL-derived ones.
+    group: Option<PathBuf>,
+    query_names: QueryNames,
+    /// Percent-decode the folder and file names taken from the URL.
+    decode_names: bool,
+    /// With `--flatten`, the URL each file name in a domain folder was given to, so
+    /// files of other URLs get a name of their own.
+    flattened: Option<RefCell<HashMap<PathBuf, String>>>,
+}
+
+impl Layout {
+    /// Splits an URL into the subfolder its file goes to and the URL's last path segment,
+    /// `index` when that is empty.
+    fn locate(&self, url: &Url) -> (Option<PathBuf>, String) {
+        let url_host = url.host_str().unwrap_or(NO_HOST_FOLDER);
+        // `about:blank` and `blob:https://...` have an opaque path rather than segments.
+        let mut url_segments = url
+            .path_segments()
+            .map(|x| x.map(Cow::Borrowed).collect::<Vec<_>>())
+            .unwrap_or_else(|| {
+                vec![Cow::Borrowed(
+                    url.path().rsplit('/').next().unwrap_or_default(),
+                )]
+            });
+        if self.decode_names {
+            for segment in &mut url_segments {
+                if let Some(decoded) = decode_name(segment) {
+                    *segment = Cow::Owned(decoded);
+                }
+            }
+        }
+        let url_path = &url_segments[..url_segments.len() - 1];
+        let depth = (self.path_depth.unsigned_abs() as usize).min(url_path.len());
+        let url_path = match self.path_depth {
+            0 => url_path,
+            x if x > 0 => &url_path[..depth],
+            _ => &url_path[url_path.len() - depth..],
+        };
+        let mut url_filename = url_segments[url_segments.len() - 1].to_string();
+        // `https://a.com/` and `https://a.com/docs/` name a folder's index page.
+        if url_filename.is_empty() {
+            url_filename.push_str("index");
+        }
+        if let Some(query) = url.query().filter(|x| !x.is_empty()) {
+            let suffix = match self.query_names {
+                QueryNames::Drop => None,
+                QueryNames::Append => Some(
+                    query
+                        .chars()
+                        .map(|x| match x {
+                            '=' => '-',
+                            x if x.is_ascii_alphanumeric() || x == '-' => x,
+                            _ => '_',
+                        })
+                        .take(64)
+                        .collect::<String>(),
+                ),
+                QueryNames::Hash => Some(manifest::sha256_hex(query.as_bytes())[..8].to_string()),
+            };
+            if let Some(suffix) = suffix {
+                // The suffix goes before the extension so type detection keeps working.
+                url_filename = match url_filename.rsplit_once('.') {
+                    Some((stem, ext)) if !stem.is_empty() => format!("{stem}_{suffix}.{ext}"),
+                    _ => format!("{url_filename}_{suffix}"),
+                };
+            }
+        }
+        let path = if self.domain && self.path {
+            let mut result = PathBuf::from_str(url_host).unwrap();
+            url_path
+                .iter()
+                .for_each(|x| result.extend(Path::new(x.as_ref())));
+            Some(result)
+        } else if self.domain {
+            Some(PathBuf::from_str(url_host).unwrap())
+        } else if self.path {
+            let mut result = PathBuf::new();
+            url_path
+                .iter()
+                .for_each(|x| result.extend(Path::new(x.as_ref())));
+            Some(result)
+        } else {
+            None
+        };
+        let path = match (&self.group, path) {
+            (Some(group), path) => Some(group.join(path.unwrap_or_default())),
+            (None, path) => path,
+        };
+        if let Some(flattened) = &self.flattened {
+            let source = url[..url::Position::AfterQuery].to_string();
+            let folder = path.clone().unwrap_or_default();
+            let mut flattened = flattened.borrow_mut();
+            let base_name = url_filename.clone();
+            let mut n = 1;
+            loop {
+                match flattened.get(&folder.join(&url_filename)) {
+                    Some(taken) if *taken != source => {
+                        n += 1;
+                        url_filename = match base_name.rsplit_once('.') {
+                            Some((stem, ext)) if !stem.is_empty() => format!("{stem}~{n}.{ext}"),
+                            _ => format!("{base_name}~{n}"),
+                        };
+                    }
+                    Some(_) => break,
+                    None => {
+                        flattened.insert(folder.join(&url_filename), source);
+                        break;
+                    }
+                }
+            }
+        }
+        (path, url_filename)
+    }
+}
+
+/// A percent-encoded URL segment as a Unicode name in NFC, the form macOS and most
+/// editors expect. `None` when there is nothing to decode, when the bytes are not
+/// UTF-8, or when decoding would give a separator, a control character or a name such
+/// as `..`.
+fn decode_name(segment: &str) -> Option<String> {
+    if !segment.contains('%') {
+        return None;
+    }
+    let decoded = String::from_utf8(percent_decode(segment)).ok()?;
+    let unsafe_name = matches!(decoded.as_str(), "" | "." | "..")
+        || decoded.contains(['/', '\\'])
+        || decoded.chars().any(char::is_control);
+    (!unsafe_name).then(|| decoded.nfc().collect())
+}
+
+/// Where extracted files go, together with everyone interested in what gets written.
+struct Output {
+    sink: DirSink,
+    observers: Vec<Box<dyn Observer>>,
+    /// Run on every file once it is written.
+    handlers: Vec<Box<dyn EntryHandler>>,
+    count_extracted: usize,
+    /// Files unpacked from source maps, which are not entries of the capture and so
+    /// not counted as extracted.
+    count_sources: usize,
+    failures: Vec<Failure>,
+    /// Hidden unless stdout is a terminal; lines are printed around it.
+    bar: ProgressBar,
+    quiet: bool,
+    verbose: bool,
+    /// Modification time given to files of the current entry.
+    modified: Option<SystemTime>,
+    /// Files written by this and earlier runs into the same folder.
+    manifest: Manifest,
+    /// Write files even when the manifest says they are up to date.
+    force: bool,
+    count_unchanged: usize,
+    /// Bodies that were not valid base64 even when read tolerantly.
+    count_undecodable: usize,
+    /// Bytes written and allowed by `--max-total-size`.
+    budget: Budget,
+    /// Files left out because they did not fit in the budget.
+    count_over_budget: usize,
+    /// Reformat JSON, CSS and JavaScript bodies before writing them.
+    beautify: bool,
+    /// Redirect chain of the current entry, recorded in the manifest.
+    redirects: Vec<String>,
+    /// Set while response bodies are written, to the entry whose body is written.
+    response_entry: Option<usize>,
+    /// Set when paths are made valid on Windows.
+    windows_paths: Option<CaseFolder>,
+    /// The output root is an extended path, so paths need no shortening.
+    long_paths: bool,
+    /// Load order number of the current entry, put in front of its file names.
+    number: Option<String>,
+    /// Saves the progress of the run, with `--checkpoint` or `--resume`.
+    checkpointer: Option<Checkpointer>,
+}
+
+impl Output {
+    fn fail(&mut self, index: usize, url: &Url, message: String) {
+        self.bar
+            .suspend(|| eprintln!("{}", tr!(EntryFailed, url, message)));
+        self.notify(&Event::Failed {
+            index,
+            url: url.as_str(),
+            error: &message,
+        });
+        self.failures.push(Failure {
+            index: Some(index),
+            message,
+        });
+    }
+
+    /// Writes a file that is no body of an entry, such as a sidecar or `cookies.txt`,
+    /// and records it in the manifest so checksums, `relayout` and `pack` see it.
+    fn write_extra(&mut self, path: &Path, bytes: &[u8], url: Option<&Url>) -> io::Result<()> {
+        self.sink.write(path, bytes)?;
+        let file = ManifestFile {
+            size: bytes.len() as u64,
+            sha256: manifest::sha256_hex(bytes),
+            url: url.map(Url::to_string),
+            entry: None,
+            redirects: Vec::new(),
+        };
+        self.manifest.record(path, file);
+        Ok(())
+    }
+
+    /// Reports an entry that could not be read, which counts as a failure.
+    fn unreadable(&mut self, skipped: SkippedEntry) {
+        let SkippedEntry { index, reason } = skipped;
+        self.bar
+            .suspend(|| eprintln!("{}", tr!(EntryUnreadable, index, reason)));
+        let message = format!("unreadable: {reason}");
+        self.notify(&Event::Failed {
+            index,
+            url: "",
+            error: &message,
+        });
+        self.failures.push(Failure {
+            index: Some(index),
+            message,
+        });
+    }
+
+    fn skip(&mut self, index: usize, url: &Url, reason: &str) {
+        if self.verbose {
+            self.bar
+                .suspend(|| eprintln!("{}", tr!(EntrySkipped, url, reason)));
+        }
+        self.notify(&Event::Skipped {
+            index,
+            url: url.as_str(),
+            reason,
+        });
+    }
+
+    /// Notes that the job of entry `index`, and every one before it, is done, saving
+    /// a checkpoint if the last one is old enough.
+    fn finished_job(&mut self, index: usize) {
+        let Some(checkpointer) = &mut self.checkpointer else {
+            return;
+        };
+        if checkpointer.saved.elapsed() < CHECKPOINT_INTERVAL {
+            return;
+        }
+        checkpointer.saved = Instant::now();
+        if let Err(err) = checkpointer.save(index, &self.manifest) {
+            let message = format!("{}: {err}", checkpointer.path.to_string_lossy());
+            self.bar.suspend(|| eprintln!("{message}"));
+        }
+    }
+
+    fn notify(&mut self, event: &Event) {
+        self.observers.iter_mut().for_each(|x| x.notify(event));
+    }
+
+    fn write(
+        &mut self,
+        index: usize,
+        url: &Url,
+        path: Option<&Path>,
+        file_name: &str,
+        bytes: &[u8],
+    ) -> Option<PathBuf> {
+        let (out_file, sha256) = match self.plan(index, url, path, file_name, bytes) {
+            Plan::Unchanged(out_file) => return Some(out_file),
+            Plan::OverBudget => return None,
+            Plan::Write(out_file, sha256) => (out_file, sha256),
+        };
+        let stored = store_file(&self.sink, &out_file, bytes, self.modified);
+        let written = Written {
+            out_file,
+            size: bytes.len(),
+            sha256,
+            redirects: self.redirects.clone(),
+            entry: self.response_entry,
+        };
+        self.commit(index, url, written, stored)
+    }
+
+    /// Picks the path a file is written to, and whether it needs writing at all.
+    fn plan(
+        &mut self,
+        index: usize,
+        url: &Url,
+        path: Option<&Path>,
+        file_name: &str,
+        bytes: &[u8],
+    ) -> Plan {
+        let file_name = match &self.number {
+            Some(number) => format!("{number}_{file_name}"),
+            None => file_name.to_string(),
+        };
+        let mut out_file = path.unwrap_or(Path::new("")).join(file_name);
+        if let Some(case_folder) = &mut self.windows_paths {
+            out_file = winpath::sanitize(&out_file);
+            if !self.long_paths {
+                out_file = winpath::fit_length(self.sink.root(), &out_file);
+            }
+            out_file = case_folder.claim(&out_file);
+        }
+        let size = bytes.len() as u64;
+        let sha256 = manifest::sha256_hex(bytes);
+        if !self.force
+            && self
+                .manifest
+                .is_current(self.sink.root(), &out_file, size, &sha256)
+        {
+            self.count_unchanged += 1;
+            self.skip(index, url, "unchanged");
+            return Plan::Unchanged(out_file);
+        }
+        if !self.budget.take(size) {
+            self.count_over_budget += 1;
+            self.skip(index, url, "max_total_size");
+            return Plan::OverBudget;
+        }
+        Plan::Write(out_file, sha256)
+    }
+
+    /// Records and reports a file once [`store_file`] is done with it, returning its
+    /// path unless it could not be written.
+    fn commit(
+        &mut self,
+        index: usize,
+        url: &Url,
+        written: Written,
+        stored: Result<(), StoreError>,
+    ) -> Option<PathBuf> {
+        let Written {
+            out_file,
+            size,
+            sha256,
+            redirects,
+            entry,
+        } = written;
+        if let Err(StoreError::Write(err)) = &stored {
+            let message = if OutsideRoot::is(err) {
+                tr!(PathOutsideOutput, out_file.to_string_lossy())
+            } else {
+                format!("{}: {err}", out_file.to_string_lossy())
+            };
+            self.fail(index, url, message);
+            self.budget.give_back(size as u64);
+            return None;
+        }
+        let file = ManifestFile {
+            size: size as u64,
+            sha256,
+            url: Some(url.to_string()),
+            entry,
+            redirects,
+        };
+        self.manifest.record(&out_file, file);
+        if let Err(StoreError::Modified(err)) = stored {
+            let message = format!("{}: {err}", out_file.to_string_lossy());
+            self.fail(index, url, message);
+        }
+        if !self.quiet {
+            let folder = out_file.parent().filter(|x| !x.as_os_str().is_empty());
+            let line = tr!(
+                Extracted,
+                out_file.file_name().unwrap_or_default().to_string_lossy(),
+                folder.unwrap_or(self.sink.root()).to_string_lossy(),
+                size
+            );
+            self.bar.suspend(|| println!("{line}"));
+        }
+        self.count_extracted += 1;
+        self.notify(&Event::Extracted {
+            index,
+            url: url.as_str(),
+            path: &out_file.to_string_lossy(),
+            bytes: size,
+        });
+        let on_disk = self.sink.root().join(&out_file);
+        let errors = self
+            .handlers
+            .iter_mut()
+            .filter_map(|x| x.handle(index, url, &on_disk).err())
+            .collect::<Vec<_>>();
+        for err in errors {
+            let message = tr!(HandlerFailed, out_file.to_string_lossy(), err);
+            self.fail(index, url, message);
+        }
+        Some(out_file)
+    }
+}
+
+/// How often `--checkpoint` saves the progress.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Progress of a run saved by `--checkpoint`, for `--resume`.
+#[derive(Deserialize)]
+struct Checkpoint {
+    /// The HAR file being extracted.
+    input: String,
+    /// How many entries it has, to notice when it was replaced.
+    entries: usize,
+    /// Index of the last entry whose files were written; jobs run in a fixed order
+    /// for the same input and options.
+    last_index: usize,
+    /// Files written so far, which the manifest only lists once the run finishes.
+    manifest: Manifest,
+}
+
+impl Checkpoint {
+    fn load(path: &Path) -> io::Result<Checkpoint> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+}
+
+/// Writes the [`Checkpoint`] of the current run.
+struct Checkpointer {
+    path: PathBuf,
+    input: String,
+    entries: usize,
+    saved: Instant,
+}
+
+impl Checkpointer {
+    fn save(&self, last_index: usize, manifest: &Manifest) -> io::Result<()> {
+        let checkpoint = serde_json::json!({
+            "input": self.input,
+            "entries": self.entries,
+            "last_index": last_index,
+            "manifest": manifest,
+        });
+        sink::write_atomic(&self.path, checkpoint.to_string().as_bytes(), false)
+    }
+}
+
+enum Plan {
+    /// The manifest says the file is up to date, so it is left as is.
+    Unchanged(PathBuf),
+    /// Writing the file would go over `--max-total-size`.
+    OverBudget,
+    /// The file needs writing; the hash of its content is recorded once it is.
+    Write(PathBuf, String),
+}
+
+/// Bytes the files of a run take, within `--max-total-size` when it is given. Files
+/// take their size when they are planned rather than once written, so that files
+/// written in parallel cannot go over the limit together.
+struct Budget {
+    limit: Option<u64>,
+    /// Bytes of the files written or being written.
+    taken: u64,
+}
+
+impl Budget {
+    /// Takes `size` bytes, unless that would go over the limit.
+    fn take(&mut self, size: u64) -> bool {
+        if self.limit.is_some_and(|x| self.taken + size > x) {
+            return false;
+        }
+        self.taken += size;
+        true
+    }
+
+    /// Gives back the bytes of a file that could not be written.
+    fn give_back(&mut self, size: u64) {
+        self.taken -= size;
+    }
+}
+
+/// A file that was handed to [`store_file`], to be recorded in the manifest.
+struct Written {
+    out_file: PathBuf,
+    size: usize,
+    sha256: String,
+    redirects: Vec<String>,
+    /// Entry whose response body the file is.
+    entry: Option<usize>,
+}
+
+enum StoreError {
+    /// The file was not written.
+    Write(io::Error),
+    /// The file was written, but its modification time could not be set.
+    Modified(io::Error),
+}
+
+/// Writes a file and gives it its modification time; safe to run on several threads.
+fn store_file(
+    sink: &DirSink,
+    path: &Path,
+    bytes: &[u8],
+    modified: Option<SystemTime>,
+) -> Result<(), StoreError> {
+    sink.write_file(path, bytes).map_err(StoreError::Write)?;
+    if let Some(time) = modified {
+        sink.set_modified(path, time)
+            .map_err(StoreError::Modified)?;
+    }
+    Ok(())
+}
+
+fn request_body_extension(mime_type: &str) -> &'static str {
+    match mime_type.split(';').next().unwrap_or("").trim() {
+        "application/json" => ".json",
+        "application/x-www-form-urlencoded" => ".form",
+        "text/plain" => ".txt",
+        "application/xml" | "text/xml" => ".xml",
+        _ => ".bin",
+    }
+}
+
+/// Only the last component of a client-supplied file name is kept, so parts cannot
+/// escape the folder they are extracted to.
+fn part_file_name(name: &str) -> Option<String> {
+    Path::new(name)
+        .file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .filter(|x| !x.is_empty())
+}
+
+/// File name suggested by a `Content-Disposition` header, preferring the RFC 5987
+/// `filename*` form, which can carry non-ASCII names. Leading dots are dropped so a
+/// server cannot name a hidden file such as the manifest.
+fn disposition_file_name(value: &str) -> Option<String> {
+    let name = multipart::header_param(value, "filename*")
+        .and_then(|x| {
+            let (charset, rest) = x.split_once('\'')?;
+            let (_, encoded) = rest.split_once('\'')?;
+            let bytes = percent_decode(encoded);
+            if charset.eq_ignore_ascii_case("utf-8") {
+                String::from_utf8(bytes).ok()
+            } else {
+                // ISO-8859-1, the only other charset the RFC requires.
+                Some(bytes.into_iter().map(char::from).collect())
+            }
+        })
+        .or_else(|| multipart::header_param(value, "filename"))?;
+    let name = part_file_name(&name.replace('\\', "/"))?;
+    Some(name.trim_start_matches('.').to_string()).filter(|x| !x.is_empty())
+}
+
+/// Writes a request's `postData`, splitting multipart bodies into one file per part
+/// inside a `<name>.request` folder.
+fn extract_request_body(
+    output: &mut Output,
+    layout: &Layout,
+    index: usize,
+    url: &Url,
+    post_data: &HarPostData,
+) {
+    let (path, url_filename) = layout.locate(url);
+    let request_name = format!("{url_filename}.request");
+    if post_data.mime_type.starts_with("multipart/") {
+        let parts_path = path.unwrap_or_default().join(&request_name);
+        let mut count = 0;
+        if let Some(parts) = multipart::split(&post_data.mime_type, &post_data.text) {
+            for (i, part) in parts.iter().enumerate() {
+                let file_name = part
+                    .file_name
+                    .as_deref()
+                    .and_then(part_file_name)
+                    .or_else(|| {
+                        part.name
+                            .as_deref()
+                            .and_then(part_file_name)
+                            .map(|x| x + ".txt")
+                    })
+                    .unwrap_or_else(|| format!("part{i}.txt"));
+                output.write(
+                    index,
+                    url,
+                    Some(&parts_path),
+                    &file_name,
+                    part.body.as_bytes(),
+                );
+                count += 1;
+            }
+        }
+        // Some exporters (Firefox) leave `text` empty and list the parts as params.
+        if count == 0 {
+            for param in &post_data.params {
+                let file_name = param
+                    .file_name
+                    .as_deref()
+                    .and_then(part_file_name)
+                    .or_else(|| part_file_name(&param.name).map(|x| x + ".txt"))
+                    .unwrap_or_else(|| format!("part{count}.txt"));
+                output.write(
+                    index,
+                    url,
+                    Some(&parts_path),
+                    &file_name,
+                    param.value.as_bytes(),
+                );
+                count += 1;
+            }
+        }
+    } else if !post_data.text.is_empty() {
+        let file_name = request_name + request_body_extension(&post_data.mime_type);
+        let essence = post_data.mime_type.split(';').next().unwrap_or("").trim();
+        let body = output
+            .beautify
+            .then(|| beautify::beautify(essence, post_data.text.as_bytes()))
+            .flatten()
+            .unwrap_or_else(|| post_data.text.as_bytes().to_vec());
+        output.write(index, url, path.as_deref(), &file_name, &body);
+    }
+}
+
+#[derive(Serialize)]
+struct WebSocketLine<'a> {
+    direction: &'a str,
+    time: f64,
+    opcode: u8,
+    data: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<String>,
+}
+
+/// Name of a GraphQL operation: its `operationName`, or else the name after `query`,
+/// `mutation` or `subscription` in the document.
+fn operation_name(operation: &serde_json::Value) -> String {
+    let named = operation["operationName"].as_str().map(str::to_string);
+    let declared = || {
+        let query = operation["query"].as_str()?;
+        let mut words = query
+            .split(|x: char| !(x.is_alphanumeric() || x == '_'))
+            .filter(|x| !x.is_empty());
+        words.find(|x| matches!(*x, "query" | "mutation" | "subscription"))?;
+        words.next().map(str::to_string)
+    };
+    named
+        .filter(|x| !x.is_empty())
+        .or_else(declared)
+        .and_then(|x| part_file_name(&x))
+        .unwrap_or_else(|| "anonymous".to_string())
+}
+
+/// Writes the GraphQL operations of a request, batched ones included, as
+/// `graphql/<operation>.graphql`, `.variables.json` and `.response.json`. Later calls
+/// of an operation get a `~2`, `~3`... suffix.
+fn extract_graphql(
+    output: &mut Output,
+    layout: &Layout,
+    calls: &mut HashMap<PathBuf, usize>,
+    index: usize,
+    entry: &HarLogEntry,
+) {
+    use serde_json::Value;
+    let Some(post_data) = &entry.request.post_data else {
+        return;
+    };
+    let Ok(request) = serde_json::from_str::<Value>(&post_data.text) else {
+        return;
+    };
+    let is_operation = |x: &Value| x.get("query").is_some() || x.get("operationName").is_some();
+    let (operations, batched) = match &request {
+        Value::Array(items) if items.iter().all(is_operation) => (items.as_slice(), true),
+        x if is_operation(x) => (std::slice::from_ref(x), false),
+        _ => return,
+    };
+    let response = entry
+        .response
+        .content
+        .decoded()
+        .map(|body| {
+            decompress::decompress(entry.response.header("content-encoding"), &body).unwrap_or(body)
+        })
+        .and_then(|x| serde_json::from_slice::<Value>(&x).ok());
+    let url = &entry.request.url;
+    let folder = layout.locate(url).0.unwrap_or_default().join("graphql");
+    for (i, operation) in operations.iter().enumerate() {
+        let name = operation_name(operation);
+        let count = calls.entry(folder.join(&name)).or_default();
+        *count += 1;
+        let stem = match *count {
+            1 => name,
+            n => format!("{name}~{n}"),
+        };
+        if let Some(query) = operation["query"].as_str() {
+            let file_name = format!("{stem}.graphql");
+            output.write(index, url, Some(&folder), &file_name, query.as_bytes());
+        }
+        if let Some(variables) = operation.get("variables").filter(|x| !x.is_null()) {
+            let file_name = format!("{stem}.variables.json");
+            let bytes = serde_json::to_vec_pretty(variables).unwrap();
+            output.write(index, url, Some(&folder), &file_name, &bytes);
+        }
+        let response = match (&response, batched) {
+            (Some(Value::Array(items)), true) => items.get(i),
+            (response, _) => response.as_ref(),
+        };
+        if let Some(response) = response {
+            let file_name = format!("{stem}.response.json");
+            let bytes = serde_json::to_vec_pretty(response).unwrap();
+            output.write(index, url, Some(&folder), &file_name, &bytes);
+        }
+    }
+}
+
+/// Writes one socket's frames as `<name>.ws.ndjson`. Binary frames (opcode 2) are
+/// base64 in the HAR, so they are also decoded into `<name>.ws/<seq>-<direction>.bin`.
+fn extract_web_socket(output: &mut Output, layout: &Layout, index: usize, entry: &HarLogEntry) {
+    let url = &entry.request.url;
+    let (path, url_filename) = layout.locate(url);
+    let frames_name = format!("{url_filename}.ws");
+    let mut lines = Vec::new();
+    for (seq, message) in entry.web_socket_messages.iter().enumerate() {
+        let mut file = None;
+        if message.opcode == 2 {
+            if let Ok(frame) = har::decode_base64(&message.data) {
+                let frame_name = format!("{seq:05}-{}.bin", message.direction);
+                let frames_path = path.clone().unwrap_or_default().join(&frames_name);
+                output.write(index, url, Some(&frames_path), &frame_name, &frame);
+                file = Some(format!("{frames_name}/{frame_name}"));
+            }
+        }
+        let line = WebSocketLine {
+            direction: &message.direction,
+            time: message.time,
+            opcode: message.opcode,
+            data: &message.data,
+            file,
+        };
+        serde_json::to_writer(&mut lines, &line).unwrap();
+        lines.push(b'\n');
+    }
+    output.write(
+        index,
+        url,
+        path.as_deref(),
+        &format!("{frames_name}.ndjson"),
+        &lines,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_without_limit_takes_everything() {
+        let mut budget = Budget {
+            limit: None,
+            taken: 0,
+        };
+        assert!(budget.take(u64::MAX / 2));
+        assert!(budget.take(10));
+    }
+
+    #[test]
+    fn budget_refuses_files_over_the_limit() {
+        let mut budget = Budget {
+            limit: Some(100),
+            taken: 0,
+        };
+        assert!(budget.take(60));
+        assert!(!budget.take(41));
+        assert!(budget.take(40));
+        assert!(!budget.take(1));
+        assert_eq!(budget.taken, 100);
+    }
+
+    #[test]
+    fn budget_takes_back_failed_writes() {
+        let mut budget = Budget {
+            limit: Some(100),
+            taken: 0,
+        };
+        assert!(budget.take(100));
+        budget.give_back(100);
+        assert!(budget.take(100));
+    }
+
+    // A failed write without `--max-total-size` used to subtract bytes that were
+    // never counted.
+    #[test]
+    fn budget_without_limit_gives_back_failed_writes() {
+        let mut budget = Budget {
+            limit: None,
+            taken: 0,
+        };
+        assert!(budget.take(10));
+        budget.give_back(10);
+        assert_eq!(budget.taken, 0);
+    }
+
+    fn split(essence: &str, body: &str) -> String {
+        String::from_utf8(split_stream(essence, body.as_bytes()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn splits_server_sent_events() {
+        let body = "event: update\nid: 1\nretry: 500\ndata: {\"a\":1}\n\ndata: two\n\n";
+        assert_eq!(
+            split("text/event-stream", body),
+            concat!(
+                r#"{"event":"update","id":"1","retry":500,"data":"{\"a\":1}"}"#,
+                "\n",
+                r#"{"event":"message","id":"1","data":"two"}"#,
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn joins_data_lines_of_an_event() {
+        let body = "data: first\r\ndata:second\r\ndata\r\n\r\n";
+        assert_eq!(
+            split("text/event-stream", body),
+            "{\"event\":\"message\",\"data\":\"first\\nsecond\\n\"}\n"
+        );
+    }
+
+    #[test]
+    fn skips_comments_and_events_without_data() {
+        let body = ": keep-alive\r\rEvent: x\nevent: ping\n\nid: 7\ndata: a\r";
+        // Cut off by the end of the capture, the last event is kept.
+        assert_eq!(
+            split("text/event-stream", body),
+            "{\"event\":\"message\",\"id\":\"7\",\"data\":\"a\"}\n"
+        );
+        assert_eq!(split("text/event-stream", ""), "");
+    }
+
+    #[test]
+    fn splits_ndjson_records() {
+        let body = "{\"a\": 1}\r\n\n  [2]  \nnot json\n";
+        assert_eq!(
+            split("application/x-ndjson", body),
+            "{\"a\":1}\n[2]\n\"not json\"\n"
+        );
+        assert!(split_stream("application/json", b"{}").is_none());
+    }
+
+    #[test]
+    fn names_an_empty_last_segment_index() {
+        let layout = Layout {
+            domain: true,
+            path: true,
+            path_depth: 0,
+            group: None,
+            query_names: QueryNames::Drop,
+            decode_names: false,
+            flattened: None,
+        };
+        for (url, folder, name) in [
+            ("https://a.com", "a.com", "index"),
+            ("https://a.com/", "a.com", "index"),
+            ("https://a.com/docs/", "a.com/docs", "index"),
+            ("https://a.com/docs/logo.png", "a.com/docs", "logo.png"),
+        ] {
+            let (path, file_name) = layout.locate(&Url::parse(url).unwrap());
+            assert_eq!(path, Some(PathBuf::from(folder)), "{url}");
+            assert_eq!(file_name, name, "{url}");
+        }
+    }
+
+    #[test]
+    fn percent_decodes_only_two_hex_digits() {
+        assert_eq!(percent_decode("a%20b%2fc"), b"a b/c");
+        assert_eq!(percent_decode("%+1%-1%2"), b"%+1%-1%2");
+        assert_eq!(percent_decode("%zz%%41"), b"%zz%A");
+    }
+
+    #[test]
+    fn decodes_names_without_separators_or_control_characters() {
+        assert_eq!(decode_name("caf%C3%A9.txt").as_deref(), Some("café.txt"));
+        assert_eq!(decode_name("plain.txt"), None);
+        for segment in [
+            "a%2Fb", "a%5Cb", "%2E%2E", "a%00b", "a%0Ab", "a%1Bb", "a%7Fb", "%FF",
+        ] {
+            assert_eq!(decode_name(segment), None, "{segment}");
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::{collections::HashMap, path::Path};
+
+use url::Url;
+
+use crate::{
+    decompress,
+    har::{Har, HarLogEntry},
+    sink::Sink,
+};
+
+/// Extensions of the MIME types extracted unless others are asked for.
+pub fn default_mime_types<'a>() -> HashMap<&'a str, &'a str> {
+    let mut map = HashMap::new();
+    map.insert("image/webp", ".webp");
+    map.insert("image/jpeg", ".jpeg");
+    map.insert("image/jpeg", ".jpg");
+    map.insert("image/png", ".png");
+    map.insert("image/svg+xml", ".svg");
+    map
+}
+
+/// What [`extract`] did with a capture.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub extracted: usize,
+    /// Indexes of the entries that could not be extracted, with the reason.
+    pub failures: Vec<(usize, String)>,
+}
+
+/// A response body decoded by [`decode_entry`].
+#[derive(Debug)]
+pub struct ExtractedEntry {
+    /// Position of the entry in `log.entries`.
+    pub index: usize,
+    pub url: Url,
+    /// Name of the file, from the last segment of the URL and the extension of its
+    /// MIME type.
+    pub file_name: String,
+    pub mime_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Decodes the response body of an entry with one of the given MIME types, and
+/// decompresses it when the server compressed it. Other entries give `None`.
+pub fn decode_entry(
+    index: usize,
+    entry: &HarLogEntry,
+    mime_types: &HashMap<&str, &str>,
+) -> Option<Result<ExtractedEntry, String>> {
+    let essence = entry.response.content.essence().to_ascii_lowercase();
+    let ext = mime_types.get(essence.as_str())?;
+    let url = &entry.request.url;
+    let mut file_name = url
+        .path_segments()
+        .and_then(|mut x| x.next_back())
+        .unwrap_or_default()
+        .to_string();
+    if !mime_types.values().any(|x| file_name.ends_with(x)) {
+        file_name.push_str(ext);
+    }
+    let Some(mut bytes) = entry.response.content.decoded() else {
+        return Some(Err(format!("{url}: invalid base64")));
+    };
+    if let Some(decompressed) =
+        decompress::decompress(entry.response.header("content-encoding"), &bytes)
+    {
+        bytes = decompressed;
+    }
+    Some(Ok(ExtractedEntry {
+        index,
+        url: url.clone(),
+        file_name,
+        mime_type: essence,
+        bytes,
+    }))
+}
+
+/// Writes the bodies of responses with one of the given MIME types into `sink`, named
+/// as [`decode_entry`] does. Touches no file system itself, so it also runs where
+/// there is none, such as in the browser; a later response for the same name replaces
+/// the earlier one, as when extracting to a folder.
+pub fn extract(har: &Har, mime_types: &HashMap<&str, &str>, sink: &mut dyn Sink) -> Summary {
+    let mut summary = Summary::default();
+    for (index, entry) in har.log.indexed_entries() {
+        match decode_entry(index, entry, mime_types) {
+            None => {}
+            Some(Ok(extracted)) => {
+                let file_name = &extracted.file_name;
+                match sink.write(Path::new(file_name), &extracted.bytes) {
+                    Ok(()) => summary.extracted += 1,
+                    Err(err) => summary
+                        .failures
+                        .push((index, format!("{file_name}: {err}"))),
+                }
+            }
+            Some(Err(message)) => summary.failures.push((index, message)),
+        }
+    }
+    summary
+}
@@ -0,0 +1,88 @@
+use std::{sync::Mutex, thread, time::Duration};
+
+use base64::Engine;
+use extract_har::har::HarLogEntry;
+use ureq::Agent;
+
+/// Whether the exporter left the response body out, as browsers do for large or cached
+/// resources. Only successful responses count; redirects, errors and `204 No Content`
+/// have no body worth fetching.
+pub fn is_missing(entry: &HarLogEntry) -> bool {
+    let status = entry.response.status;
+    entry.request.method.eq_ignore_ascii_case("GET")
+        && (200..300).contains(&status)
+        && status != 204
+        && entry.response.content.text.is_empty()
+        && ["http", "https"].contains(&entry.request.url.scheme())
+}
+
+fn fetch(agent: &Agent, entry: &HarLogEntry) -> Result<Vec<u8>, ureq::Error> {
+    let request = &entry.request;
+    let mut call = agent.get(request.url.as_str());
+    let mut has_cookie_header = false;
+    for header in &request.headers {
+        let name = header.name.to_lowercase();
+        // Compression is negotiated by the client itself, the rest is recomputed.
+        if name.starts_with(':')
+            || ["host", "content-length", "accept-encoding", "connection"].contains(&name.as_str())
+        {
+            continue;
+        }
+        has_cookie_header |= name == "cookie";
+        call = call.header(&header.name, &header.value);
+    }
+    if !has_cookie_header && !request.cookies.is_empty() {
+        let cookies = request
+            .cookies
+            .iter()
+            .map(|x| format!("{}={}", x.name, x.value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        call = call.header("Cookie", &cookies);
+    }
+    let mut response = call.call()?;
+    // Not followed, which could send the captured cookies and headers to another host.
+    if response.status().is_redirection() {
+        return Err(ureq::Error::StatusCode(response.status().as_u16()));
+    }
+    response
+        .body_mut()
+        .with_config()
+        .limit(u64::MAX)
+        .read_to_vec()
+}
+
+/// Re-downloads the bodies of `entries` using their captured headers and cookies,
+/// storing them base64-encoded in the entries. Returns the errors by HAR index.
+pub fn fetch_missing<'a>(
+    entries: impl Iterator<Item = (usize, &'a mut HarLogEntry)> + Send,
+    concurrency: usize,
+    timeout: Duration,
+) -> Vec<(usize, String)> {
+    let agent: Agent = Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .max_redirects(0)
+        .build()
+        .into();
+    let queue = Mutex::new(entries);
+    let errors = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..concurrency.max(1) {
+            scope.spawn(|| loop {
+                let Some((index, entry)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                match fetch(&agent, entry) {
+                    Ok(body) => {
+                        let content = &mut entry.response.content;
+                        content.size = body.len() as i64;
+                        content.text = base64::engine::general_purpose::STANDARD.encode(body);
+                        content.encoding = Some("base64".into());
+                    }
+                    Err(err) => errors.lock().unwrap().push((index, err.to_string())),
+                }
+            });
+        }
+    });
+    errors.into_inner().unwrap()
+}
@@ -0,0 +1,249 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+};
+
+use chrono::DateTime;
+use zip::ZipArchive;
+
+use crate::import::{invalid_data, Exchange};
+
+/// A raw HTTP message as Fiddler saves it: start line, headers and body.
+struct Message {
+    start_line: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|x| x == needle)
+}
+
+/// Undoes `Transfer-Encoding: chunked`, keeping what was received of a truncated body.
+fn dechunk(mut body: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+    while let Some(line_end) = find(body, b"\r\n") {
+        let size = String::from_utf8_lossy(&body[..line_end]);
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let Ok(size) = usize::from_str_radix(size, 16) else {
+            break;
+        };
+        let start = line_end + 2;
+        if size == 0 || start >= body.len() {
+            break;
+        }
+        let end = (start + size).min(body.len());
+        result.extend_from_slice(&body[start..end]);
+        body = body.get(end + 2..).unwrap_or_default();
+    }
+    result
+}
+
+impl Message {
+    fn parse(data: &[u8]) -> Message {
+        let (head, body) = match find(data, b"\r\n\r\n") {
+            Some(x) => (&data[..x], &data[x + 4..]),
+            None => (data, &[][..]),
+        };
+        let head = String::from_utf8_lossy(head);
+        let mut lines = head.lines();
+        let start_line = lines.next().unwrap_or_default().to_string();
+        let headers = lines
+            .filter_map(|x| x.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect::<Vec<_>>();
+        let chunked = headers.iter().any(|(name, value)| {
+            name.eq_ignore_ascii_case("transfer-encoding") && value.eq_ignore_ascii_case("chunked")
+        });
+        Message {
+            start_line,
+            body: if chunked {
+                dechunk(body)
+            } else {
+                body.to_vec()
+            },
+            headers,
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(x, _)| x.eq_ignore_ascii_case(name))
+            .map(|(_, x)| x.as_str())
+    }
+}
+
+/// Value of the first `name="..."` attribute in Fiddler's session metadata.
+fn attribute<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("{name}=\""))? + name.len() + 2;
+    let length = xml[start..].find('"')?;
+    Some(&xml[start..start + length])
+}
+
+fn read_file(archive: &mut ZipArchive<File>, name: &str) -> io::Result<Option<Vec<u8>>> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(err) => return Err(io::Error::other(err)),
+    };
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    Ok(Some(data))
+}
+
+fn exchange(archive: &mut ZipArchive<File>, prefix: &str) -> io::Result<Option<Exchange>> {
+    let Some(request) = read_file(archive, &format!("{prefix}_c.txt"))? else {
+        return Ok(None);
+    };
+    let request = Message::parse(&request);
+    let mut start_line = request.start_line.split_whitespace();
+    let method = start_line.next().unwrap_or_default().to_string();
+    let target = start_line.next().unwrap_or_default();
+    // Tunnels only show the host they were opened to.
+    if method == "CONNECT" {
+        return Ok(None);
+    }
+    let url = if target.starts_with('/') {
+        format!(
+            "http://{}{target}",
+            request.header("host").unwrap_or_default()
+        )
+    } else {
+        target.to_string()
+    };
+    let mut exchange = Exchange {
+        method,
+        url,
+        http_version: start_line.next().unwrap_or_default().to_string(),
+        request_headers: request.headers,
+        request_body: request.body,
+        ..Default::default()
+    };
+    if let Some(response) = read_file(archive, &format!("{prefix}_s.txt"))? {
+        let response = Message::parse(&response);
+        let mut start_line = response.start_line.splitn(3, ' ').skip(1);
+        exchange.status = start_line
+            .next()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or_default();
+        exchange.status_text = start_line.next().unwrap_or_default().to_string();
+        exchange.response_headers = response.headers;
+        exchange.response_body = response.body;
+    }
+    if let Some(metadata) = read_file(archive, &format!("{prefix}_m.xml"))? {
+        let metadata = String::from_utf8_lossy(&metadata);
+        let time =
+            |name| attribute(&metadata, name).and_then(|x| DateTime::parse_from_rfc3339(x).ok());
+        exchange.started = time("ClientBeginRequest");
+        if let (Some(start), Some(end)) = (exchange.started, time("ClientDoneResponse")) {
+            exchange.time =
+                (end - start).num_microseconds().unwrap_or_default().max(0) as f64 / 1000.0;
+        }
+        exchange.server_ip_address = metadata
+            .find("N=\"x-hostip\"")
+            .and_then(|x| attribute(&metadata[x..], "V"))
+            .map(String::from);
+    }
+    Ok(Some(exchange))
+}
+
+/// Reads the sessions of a Fiddler archive, in session order.
+pub fn parse(file: File) -> io::Result<Vec<Exchange>> {
+    let mut archive = ZipArchive::new(file).map_err(|_| invalid_data("not a Fiddler archive"))?;
+    let mut prefixes = archive
+        .file_names()
+        .filter_map(|x| Some(x.ok()?.strip_suffix("_c.txt")?.to_string()))
+        .collect::<Vec<_>>();
+    // Session numbers are zero-padded to a common width, but not in every version.
+    prefixes.sort_by_key(|x| {
+        let number = x.rsplit(['/', '\\']).next().unwrap_or_default();
+        (number.parse::<u64>().unwrap_or(u64::MAX), x.clone())
+    });
+    let mut exchanges = Vec::new();
+    for prefix in prefixes {
+        exchanges.extend(exchange(&mut archive, &prefix)?);
+    }
+    Ok(exchanges)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::*;
+
+    #[test]
+    fn dechunks_bodies() {
+        assert_eq!(dechunk(b"3\r\nabc\r\n2;ext=1\r\nde\r\n0\r\n\r\n"), b"abcde");
+        assert_eq!(dechunk(b"A\r\n0123456789\r\n0\r\n\r\n"), b"0123456789");
+        // A truncated body keeps what arrived.
+        assert_eq!(dechunk(b"5\r\nabc"), b"abc");
+        assert_eq!(dechunk(b"zz\r\nabc\r\n"), b"");
+    }
+
+    #[test]
+    fn parses_messages() {
+        let message = Message::parse(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nTransfer-Encoding: Chunked\r\n\r\n2\r\nhi\r\n0\r\n\r\n",
+        );
+        assert_eq!(message.start_line, "HTTP/1.1 200 OK");
+        assert_eq!(message.header("content-type"), Some("text/plain"));
+        assert_eq!(message.body, b"hi");
+        let message = Message::parse(b"GET / HTTP/1.1\r\nHost: a.com");
+        assert_eq!(message.header("Host"), Some("a.com"));
+        assert!(message.body.is_empty());
+    }
+
+    #[test]
+    fn reads_sessions_in_session_order() {
+        let path =
+            std::env::temp_dir().join(format!("extract_har-fiddler-{}.saz", std::process::id()));
+        let mut zip = ZipWriter::new(File::create(&path).unwrap());
+        let sessions = [
+            ("raw/10_c.txt", "GET /ten HTTP/1.1\r\nHost: a.com\r\n\r\n"),
+            ("raw/2_c.txt", "GET http://b.com/two HTTP/1.1\r\n\r\n"),
+            ("raw/2_s.txt", "HTTP/1.1 404 Not Found\r\n\r\nmissing"),
+            ("raw/3_c.txt", "CONNECT c.com:443 HTTP/1.1\r\n\r\n"),
+            (
+                "raw/01_c.txt",
+                "POST /one HTTP/1.1\r\nHost: a.com\r\n\r\nbody",
+            ),
+            (
+                "raw/01_m.xml",
+                r#"<Session><SessionTimers ClientBeginRequest="2024-01-01T00:00:00.000+00:00" ClientDoneResponse="2024-01-01T00:00:00.250+00:00"/><SessionFlags><SessionFlag N="x-hostip" V="10.0.0.1"/></SessionFlags></Session>"#,
+            ),
+        ];
+        for (name, data) in sessions {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        let exchanges = parse(File::open(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let urls = exchanges.iter().map(|x| x.url.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            ["http://a.com/one", "http://b.com/two", "http://a.com/ten"]
+        );
+        assert_eq!(exchanges[0].request_body, b"body");
+        assert_eq!(exchanges[0].time, 250.0);
+        assert_eq!(exchanges[0].server_ip_address.as_deref(), Some("10.0.0.1"));
+        assert_eq!(exchanges[1].status, 404);
+        assert_eq!(exchanges[1].status_text, "Not Found");
+        assert_eq!(exchanges[1].response_body, b"missing");
+        assert_eq!(exchanges[2].status, 0);
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_archives() {
+        let path =
+            std::env::temp_dir().join(format!("extract_har-fiddler-{}.txt", std::process::id()));
+        std::fs::write(&path, "not a zip").unwrap();
+        let parsed = parse(File::open(&path).unwrap());
+        let _ = std::fs::remove_file(&path);
+        assert!(parsed.is_err());
+    }
+}
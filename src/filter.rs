@@ -0,0 +1,235 @@
+use std::{collections::HashSet, ops::RangeInclusive, path::PathBuf};
+
+use chrono::{DateTime, Duration, FixedOffset};
+use clap::Args;
+use serde_json::Value;
+
+use crate::input;
+
+#[derive(Args)]
+pub struct FilterArgs {
+    input_har: String,
+    /// HAR file to write the matching entries to
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Keep entries whose URL contains this text (repeatable, any may match)
+    #[arg(long)]
+    include_url: Vec<String>,
+    /// Drop entries whose URL contains this text (repeatable)
+    #[arg(long)]
+    exclude_url: Vec<String>,
+    /// Keep entries with this response status, `x` matches any digit, e.g. `2xx` (repeatable)
+    #[arg(long, value_parser = parse_status)]
+    status: Vec<String>,
+    /// Keep entries started at or after this RFC 3339 time
+    #[arg(long, value_parser = parse_time)]
+    after: Option<DateTime<FixedOffset>>,
+    /// Keep entries started before this RFC 3339 time
+    #[arg(long, value_parser = parse_time)]
+    before: Option<DateTime<FixedOffset>>,
+    /// Keep entries started in this last stretch of the capture, e.g. `90s`, `5m` or `1h`
+    #[arg(long, value_parser = parse_duration)]
+    last: Option<Duration>,
+}
+
+fn parse_status(value: &str) -> Result<String, String> {
+    let value = value.to_lowercase();
+    if value.len() == 3 && value.chars().all(|x| x.is_ascii_digit() || x == 'x') {
+        Ok(value)
+    } else {
+        Err("expected a status code such as 404 or a class such as 2xx".into())
+    }
+}
+
+pub fn parse_time(value: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(value).map_err(|err| err.to_string())
+}
+
+/// A range of entry indexes such as `10-50`, `103` or `200-`, as `list` numbers them.
+pub fn parse_index_range(value: &str) -> Result<RangeInclusive<usize>, String> {
+    let error = || format!("expected an index or a range such as 10-50 or 200-, got {value}");
+    let parse = |x: &str| x.trim().parse::<usize>().map_err(|_| error());
+    let range = match value.split_once('-') {
+        Some((start, end)) => {
+            let start = if start.trim().is_empty() {
+                0
+            } else {
+                parse(start)?
+            };
+            let end = if end.trim().is_empty() {
+                usize::MAX
+            } else {
+                parse(end)?
+            };
+            start..=end
+        }
+        None => parse(value).map(|x| x..=x)?,
+    };
+    if range.is_empty() {
+        return Err(error());
+    }
+    Ok(range)
+}
+
+/// A size such as `1024`, `500k`, `20mb` or `2GiB`, in units of 1024 bytes.
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let error = || "expected a size such as 500k, 20mb or 2gb".to_string();
+    let value = value.trim().to_ascii_lowercase();
+    let split = value
+        .find(|x: char| !x.is_ascii_digit() && x != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number = number.parse::<f64>().map_err(|_| error())?;
+    let power = match unit.trim() {
+        "" | "b" => 0,
+        "k" | "kb" | "kib" => 1,
+        "m" | "mb" | "mib" => 2,
+        "g" | "gb" | "gib" => 3,
+        "t" | "tb" | "tib" => 4,
+        _ => return Err(error()),
+    };
+    Ok((number * 1024f64.powi(power)) as u64)
+}
+
+/// A duration such as `90s`, `5m`, `2h` or `1d`.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let error = || "expected a duration such as 90s, 5m, 2h or 1d".to_string();
+    let split = value
+        .find(|x: char| !x.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number = number.parse::<i64>().map_err(|_| error())?;
+    match unit {
+        "s" => Duration::try_seconds(number),
+        "m" => Duration::try_minutes(number),
+        "h" => Duration::try_hours(number),
+        "d" => Duration::try_days(number),
+        _ => None,
+    }
+    .ok_or_else(error)
+}
+
+/// Bounds on when entries started, from `--after`, `--before` and `--last`.
+pub struct TimeWindow {
+    after: Option<DateTime<FixedOffset>>,
+    before: Option<DateTime<FixedOffset>>,
+}
+
+impl TimeWindow {
+    /// `last` counts back from the latest start time among `started`.
+    pub fn new(
+        after: Option<DateTime<FixedOffset>>,
+        before: Option<DateTime<FixedOffset>>,
+        last: Option<Duration>,
+        started: impl Iterator<Item = DateTime<FixedOffset>>,
+    ) -> TimeWindow {
+        let after = match last.and_then(|last| Some(started.max()? - last)) {
+            Some(since) => Some(after.map_or(since, |x| x.max(since))),
+            None => after,
+        };
+        TimeWindow { after, before }
+    }
+
+    /// Whether an entry started at this time is kept. Entries without a valid start
+    /// time are only kept when no bound is set.
+    pub fn contains(&self, started: Option<DateTime<FixedOffset>>) -> bool {
+        if self.after.is_none() && self.before.is_none() {
+            return true;
+        }
+        started.is_some_and(|started| {
+            self.after.is_none_or(|x| started >= x) && self.before.is_none_or(|x| started < x)
+        })
+    }
+}
+
+fn status_matches(pattern: &str, status: i64) -> bool {
+    let status = status.to_string();
+    status.len() == pattern.len()
+        && pattern
+            .chars()
+            .zip(status.chars())
+            .all(|(x, y)| x == 'x' || x == y)
+}
+
+fn started(entry: &Value) -> Option<DateTime<FixedOffset>> {
+    entry["startedDateTime"]
+        .as_str()
+        .and_then(|x| DateTime::parse_from_rfc3339(x).ok())
+}
+
+impl FilterArgs {
+    fn matches(&self, entry: &Value, window: &TimeWindow) -> bool {
+        let url = entry["request"]["url"].as_str().unwrap_or_default();
+        if !self.include_url.is_empty()
+            && !self.include_url.iter().any(|x| url.contains(x.as_str()))
+        {
+            return false;
+        }
+        if self.exclude_url.iter().any(|x| url.contains(x.as_str())) {
+            return false;
+        }
+        if !self.status.is_empty() {
+            let status = entry["response"]["status"].as_i64().unwrap_or_default();
+            if !self.status.iter().any(|x| status_matches(x, status)) {
+                return false;
+            }
+        }
+        window.contains(started(entry))
+    }
+}
+
+pub fn run(args: FilterArgs) {
+    let mut document = input::load_document(&args.input_har);
+    let log = &mut document["log"];
+    let mut entries = log["entries"].as_array().cloned().unwrap_or_default();
+    let total = entries.len();
+    let window = TimeWindow::new(
+        args.after,
+        args.before,
+        args.last,
+        entries.iter().filter_map(started),
+    );
+    entries.retain(|x| args.matches(x, &window));
+    // Drop pages none of the remaining entries belong to.
+    let page_refs = entries
+        .iter()
+        .filter_map(|x| x["pageref"].as_str())
+        .collect::<HashSet<_>>();
+    if let Some(pages) = log.get_mut("pages").and_then(|x| x.as_array_mut()) {
+        pages.retain(|x| x["id"].as_str().is_some_and(|x| page_refs.contains(x)));
+    }
+    let count = entries.len();
+    log["entries"] = Value::from(entries);
+    input::write_document(&args.output, &document);
+    eprintln!(
+        "{}",
+        tr!(FilterSummary, count, total, args.output.to_string_lossy())
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_indices_and_ranges() {
+        assert_eq!(parse_index_range("103"), Ok(103..=103));
+        assert_eq!(parse_index_range("10-50"), Ok(10..=50));
+        assert_eq!(parse_index_range(" 10 - 50 "), Ok(10..=50));
+        assert_eq!(parse_index_range("7-7"), Ok(7..=7));
+    }
+
+    #[test]
+    fn open_ends_reach_the_first_and_last_entries() {
+        assert_eq!(parse_index_range("200-"), Ok(200..=usize::MAX));
+        assert_eq!(parse_index_range("-5"), Ok(0..=5));
+        assert_eq!(parse_index_range("-"), Ok(0..=usize::MAX));
+    }
+
+    #[test]
+    fn rejects_empty_and_malformed_ranges() {
+        for value in ["", " ", "50-10", "a", "1-b", "1-2-3", "-1-", "1.5", "+-"] {
+            assert!(parse_index_range(value).is_err(), "{value}");
+        }
+    }
+}
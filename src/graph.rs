@@ -0,0 +1,222 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{export, input};
+
+#[derive(Args)]
+pub struct GraphArgs {
+    input_har: String,
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    format: GraphFormat,
+    /// Write to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz source, to be rendered with `dot -Tsvg`
+    Dot,
+    /// Lists of nodes and edges
+    Json,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Via {
+    /// Chrome's `_initiator`: the document or script that issued the request.
+    Initiator,
+    Referer,
+    /// Nothing better known than the page the entry was loaded for.
+    Page,
+}
+
+#[derive(Serialize)]
+struct Node {
+    id: String,
+    label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mime_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct Edge {
+    from: String,
+    to: String,
+    via: Via,
+}
+
+fn entry_id(index: usize) -> String {
+    format!("e{index}")
+}
+
+fn page_id(index: usize) -> String {
+    format!("p{index}")
+}
+
+/// The URL without its fragment, which is never sent and so never recorded.
+fn without_fragment(url: &str) -> &str {
+    url.split('#').next().unwrap_or_default()
+}
+
+fn entry_url(entry: &Value) -> &str {
+    without_fragment(entry["request"]["url"].as_str().unwrap_or_default())
+}
+
+/// Chrome records the URL of the script for script-initiated requests only in the
+/// call stack, parent frames included.
+fn initiator_url(entry: &Value) -> Option<&str> {
+    let initiator = &entry["_initiator"];
+    initiator["url"]
+        .as_str()
+        .filter(|x| !x.is_empty())
+        .or_else(|| {
+            let mut stack = &initiator["stack"];
+            while stack.is_object() {
+                let frame = stack["callFrames"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .find_map(|x| x["url"].as_str().filter(|x| !x.is_empty()));
+                if frame.is_some() {
+                    return frame;
+                }
+                stack = &stack["parent"];
+            }
+            None
+        })
+}
+
+fn referer(entry: &Value) -> Option<&str> {
+    entry["request"]["headers"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|x| {
+            x["name"]
+                .as_str()
+                .is_some_and(|x| x.eq_ignore_ascii_case("referer"))
+        })
+        .and_then(|x| x["value"].as_str())
+}
+
+fn graph(document: &Value) -> (Vec<Node>, Vec<Edge>) {
+    let pages = document["log"]["pages"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice);
+    let entries = document["log"]["entries"]
+        .as_array()
+        .map_or(&[][..], Vec::as_slice);
+    let mut nodes = Vec::new();
+    let mut page_ids = HashMap::new();
+    for (index, page) in pages.iter().enumerate() {
+        let title = page["title"].as_str().unwrap_or_default();
+        let id = page["id"].as_str().unwrap_or_default();
+        page_ids.insert(id, page_id(index));
+        nodes.push(Node {
+            id: page_id(index),
+            label: if title.is_empty() { id } else { title }.to_string(),
+            mime_type: None,
+            status: None,
+        });
+    }
+    let mut by_url = HashMap::<&str, Vec<usize>>::new();
+    for (index, entry) in entries.iter().enumerate() {
+        by_url.entry(entry_url(entry)).or_default().push(index);
+        nodes.push(Node {
+            id: entry_id(index),
+            label: entry_url(entry).to_string(),
+            mime_type: entry["response"]["content"]["mimeType"]
+                .as_str()
+                .map(str::to_string),
+            status: entry["response"]["status"].as_i64(),
+        });
+    }
+    // The latest request for the URL made before this one, which is the one the
+    // browser had loaded, or else the first one.
+    let loaded_before = |target: &str, index: usize| {
+        let candidates = by_url.get(without_fragment(target))?;
+        candidates
+            .iter()
+            .rev()
+            .find(|x| **x < index)
+            .or(candidates.first())
+            .copied()
+            .filter(|x| *x != index)
+    };
+    let mut edges = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let from = initiator_url(entry)
+            .and_then(|x| loaded_before(x, index))
+            .map(|x| (entry_id(x), Via::Initiator))
+            .or_else(|| {
+                let parent = loaded_before(referer(entry)?, index)?;
+                Some((entry_id(parent), Via::Referer))
+            })
+            .or_else(|| {
+                let page = page_ids.get(entry["pageref"].as_str()?)?;
+                Some((page.clone(), Via::Page))
+            });
+        if let Some((from, via)) = from {
+            edges.push(Edge {
+                from,
+                to: entry_id(index),
+                via,
+            });
+        }
+    }
+    (nodes, edges)
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn to_dot(nodes: &[Node], edges: &[Edge]) -> String {
+    let mut dot = String::from("digraph har {\n  rankdir=LR;\n  node [shape=box];\n");
+    for node in nodes {
+        // Long URLs would make the boxes unreadably wide.
+        let mut label = node.label.chars().take(80).collect::<String>();
+        if label.len() < node.label.len() {
+            label.push('…');
+        }
+        let shape = if node.id.starts_with('p') {
+            ", shape=ellipse"
+        } else {
+            ""
+        };
+        dot.push_str(&format!(
+            "  {} [label={}{shape}];\n",
+            node.id,
+            quote(&label)
+        ));
+    }
+    for edge in edges {
+        let style = match edge.via {
+            Via::Initiator => "",
+            Via::Referer => " [style=dashed]",
+            Via::Page => " [style=dotted]",
+        };
+        dot.push_str(&format!("  {} -> {}{style};\n", edge.from, edge.to));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+pub fn run(args: GraphArgs) {
+    let document = input::load_document(&args.input_har);
+    let (nodes, edges) = graph(&document);
+    let text = match args.format {
+        GraphFormat::Dot => to_dot(&nodes, &edges),
+        GraphFormat::Json => {
+            let graph = serde_json::json!({ "nodes": nodes, "edges": edges });
+            serde_json::to_string_pretty(&graph).unwrap() + "\n"
+        }
+    };
+    export::write_bytes(args.output.as_deref(), text.as_bytes());
+}
@@ -0,0 +1,131 @@
+use std::ops::Range;
+
+use clap::Args;
+use extract_har::decompress;
+use regex::{Regex, RegexBuilder};
+
+use crate::{exit_code, input};
+
+/// Lines longer than this, as in minified code, are cut down to the text around the
+/// first match.
+const MAX_LINE_LENGTH: usize = 200;
+
+/// Characters kept on each side of the match when a line is cut down.
+const LINE_EXCERPT: usize = 80;
+
+#[derive(Args)]
+pub struct GrepArgs {
+    input_har: String,
+    /// Regular expression to search the decoded response bodies for
+    pattern: String,
+    /// Match regardless of case
+    #[arg(short, long)]
+    ignore_case: bool,
+    /// Only search responses of this MIME type, e.g. `text/html`, or whose type
+    /// contains this text, e.g. `json` (repeatable)
+    #[arg(long = "type", value_delimiter = ',')]
+    types: Vec<String>,
+    /// Also print this many lines before and after each matching line
+    #[arg(short = 'C', long, default_value_t = 0)]
+    context: usize,
+    /// Only print the index and URL of the entries whose bodies match
+    #[arg(short = 'l', long)]
+    files_with_matches: bool,
+}
+
+/// `line`, or the part of it around `found` when it is too long to print.
+fn excerpt(line: &str, found: Option<Range<usize>>) -> String {
+    if line.chars().count() <= MAX_LINE_LENGTH {
+        return line.to_string();
+    }
+    let found = found.unwrap_or(0..0);
+    let start = line[..found.start]
+        .char_indices()
+        .rev()
+        .nth(LINE_EXCERPT - 1)
+        .map_or(0, |(x, _)| x);
+    let end = line[found.end..]
+        .char_indices()
+        .nth(LINE_EXCERPT)
+        .map_or(line.len(), |(x, _)| found.end + x);
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < line.len() { "…" } else { "" };
+    format!("{prefix}{}{suffix}", &line[start..end])
+}
+
+/// Prints the matching lines of a body with their line numbers, `:` after those
+/// that match and `-` after context lines, and with context,
+/// a `--` between groups that are apart.
+fn print_matches(regex: &Regex, text: &str, context: usize) {
+    let lines = text.lines().collect::<Vec<_>>();
+    let matching = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| regex.is_match(line))
+        .map(|(number, _)| number)
+        .collect::<Vec<_>>();
+    let mut printed = None::<usize>;
+    for &number in &matching {
+        let first = number.saturating_sub(context);
+        let first = printed.map_or(first, |x| first.max(x + 1));
+        if context > 0 && printed.is_some_and(|x| first > x + 1) {
+            println!("--");
+        }
+        let last = (number + context).min(lines.len() - 1);
+        for (position, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+            let found = regex.find(line).map(|x| x.range());
+            let separator = if found.is_some() { ':' } else { '-' };
+            println!("{}{separator}{}", position + 1, excerpt(line, found));
+            printed = Some(position);
+        }
+    }
+}
+
+pub fn run(args: GrepArgs) {
+    let regex = RegexBuilder::new(&args.pattern)
+        .case_insensitive(args.ignore_case)
+        .build()
+        .unwrap_or_else(|err| pexit!(InvalidPattern, err));
+    let types = args
+        .types
+        .iter()
+        .map(|x| x.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    let session = input::open_session(&args.input_har);
+    let mut count_matched = 0;
+    for (index, entry) in session.indexed_entries() {
+        let response = &entry.response;
+        let essence = response.content.essence().to_ascii_lowercase();
+        if !types.is_empty() && !types.iter().any(|x| essence.contains(x.as_str())) {
+            continue;
+        }
+        let Some(mut body) = response.content.decoded() else {
+            continue;
+        };
+        if let Some(decompressed) =
+            decompress::decompress(response.header("content-encoding"), &body)
+        {
+            body = decompressed;
+        }
+        // Binary bodies would only give chance matches.
+        let Ok(text) = String::from_utf8(body) else {
+            continue;
+        };
+        if !regex.is_match(&text) {
+            continue;
+        }
+        if args.files_with_matches {
+            println!("{index:>6}  {}", entry.request.url);
+        } else {
+            if count_matched > 0 {
+                println!();
+            }
+            println!("[{index}] {}", entry.request.url);
+            print_matches(&regex, &text, args.context);
+        }
+        count_matched += 1;
+    }
+    if count_matched == 0 {
+        std::process::exit(exit_code::NOTHING_MATCHED);
+    }
+}
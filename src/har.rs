@@ -0,0 +1,831 @@
+use std::{
+    fmt,
+    io::{BufRead, BufReader, Read},
+};
+
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use chrono::{DateTime, FixedOffset};
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::Value;
+use url::Url;
+
+#[derive(Default, Deserialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+#[derive(Default, Deserialize)]
+pub struct HarLog {
+    #[serde(default)]
+    pub pages: Vec<HarPage>,
+    pub entries: Vec<HarLogEntry>,
+    /// Position in the document's `log.entries` of each entry, when a lenient read left
+    /// unreadable ones out; empty when the positions are those in `entries`.
+    #[serde(skip)]
+    pub indices: Vec<usize>,
+    /// Entries a lenient load left out, for commands that report them themselves.
+    #[serde(skip)]
+    pub skipped: Vec<SkippedEntry>,
+}
+
+impl HarLog {
+    /// Position in the document's `log.entries` of `entries[position]`, the index
+    /// shown to users and recorded in manifests.
+    pub fn index_of(&self, position: usize) -> usize {
+        self.indices.get(position).copied().unwrap_or(position)
+    }
+
+    /// Where in `entries` the entry at `index` of the document is, unless it was left
+    /// out.
+    pub fn position_of(&self, index: usize) -> Option<usize> {
+        if self.indices.is_empty() {
+            return (index < self.entries.len()).then_some(index);
+        }
+        self.indices.binary_search(&index).ok()
+    }
+
+    /// The entries with their indices in the document.
+    pub fn indexed_entries(&self) -> impl Iterator<Item = (usize, &HarLogEntry)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(position, entry)| (self.index_of(position), entry))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct HarPage {
+    pub id: String,
+    #[serde(default)]
+    pub title: String,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct HarLogEntry {
+    #[serde(rename = "startedDateTime", default)]
+    pub started_date_time: String,
+    /// Id of the page in `log.pages` this entry was loaded for.
+    #[serde(default)]
+    pub pageref: Option<String>,
+    /// Total time of the request in milliseconds.
+    #[serde(default)]
+    pub time: f64,
+    #[serde(default)]
+    pub timings: Option<HarTimings>,
+    #[serde(rename = "serverIPAddress", default)]
+    pub server_ip_address: Option<String>,
+    pub request: HarLogEntryRequest,
+    pub response: HarLogEntryResponse,
+    /// Chrome's record of the frames exchanged over a WebSocket connection.
+    #[serde(rename = "_webSocketMessages", default)]
+    pub web_socket_messages: Vec<WebSocketMessage>,
+}
+
+impl HarLogEntry {
+    pub fn started(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.started_date_time).ok()
+    }
+
+    /// Where a 3xx response sends the client, from `redirectURL` or else the
+    /// `Location` header, resolved against the request URL.
+    pub fn redirect_target(&self) -> Option<Url> {
+        if !(300..400).contains(&self.response.status) {
+            return None;
+        }
+        let location = Some(self.response.redirect_url.as_str())
+            .filter(|x| !x.is_empty())
+            .or_else(|| self.response.header("location"))?;
+        self.request.url.join(location).ok()
+    }
+
+    /// Milliseconds between sending the request and the first byte of the response.
+    pub fn wait(&self) -> Option<f64> {
+        self.timings
+            .as_ref()
+            .and_then(|x| x.wait)
+            .filter(|x| *x >= 0.0)
+    }
+}
+
+/// Milliseconds spent in each phase of a request; `-1` or absent when not applicable.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct HarTimings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocked: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receive: Option<f64>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct WebSocketMessage {
+    #[serde(rename = "type")]
+    pub direction: String,
+    pub time: f64,
+    pub opcode: u8,
+    pub data: String,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct HarLogEntryRequest {
+    #[serde(default)]
+    pub method: String,
+    pub url: Url,
+    #[serde(default)]
+    pub headers: Vec<HarHeader>,
+    #[serde(default)]
+    pub cookies: Vec<HarCookie>,
+    #[serde(rename = "postData")]
+    pub post_data: Option<HarPostData>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct HarPostData {
+    #[serde(rename = "mimeType", default)]
+    pub mime_type: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub params: Vec<HarParam>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct HarParam {
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+    #[serde(rename = "fileName")]
+    pub file_name: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct HarLogEntryResponse {
+    #[serde(default)]
+    pub status: i32,
+    #[serde(default)]
+    pub headers: Vec<HarHeader>,
+    #[serde(default)]
+    pub cookies: Vec<HarCookie>,
+    pub content: HarLogEntryResponseContent,
+    #[serde(rename = "redirectURL", default)]
+    pub redirect_url: String,
+    /// Bytes Chrome received for the response, headers included and before
+    /// decompression.
+    #[serde(rename = "_transferSize", default)]
+    pub transfer_size: Option<i64>,
+}
+
+impl HarLogEntryResponse {
+    /// Value of the first header with this name, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|x| x.name.eq_ignore_ascii_case(name))
+            .map(|x| x.value.as_str())
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct HarLogEntryResponseContent {
+    #[serde(default)]
+    pub size: i64,
+    #[serde(default)]
+    pub text: String,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    #[serde(default)]
+    pub encoding: Option<String>,
+}
+
+impl HarLogEntryResponseContent {
+    /// MIME type without parameters such as `charset`.
+    pub fn essence(&self) -> &str {
+        self.mime_type.split(';').next().unwrap_or("").trim()
+    }
+
+    /// Body bytes, decoding base64 when the exporter declared that encoding.
+    pub fn decoded(&self) -> Option<Vec<u8>> {
+        if self.encoding.as_deref() == Some("base64") {
+            decode_base64(&self.text).ok()
+        } else {
+            Some(self.text.as_bytes().to_vec())
+        }
+    }
+
+    /// Decoded body size, estimated from the text when the exporter left `size` unset.
+    pub fn body_size(&self) -> u64 {
+        if self.size > 0 {
+            self.size as u64
+        } else if self.encoding.as_deref() == Some("base64") {
+            self.text.trim_end_matches('=').len() as u64 * 3 / 4
+        } else {
+            self.text.len() as u64
+        }
+    }
+}
+
+const TOLERANT: GeneralPurposeConfig = GeneralPurposeConfig::new()
+    .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+    .with_decode_allow_trailing_bits(true);
+const TOLERANT_STANDARD: GeneralPurpose = GeneralPurpose::new(&alphabet::STANDARD, TOLERANT);
+const TOLERANT_URL_SAFE: GeneralPurpose = GeneralPurpose::new(&alphabet::URL_SAFE, TOLERANT);
+
+/// Decodes base64 the way exporters actually write it: wrapped over several lines,
+/// with or without padding, in the standard or the URL-safe alphabet.
+pub fn decode_base64(data: impl AsRef<[u8]>) -> Result<Vec<u8>, base64::DecodeError> {
+    // The vectorized decoder handles well-formed bodies, which are most of them.
+    #[cfg(feature = "fast")]
+    if let Ok(decoded) = base64_simd::STANDARD.decode_to_vec(data.as_ref()) {
+        return Ok(decoded);
+    }
+    let data = data
+        .as_ref()
+        .iter()
+        .copied()
+        .filter(|x| !x.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+    TOLERANT_STANDARD
+        .decode(&data)
+        .or_else(|err| TOLERANT_URL_SAFE.decode(&data).map_err(|_| err))
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct HarHeader {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct HarCookie {
+    pub name: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    #[serde(rename = "httpOnly", default, skip_serializing_if = "Option::is_none")]
+    pub http_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secure: Option<bool>,
+}
+
+pub fn parse<R: Read>(reader: R) -> serde_json::Result<Har> {
+    serde_json::from_reader(reader)
+}
+
+/// An entry left out when a capture is read leniently.
+pub struct SkippedEntry {
+    /// Position of the entry in the document's `log.entries`.
+    pub index: usize,
+    pub reason: String,
+}
+
+/// Fields that some exporters write as strings, or as `200.0`, although HAR makes
+/// them numbers.
+const NUMBER_FIELDS: &[&str] = &[
+    "time",
+    "status",
+    "size",
+    "bodySize",
+    "headersSize",
+    "compression",
+    "blocked",
+    "dns",
+    "connect",
+    "ssl",
+    "send",
+    "wait",
+    "receive",
+    "opcode",
+    "_transferSize",
+];
+
+/// Reads the first JSON value of `data`, skipping a byte order mark and ignoring
+/// whatever follows the value.
+pub fn read_json(data: &[u8]) -> serde_json::Result<Value> {
+    read_first(data)
+}
+
+fn read_first<T: DeserializeOwned>(data: &[u8]) -> serde_json::Result<T> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    serde_json::Deserializer::from_slice(data)
+        .into_iter::<T>()
+        .next()
+        .unwrap_or_else(|| serde_json::from_slice(b""))
+}
+
+/// Fixes in place what nonstandard exporters get wrong: `null` for absent fields and
+/// numbers written as strings or with a fraction.
+fn repair(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, x| !x.is_null());
+            for (key, x) in map.iter_mut() {
+                if NUMBER_FIELDS.contains(&key.as_str()) {
+                    let number = match x {
+                        Value::String(text) => {
+                            text.trim().parse::<f64>().ok().filter(|x| x.is_finite())
+                        }
+                        Value::Number(number) if !number.is_i64() => number.as_f64(),
+                        _ => None,
+                    };
+                    if let Some(number) = number {
+                        *x = if number.fract() == 0.0 && number.abs() < 1e15 {
+                            Value::from(number as i64)
+                        } else {
+                            Value::from(number)
+                        };
+                    }
+                }
+                repair(x);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(repair),
+        _ => {}
+    }
+}
+
+/// Gives an entry the parts that some tools leave out for aborted or bodiless
+/// requests: a `response`, its `content` and the content's `mimeType`.
+fn fill_entry(entry: &mut Value) {
+    let Some(entry) = entry.as_object_mut() else {
+        return;
+    };
+    let response = entry
+        .entry("response")
+        .or_insert_with(|| Value::from(serde_json::Map::new()));
+    let Some(response) = response.as_object_mut() else {
+        return;
+    };
+    let content = response
+        .entry("content")
+        .or_insert_with(|| Value::from(serde_json::Map::new()));
+    if let Some(content) = content.as_object_mut() {
+        content.entry("mimeType").or_insert_with(|| Value::from(""));
+    }
+}
+
+/// Repairs an entry and converts it.
+fn read_entry(mut entry: Value) -> serde_json::Result<HarLogEntry> {
+    repair(&mut entry);
+    fill_entry(&mut entry);
+    serde_json::from_value(entry)
+}
+
+#[derive(Deserialize)]
+struct LenientHar {
+    log: LenientLog,
+}
+
+#[derive(Deserialize)]
+struct LenientLog {
+    #[serde(default)]
+    pages: Option<Vec<Value>>,
+    #[serde(default)]
+    entries: LenientEntries,
+}
+
+/// `log.entries` read one entry at a time, each repaired and converted before the next
+/// is read, so that no document tree of the whole capture is built.
+#[derive(Default)]
+struct LenientEntries {
+    entries: Vec<HarLogEntry>,
+    indices: Vec<usize>,
+    skipped: Vec<SkippedEntry>,
+}
+
+impl<'de> Deserialize<'de> for LenientEntries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = LenientEntries;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an array of entries")
+            }
+
+            // Written by some exporters for a capture without entries.
+            fn visit_unit<E>(self) -> Result<LenientEntries, E> {
+                Ok(LenientEntries::default())
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<LenientEntries, A::Error> {
+                let mut result = LenientEntries::default();
+                let mut index = 0;
+                while let Some(entry) = seq.next_element::<Value>()? {
+                    match read_entry(entry) {
+                        Ok(entry) => {
+                            result.entries.push(entry);
+                            result.indices.push(index);
+                        }
+                        Err(err) => result.skipped.push(SkippedEntry {
+                            index,
+                            reason: err.to_string(),
+                        }),
+                    }
+                    index += 1;
+                }
+                Ok(result)
+            }
+        }
+
+        deserializer.deserialize_any(EntriesVisitor)
+    }
+}
+
+impl LenientHar {
+    fn into_har(self) -> (Har, Vec<SkippedEntry>) {
+        let pages = self
+            .log
+            .pages
+            .into_iter()
+            .flatten()
+            .filter_map(|mut x| {
+                repair(&mut x);
+                serde_json::from_value(x).ok()
+            })
+            .collect();
+        let LenientEntries {
+            entries,
+            mut indices,
+            skipped,
+        } = self.log.entries;
+        // Positions only need keeping when they differ.
+        if skipped.is_empty() {
+            indices = Vec::new();
+        }
+        let log = HarLog {
+            pages,
+            entries,
+            indices,
+            skipped: Vec::new(),
+        };
+        (Har { log }, skipped)
+    }
+}
+
+/// Reads a HAR document the way browsers and proxies actually write them, leaving out
+/// the pages and entries that still cannot be read instead of failing as a whole.
+/// Entries keep their indices in the document through [`HarLog::index_of`].
+pub fn from_value_lenient(document: Value) -> serde_json::Result<(Har, Vec<SkippedEntry>)> {
+    Ok(serde_json::from_value::<LenientHar>(document)?.into_har())
+}
+
+/// Like [`from_value_lenient`], also tolerating a byte order mark and trailing data.
+pub fn parse_lenient(data: &[u8]) -> serde_json::Result<(Har, Vec<SkippedEntry>)> {
+    Ok(read_first::<LenientHar>(data)?.into_har())
+}
+
+/// An entry read by [`stream_entries`] with its index, or why it was left out.
+pub type StreamedEntry = Result<(usize, HarLogEntry), SkippedEntry>;
+
+/// Reads a HAR document from `reader` one entry at a time, handing each to `send`
+/// before the next is read, so that the capture is never held in memory as a whole.
+/// Reading stops early when `send` returns `false`. When `lenient`, entries are
+/// repaired as by [`parse_lenient`], and those that still cannot be read are handed
+/// over as skipped. Returns how many entries the document has, skipped ones included;
+/// pages are not kept.
+pub fn stream_entries<R: Read>(
+    reader: R,
+    lenient: bool,
+    send: impl FnMut(StreamedEntry) -> bool,
+) -> serde_json::Result<usize> {
+    let mut reader = BufReader::new(reader);
+    let start = reader.fill_buf().map_err(serde_json::Error::io)?;
+    if lenient && start.starts_with(b"\xEF\xBB\xBF") {
+        reader.consume(3);
+    }
+    let mut stream = Stream {
+        lenient,
+        send,
+        index: 0,
+        stopped: false,
+    };
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    match StreamDocument(&mut stream).deserialize(&mut deserializer) {
+        Err(_) if stream.stopped => return Ok(stream.index),
+        result => result?,
+    }
+    // Like `parse`, a strict read allows nothing after the document.
+    if !lenient {
+        deserializer.end()?;
+    }
+    Ok(stream.index)
+}
+
+struct Stream<F> {
+    lenient: bool,
+    send: F,
+    /// Index in the document of the next entry.
+    index: usize,
+    stopped: bool,
+}
+
+/// The document's top level, of which only `log` is read.
+struct StreamDocument<'s, F>(&'s mut Stream<F>);
+
+/// `log`, of which `entries` is streamed and, for a strict read, `pages` checked.
+struct StreamLog<'s, F>(&'s mut Stream<F>);
+
+struct StreamEntries<'s, F>(&'s mut Stream<F>);
+
+impl<'de, F: FnMut(StreamedEntry) -> bool> DeserializeSeed<'de> for StreamDocument<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(StreamedEntry) -> bool> Visitor<'de> for StreamDocument<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a HAR document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut log = false;
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "log" && !log {
+                map.next_value_seed(StreamLog(&mut *self.0))?;
+                log = true;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        if !log {
+            return Err(de::Error::missing_field("log"));
+        }
+        Ok(())
+    }
+}
+
+impl<'de, F: FnMut(StreamedEntry) -> bool> DeserializeSeed<'de> for StreamLog<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(StreamedEntry) -> bool> Visitor<'de> for StreamLog<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a HAR log")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut entries = false;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "entries" if !entries => {
+                    map.next_value_seed(StreamEntries(&mut *self.0))?;
+                    entries = true;
+                }
+                "pages" if !self.0.lenient => {
+                    map.next_value::<Vec<HarPage>>()?;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if !entries && !self.0.lenient {
+            return Err(de::Error::missing_field("entries"));
+        }
+        Ok(())
+    }
+}
+
+impl<'de, F: FnMut(StreamedEntry) -> bool> DeserializeSeed<'de> for StreamEntries<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, F: FnMut(StreamedEntry) -> bool> Visitor<'de> for StreamEntries<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of entries")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        if self.0.lenient {
+            return Ok(());
+        }
+        Err(E::invalid_type(de::Unexpected::Unit, &self))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let stream = self.0;
+        loop {
+            let index = stream.index;
+            let entry = if stream.lenient {
+                let Some(entry) = seq.next_element::<Value>()? else {
+                    break;
+                };
+                read_entry(entry).map_err(|err| SkippedEntry {
+                    index,
+                    reason: err.to_string(),
+                })
+            } else {
+                let Some(entry) = seq.next_element::<HarLogEntry>()? else {
+                    break;
+                };
+                Ok(entry)
+            };
+            stream.index += 1;
+            if !(stream.send)(entry.map(|x| (index, x))) {
+                stream.stopped = true;
+                return Err(de::Error::custom("reading was stopped"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn entry(url: &str) -> Value {
+        json!({
+            "request": { "method": "GET", "url": url },
+            "response": { "status": 200, "content": { "mimeType": "text/css" } },
+        })
+    }
+
+    #[test]
+    fn keeps_document_indices_of_entries_after_skipped_ones() {
+        let document = json!({ "log": { "entries": [
+            entry("https://a.com/0"),
+            { "request": { "url": "not a url" } },
+            entry("https://a.com/2"),
+        ] } });
+        let (har, skipped) = from_value_lenient(document).unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].index, 1);
+        let indices = har
+            .log
+            .indexed_entries()
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [0, 2]);
+        assert_eq!(har.log.position_of(2), Some(1));
+        assert_eq!(har.log.position_of(1), None);
+    }
+
+    #[test]
+    fn indices_are_positions_when_nothing_was_skipped() {
+        let document = json!({ "log": { "entries": [entry("https://a.com/0")] } });
+        let (har, skipped) = from_value_lenient(document).unwrap();
+        assert!(skipped.is_empty());
+        assert!(har.log.indices.is_empty());
+        assert_eq!(har.log.index_of(0), 0);
+        assert_eq!(har.log.position_of(0), Some(0));
+        assert_eq!(har.log.position_of(1), None);
+    }
+
+    #[test]
+    fn repairs_entries() {
+        let data = br#"{"log": {"pages": null, "entries": [{
+            "request": {"method": "GET", "url": "https://a.com/", "headers": null},
+            "response": {"status": "404", "bodySize": 12.0}
+        }]}}"#;
+        let (har, skipped) = parse_lenient(data).unwrap();
+        assert!(skipped.is_empty());
+        let entry = &har.log.entries[0];
+        assert_eq!(entry.response.status, 404);
+        assert!(entry.request.headers.is_empty());
+        assert_eq!(entry.response.content.mime_type, "");
+    }
+
+    #[test]
+    fn reads_null_and_missing_entries_as_none() {
+        for data in [&br#"{"log": {"entries": null}}"#[..], br#"{"log": {}}"#] {
+            let (har, skipped) = parse_lenient(data).unwrap();
+            assert!(har.log.entries.is_empty());
+            assert!(skipped.is_empty());
+        }
+    }
+
+    #[test]
+    fn skips_a_byte_order_mark_and_trailing_data() {
+        let data = b"\xEF\xBB\xBF{\"log\": {\"entries\": []}} trailing";
+        assert!(parse_lenient(data).is_ok());
+        assert!(parse_lenient(b"").is_err());
+        assert!(parse_lenient(br#"{"entries": []}"#).is_err());
+    }
+
+    fn streamed(data: &[u8], lenient: bool) -> (serde_json::Result<usize>, Vec<StreamedEntry>) {
+        let mut entries = Vec::new();
+        let result = stream_entries(data, lenient, |x| {
+            entries.push(x);
+            true
+        });
+        (result, entries)
+    }
+
+    #[test]
+    fn streams_entries_with_their_indices() {
+        let document = json!({ "log": { "pages": null, "entries": [
+            entry("https://a.com/0"),
+            { "request": { "url": "not a url" } },
+            entry("https://a.com/2"),
+        ] } });
+        let (result, entries) = streamed(document.to_string().as_bytes(), true);
+        assert_eq!(result.unwrap(), 3);
+        let indices = entries
+            .iter()
+            .map(|x| x.as_ref().map(|(index, _)| *index).map_err(|x| x.index))
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [Ok(0), Err(1), Ok(2)]);
+    }
+
+    #[test]
+    fn stops_streaming_when_asked() {
+        let document = json!({ "log": { "entries": [
+            entry("https://a.com/0"),
+            entry("https://a.com/1"),
+        ] } });
+        let mut count = 0;
+        let result = stream_entries(document.to_string().as_bytes(), true, |_| {
+            count += 1;
+            false
+        });
+        assert_eq!(result.unwrap(), 1);
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn streams_strictly_like_parse() {
+        let data = format!(r#"{{"log": {{"entries": [{}]}}}}"#, entry("https://a.com/"));
+        assert_eq!(streamed(data.as_bytes(), false).0.unwrap(), 1);
+        let trailing = format!("{data} trailing");
+        assert!(streamed(trailing.as_bytes(), true).0.is_ok());
+        assert!(streamed(trailing.as_bytes(), false).0.is_err());
+        let bom = format!("\u{FEFF}{data}");
+        assert!(streamed(bom.as_bytes(), true).0.is_ok());
+        assert!(streamed(bom.as_bytes(), false).0.is_err());
+        for data in [&br#"{"log": {"entries": null}}"#[..], br#"{"log": {}}"#] {
+            assert_eq!(streamed(data, true).0.unwrap(), 0);
+            assert!(streamed(data, false).0.is_err());
+        }
+        assert!(streamed(br#"{"entries": []}"#, true).0.is_err());
+    }
+
+    #[test]
+    fn decodes_base64_with_or_without_padding() {
+        assert_eq!(decode_base64("aGk=").unwrap(), b"hi");
+        assert_eq!(decode_base64("aGk").unwrap(), b"hi");
+        assert_eq!(decode_base64("aA==").unwrap(), b"h");
+        assert_eq!(decode_base64("aA").unwrap(), b"h");
+        assert_eq!(decode_base64("").unwrap(), b"");
+    }
+
+    #[test]
+    fn decodes_wrapped_and_url_safe_base64() {
+        assert_eq!(
+            decode_base64("aGVs\r\nbG8g\nd29y bGQ=\n").unwrap(),
+            b"hello world"
+        );
+        assert_eq!(decode_base64("-_8").unwrap(), [0xFB, 0xFF]);
+        assert_eq!(decode_base64("+/8=").unwrap(), [0xFB, 0xFF]);
+        // Some encoders leave the unused bits of the last character set.
+        assert_eq!(decode_base64("aGl=").unwrap(), b"hi");
+    }
+
+    #[test]
+    fn rejects_what_is_not_base64() {
+        assert!(decode_base64("a").is_err());
+        assert!(decode_base64("aGk=aGk=").is_err());
+        assert!(decode_base64("+_8=").is_err());
+        assert!(decode_base64("not base64!").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    io,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use url::Url;
+
+/// Custom processing of each file an extraction writes, run once it is on disk.
+pub trait EntryHandler {
+    /// Called with the entry's index and URL and where its file was written; an error
+    /// fails the entry.
+    fn handle(&mut self, index: usize, url: &Url, path: &Path) -> Result<(), String>;
+}
+
+/// Runs a command for every file, such as `clamscan {path}`. `{path}`, `{url}` and
+/// `{index}` are replaced in each argument, and the command is started directly
+/// rather than through a shell, so URLs cannot inject shell syntax.
+pub struct ExecHandler {
+    args: Vec<String>,
+}
+
+impl ExecHandler {
+    /// Splits `command` into arguments at spaces outside single or double quotes.
+    pub fn new(command: &str) -> Result<ExecHandler, String> {
+        let mut args = Vec::new();
+        let mut current = None::<String>;
+        let mut quote = None;
+        for x in command.chars() {
+            match (quote, x) {
+                (Some(q), x) if x == q => quote = None,
+                (Some(_), x) => current.get_or_insert_default().push(x),
+                (None, '\'' | '"') => {
+                    quote = Some(x);
+                    current.get_or_insert_default();
+                }
+                (None, x) if x.is_whitespace() => args.extend(current.take()),
+                (None, x) => current.get_or_insert_default().push(x),
+            }
+        }
+        if quote.is_some() {
+            return Err("unbalanced quotes".to_string());
+        }
+        args.extend(current);
+        if args.is_empty() {
+            return Err("empty command".to_string());
+        }
+        Ok(ExecHandler { args })
+    }
+}
+
+/// Replaces every placeholder in one pass, so replaced text is never expanded again.
+pub fn expand(arg: &str, values: &[(&str, &str)]) -> String {
+    let mut result = String::new();
+    let mut rest = arg;
+    loop {
+        let found = values
+            .iter()
+            .filter_map(|(name, value)| Some((rest.find(name)?, *name, *value)))
+            .min_by_key(|(position, _, _)| *position);
+        let Some((position, name, value)) = found else {
+            result.push_str(rest);
+            return result;
+        };
+        result.push_str(&rest[..position]);
+        result.push_str(value);
+        rest = &rest[position + name.len()..];
+    }
+}
+
+impl EntryHandler for ExecHandler {
+    fn handle(&mut self, index: usize, url: &Url, path: &Path) -> Result<(), String> {
+        let path = path.to_string_lossy();
+        let index = index.to_string();
+        let values = [
+            ("{path}", path.as_ref()),
+            ("{url}", url.as_str()),
+            ("{index}", index.as_str()),
+        ];
+        let program = expand(&self.args[0], &values);
+        let status = Command::new(&program)
+            .args(self.args[1..].iter().map(|x| expand(x, &values)))
+            // Stdout carries the JSON log and summary, which the command's output would
+            // break.
+            .stdout(Stdio::from(io::stderr()))
+            .status()
+            .map_err(|err| format!("{program}: {err}"))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("{program}: {status}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(command: &str) -> Result<Vec<String>, String> {
+        ExecHandler::new(command).map(|x| x.args)
+    }
+
+    #[test]
+    fn splits_commands_at_spaces_outside_quotes() {
+        assert_eq!(
+            split("clamscan  --no-summary {path}").unwrap(),
+            ["clamscan", "--no-summary", "{path}"]
+        );
+        assert_eq!(
+            split("cp '{path}' \"/tmp/a b\"").unwrap(),
+            ["cp", "{path}", "/tmp/a b"]
+        );
+        assert_eq!(split("echo it\"'\"s").unwrap(), ["echo", "it's"]);
+        assert_eq!(split("touch '' x").unwrap(), ["touch", "", "x"]);
+        assert_eq!(split("a\tb\n").unwrap(), ["a", "b"]);
+    }
+
+    #[test]
+    fn rejects_empty_commands_and_unbalanced_quotes() {
+        assert!(split("").is_err());
+        assert!(split("   ").is_err());
+        assert!(split("echo 'a").is_err());
+        assert!(split("echo \"a'").is_err());
+    }
+
+    #[test]
+    fn expands_placeholders_once() {
+        let values = [("{path}", "out/{url}"), ("{url}", "https://a.com/")];
+        assert_eq!(expand("{path}", &values), "out/{url}");
+        assert_eq!(
+            expand("{url}:{path}:{url}", &values),
+            "https://a.com/:out/{url}:https://a.com/"
+        );
+        assert_eq!(expand("--file={path}.txt", &values), "--file=out/{url}.txt");
+        assert_eq!(expand("{index}", &values), "{index}");
+    }
+}
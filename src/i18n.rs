@@ -0,0 +1,611 @@
+use std::{env, fmt::Display, sync::OnceLock};
+
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    En,
+    Ru,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+impl Lang {
+    /// Picks the language from the usual locale variables, falling back to English.
+    pub fn detect() -> Lang {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .map(|value| {
+                if value.to_lowercase().starts_with("ru") {
+                    Lang::Ru
+                } else {
+                    Lang::En
+                }
+            })
+            .unwrap_or(Lang::En)
+    }
+}
+
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+pub fn lang() -> Lang {
+    *LANG.get_or_init(Lang::detect)
+}
+
+macro_rules! catalog {
+    ($($key:ident { en: $en:literal, ru: $ru:literal })*) => {
+        // Messages of feature-gated commands go unused in default builds.
+        #[allow(dead_code)]
+        #[derive(Clone, Copy)]
+        pub enum Msg {
+            $($key,)*
+        }
+
+        impl Msg {
+            fn template(self, lang: Lang) -> &'static str {
+                match (self, lang) {
+                    $(
+                        (Msg::$key, Lang::En) => $en,
+                        (Msg::$key, Lang::Ru) => $ru,
+                    )*
+                }
+            }
+        }
+    };
+}
+
+// Placeholders are positional (`{0}`, `{1}`, ...) so translations may reorder them.
+catalog! {
+    CannotParsePath {
+        en: "Cannot parse path {0}",
+        ru: "Не удалось разобрать путь {0}"
+    }
+    NotAFile {
+        en: "Specified path ({0}) is not a file",
+        ru: "Указанный путь ({0}) не является файлом"
+    }
+    CannotCreateDirs {
+        en: "Cannot create dirs at path {0}",
+        ru: "Не удалось создать каталоги по пути {0}"
+    }
+    ConfirmForeignFolder {
+        en: "{0} already holds files that were not extracted by this tool. Extract into it anyway? [y/N]",
+        ru: "В {0} уже есть файлы, извлечённые не этой программой. Всё равно извлечь туда? [y/N]"
+    }
+    ForeignFolder {
+        en: "Not extracting into {0}, which holds files that were not extracted by this tool; pass --force to do so anyway",
+        ru: "Извлечение в {0} отменено: там есть файлы, извлечённые не этой программой; чтобы извлечь всё равно, укажите --force"
+    }
+    LoadingFile {
+        en: "Loading file",
+        ru: "Загрузка файла"
+    }
+    CannotOpenFile {
+        en: "Cannot open file",
+        ru: "Не удалось открыть файл"
+    }
+    CannotParseHar {
+        en: "Cannot parse file as json to .har model: {0}",
+        ru: "Не удалось разобрать файл как json-модель .har: {0}"
+    }
+    EntryUnreadable {
+        en: "Skipping unreadable entry {0}: {1}",
+        ru: "Пропуск нечитаемой записи {0}: {1}"
+    }
+    OutputSettings {
+        en: "Extraction output settings:",
+        ru: "Параметры вывода извлечения:"
+    }
+    NoDirectoryStructure {
+        en: "- do not create any directory structure - extract images directly to base folder",
+        ru: "- не создавать структуру каталогов - извлекать изображения прямо в базовую папку"
+    }
+    OutputDomainRequired {
+        en: "--output-domain is required in this context",
+        ru: "в этом случае требуется --output-domain"
+    }
+    DomainSubfolders {
+        en: "- create subfolders for domain {0}",
+        ru: "- создавать подпапки для домена {0}"
+    }
+    PathSubfolders {
+        en: " - create subfolders for URL path: {0} (only for {1} {2} parts)",
+        ru: " - создавать подпапки для пути URL: {0} (только для {1} {2} частей)"
+    }
+    MaxDepthSubfolders {
+        en: " - create subfolders for at most the first {0} parts of the URL path",
+        ru: " - создавать подпапки не более чем для первых {0} частей пути URL"
+    }
+    FlattenedPaths {
+        en: "- create subfolders for domains only, with every file directly in its domain folder",
+        ru: "- создавать подпапки только для доменов, все файлы прямо в папке своего домена"
+    }
+    First {
+        en: "first",
+        ru: "первых"
+    }
+    Last {
+        en: "last",
+        ru: "последних"
+    }
+    StartingExtraction {
+        en: "Starting extraction...",
+        ru: "Начинаем извлечение..."
+    }
+    Extracted {
+        en: "- {0}: extracted to {1} [{2} bytes]",
+        ru: "- {0}: извлечён в {1} [{2} байт]"
+    }
+    Finished {
+        en: "Finished extracting {0} (out of total {1}) files.",
+        ru: "Извлечение завершено: {0} файлов (всего {1})."
+    }
+    SourcesUnpacked {
+        en: "Unpacked {0} sources from {1} source maps.",
+        ru: "Восстановлено исходных файлов: {0} из карт кода: {1}."
+    }
+    StreamsAssembled {
+        en: "Assembled {0} media streams.",
+        ru: "Собрано медиапотоков: {0}."
+    }
+    StreamIncomplete {
+        en: "{0}: {1} of {2} segments are missing from the capture",
+        ru: "{0}: в записи нет {1} из {2} сегментов"
+    }
+    StreamEncrypted {
+        en: "{0}: segments are encrypted and cannot be joined",
+        ru: "{0}: сегменты зашифрованы, их нельзя объединить"
+    }
+    ReportWritten {
+        en: "Report written to {0}",
+        ru: "Отчёт записан в {0}"
+    }
+    ReportTitle {
+        en: "Extraction of {0}",
+        ru: "Извлечение из {0}"
+    }
+    ReportEntries {
+        en: "Entries",
+        ru: "Записей"
+    }
+    ReportExtracted {
+        en: "Extracted",
+        ru: "Извлечено"
+    }
+    ReportSkipped {
+        en: "Skipped",
+        ru: "Пропущено"
+    }
+    ReportFailed {
+        en: "Failed",
+        ru: "С ошибками"
+    }
+    ReportReason {
+        en: "REASON",
+        ru: "ПРИЧИНА"
+    }
+    ReportSkippedByReason {
+        en: "Skipped by reason",
+        ru: "Пропущено по причинам"
+    }
+    GalleryWritten {
+        en: "Thumbnail gallery written to {0}",
+        ru: "Галерея миниатюр записана в {0}"
+    }
+    OverBudgetCount {
+        en: "{0} files were not written as they would have gone over --max-total-size {1}.",
+        ru: "Не записано файлов сверх --max-total-size {1}: {0}."
+    }
+    UndecodableCount {
+        en: "{0} bodies could not be decoded from base64.",
+        ru: "Тел, которые не удалось декодировать из base64: {0}."
+    }
+    CannotReadCheckpoint {
+        en: "Cannot read the checkpoint {0}: {1}",
+        ru: "Не удалось прочитать контрольную точку {0}: {1}"
+    }
+    CheckpointMismatch {
+        en: "The checkpoint {0} was saved extracting {1}, which has changed since or is not the input",
+        ru: "Контрольная точка {0} сохранена при извлечении {1}: файл изменился или не совпадает с входным"
+    }
+    Resuming {
+        en: "Resuming after the first {0} files, which the checkpoint lists as done.",
+        ru: "Продолжение после первых файлов, завершённых по контрольной точке: {0}."
+    }
+    UnchangedCount {
+        en: "{0} files were already up to date.",
+        ru: "Файлов уже в актуальном состоянии: {0}."
+    }
+    CannotOpenProgressSocket {
+        en: "Cannot open progress socket {0}",
+        ru: "Не удалось открыть сокет прогресса {0}"
+    }
+    CannotParseJson {
+        en: "Cannot parse {0} as json",
+        ru: "Не удалось разобрать {0} как json"
+    }
+    RedactSummary {
+        en: "Masked {0} headers, {1} cookies, {2} parameters, removed {3} bodies; written to {4}",
+        ru: "Скрыто заголовков: {0}, cookie: {1}, параметров: {2}, удалено тел: {3}; записано в {4}"
+    }
+    PackSummary {
+        en: "Packed {0} files, {1} of them edited, into {2}; {3} could not be packed",
+        ru: "В {2} упаковано файлов: {0}, из них изменено: {1}; не удалось упаковать: {3}"
+    }
+    PackEntryMismatch {
+        en: "{0}: the original capture has no response of this URL at entry {1}",
+        ru: "{0}: в исходном захвате нет ответа с этим URL в записи {1}"
+    }
+    PackNothing {
+        en: "The manifest in {0} lists no response bodies; extract the capture again to record them",
+        ru: "Манифест в {0} не содержит тел ответов; извлеките захват заново, чтобы они были записаны"
+    }
+    MergeSummary {
+        en: "Merged {0} entries from {1} files ({2} duplicates dropped) into {3}",
+        ru: "Объединено записей: {0} из файлов: {1} (отброшено дубликатов: {2}) в {3}"
+    }
+    FilterSummary {
+        en: "Kept {0} of {1} entries; written to {2}",
+        ru: "Оставлено записей: {0} из {1}; записано в {2}"
+    }
+    EntryNotFound {
+        en: "No matching entry in the capture",
+        ru: "В файле HAR нет подходящей записи"
+    }
+    GeneratedFiles {
+        en: "Written to {0}",
+        ru: "Записано в {0}"
+    }
+    CannotWriteFile {
+        en: "Cannot write file {0}",
+        ru: "Не удалось записать файл {0}"
+    }
+    CannotDecodeBody {
+        en: "cannot decode body: {0}",
+        ru: "не удалось декодировать тело: {0}"
+    }
+    FetchingMissing {
+        en: "Fetching {0} missing bodies...",
+        ru: "Загрузка недостающих тел: {0}..."
+    }
+    CannotFetchBody {
+        en: "cannot fetch body: {0}",
+        ru: "не удалось загрузить тело: {0}"
+    }
+    DecompressedTooLarge {
+        en: "decompresses to more than {0}; written compressed (see --max-decompressed-size)",
+        ru: "после распаковки больше {0}; записано сжатым (см. --max-decompressed-size)"
+    }
+    CannotTranscodeBody {
+        en: "body is not valid {0} text; malformed bytes were replaced",
+        ru: "тело не является корректным текстом {0}; повреждённые байты заменены"
+    }
+    CannotConvertImage {
+        en: "cannot convert image: {0}",
+        ru: "не удалось преобразовать изображение: {0}"
+    }
+    PathOutsideOutput {
+        en: "refusing to write {0}, which leads outside the output folder",
+        ru: "файл {0} не записан: путь ведёт за пределы выходной папки"
+    }
+    CannotCreateThumbnail {
+        en: "cannot create thumbnail: {0}",
+        ru: "не удалось создать миниатюру: {0}"
+    }
+    EntryFailed {
+        en: "- {0}: failed: {1}",
+        ru: "- {0}: ошибка: {1}"
+    }
+    EntrySkipped {
+        en: "- {0}: skipped ({1})",
+        ru: "- {0}: пропущен ({1})"
+    }
+    FailureCount {
+        en: "{0} failures occurred during extraction.",
+        ru: "Ошибок при извлечении: {0}."
+    }
+    ErrorBundleWritten {
+        en: "Error bundle written to {0}",
+        ru: "Отчёт об ошибках записан в {0}"
+    }
+    CannotWriteErrorBundle {
+        en: "Cannot write error bundle {0}: {1}",
+        ru: "Не удалось записать отчёт об ошибках {0}: {1}"
+    }
+    CookiesExported {
+        en: "Cookies exported to {0}",
+        ru: "Cookie экспортированы в {0}"
+    }
+    TuiFilter {
+        en: "Filter:",
+        ru: "Фильтр:"
+    }
+    TuiTitle {
+        en: " {0} of {1} entries selected ",
+        ru: " Выбрано записей: {0} из {1} "
+    }
+    TuiHelp {
+        en: "Type to filter, Space select, Ctrl+A select all shown, Enter extract, Esc cancel",
+        ru: "Ввод - фильтр, Space - выбрать, Ctrl+A - выбрать все видимые, Enter - извлечь, Esc - отмена"
+    }
+    SelectionCancelled {
+        en: "Selection cancelled, nothing extracted.",
+        ru: "Выбор отменён, ничего не извлечено."
+    }
+    CannotReachDevTools {
+        en: "Cannot reach Chrome DevTools at {0}:{1}: {2}",
+        ru: "Не удалось подключиться к Chrome DevTools на {0}:{1}: {2}"
+    }
+    NoDevToolsTarget {
+        en: "No matching page found among Chrome's debugging targets",
+        ru: "Среди отлаживаемых вкладок Chrome нет подходящей страницы"
+    }
+    DevToolsConnectionFailed {
+        en: "DevTools connection failed: {0}",
+        ru: "Ошибка соединения с DevTools: {0}"
+    }
+    CannotParseOutputUrl {
+        en: "Cannot use {0} as the upload target: {1}",
+        ru: "Нельзя выгружать в {0}: {1}"
+    }
+    CannotParseExec {
+        en: "Cannot run {0}: {1}",
+        ru: "Нельзя выполнить {0}: {1}"
+    }
+    HandlerFailed {
+        en: "{0}: post-processing failed: {1}",
+        ru: "{0}: ошибка последующей обработки: {1}"
+    }
+    Uploading {
+        en: "Uploading {0} files to {1}...",
+        ru: "Выгрузка файлов: {0} в {1}..."
+    }
+    Uploaded {
+        en: "Uploaded {0} files.",
+        ru: "Выгружено файлов: {0}."
+    }
+    WatchingFolder {
+        en: "Watching {0} for new HAR files, press Ctrl+C to stop...",
+        ru: "Ожидание новых файлов HAR в {0}, нажмите Ctrl+C для остановки..."
+    }
+    CannotWatchFolder {
+        en: "Cannot watch folder {0}: {1}",
+        ru: "Не удалось следить за папкой {0}: {1}"
+    }
+    WatchFound {
+        en: "New capture {0}",
+        ru: "Новый файл {0}"
+    }
+    WatchFailed {
+        en: "{0} was not extracted: {1}",
+        ru: "{0} не извлечён: {1}"
+    }
+    CaptureStarted {
+        en: "Recording network traffic, press Ctrl+C to stop...",
+        ru: "Запись сетевого трафика, нажмите Ctrl+C для остановки..."
+    }
+    CannotRecordUrl {
+        en: "Cannot fetch {0}: {1}",
+        ru: "Не удалось загрузить {0}: {1}"
+    }
+    CaptureWritten {
+        en: "Recorded {0} entries to {1}",
+        ru: "Записано {0} записей в {1}"
+    }
+    PageNotFound {
+        en: "The HAR file contains no such page with an HTML document",
+        ru: "В файле HAR нет такой страницы с HTML-документом"
+    }
+    ProfileNotFound {
+        en: "No profile {0} in {1}",
+        ru: "В {1} нет профиля {0}"
+    }
+    CannotParseConfig {
+        en: "Cannot read configuration file {0}: {1}",
+        ru: "Не удалось прочитать файл настроек {0}: {1}"
+    }
+    OutputFileRequired {
+        en: "This format needs an output file, given with --output",
+        ru: "Для этого формата нужен выходной файл, укажите его в --output"
+    }
+    CannotWriteDatabase {
+        en: "Cannot write database {0}: {1}",
+        ru: "Не удалось записать базу данных {0}: {1}"
+    }
+    CannotCreateIndex {
+        en: "Cannot create search index: {0}",
+        ru: "Не удалось создать поисковый индекс: {0}"
+    }
+    CannotOpenIndex {
+        en: "Cannot read search index: {0}",
+        ru: "Не удалось прочитать поисковый индекс: {0}"
+    }
+    InvalidQuery {
+        en: "Invalid query: {0}",
+        ru: "Некорректный запрос: {0}"
+    }
+    IndexCreated {
+        en: "Indexed {0} entries ({1} with text bodies)",
+        ru: "Проиндексировано записей: {0} (с текстовым телом: {1})"
+    }
+    ValidateError {
+        en: "error at {0}: {1}",
+        ru: "ошибка в {0}: {1}"
+    }
+    ValidateDocumentError {
+        en: "error in the document: {0}",
+        ru: "ошибка в документе: {0}"
+    }
+    ValidateWarning {
+        en: "warning at {0}: {1}",
+        ru: "предупреждение в {0}: {1}"
+    }
+    ValidateMissing {
+        en: "missing required field",
+        ru: "отсутствует обязательное поле"
+    }
+    ValidateWrongType {
+        en: "expected {0}",
+        ru: "ожидается {0}"
+    }
+    ValidateOutOfRange {
+        en: "{0} is out of range",
+        ru: "значение {0} вне допустимого диапазона"
+    }
+    ValidateInvalidDate {
+        en: "not an ISO 8601 date and time",
+        ru: "не является датой и временем ISO 8601"
+    }
+    ValidateInvalidUrl {
+        en: "not an absolute URL",
+        ru: "не является абсолютным URL"
+    }
+    ValidateUnknownEncoding {
+        en: "unknown encoding {0}",
+        ru: "неизвестная кодировка {0}"
+    }
+    ValidateInvalidBase64 {
+        en: "text is declared as base64 but does not decode",
+        ru: "текст объявлен как base64, но не декодируется"
+    }
+    ValidateUnknownPage {
+        en: "no page with id {0}",
+        ru: "нет страницы с id {0}"
+    }
+    ValidateByteOrderMark {
+        en: "starts with a byte order mark, which JSON does not allow",
+        ru: "начинается с метки порядка байтов, недопустимой в JSON"
+    }
+    ValidateTrailingData {
+        en: "{0} bytes of data after the end of the JSON value",
+        ru: "после конца значения JSON идут данные, байт: {0}"
+    }
+    ValidateSummary {
+        en: "{0} errors, {1} warnings in {2} entries",
+        ru: "ошибок: {0}, предупреждений: {1}, записей: {2}"
+    }
+    ScanFinding {
+        en: "{0}  {1}  in {2}, entries {3}",
+        ru: "{0}  {1}  в {2}, записи {3}"
+    }
+    InvalidPattern {
+        en: "Invalid regular expression: {0}",
+        ru: "Неверное регулярное выражение: {0}"
+    }
+    ScanSummary {
+        en: "{0} possible secrets in {1} entries",
+        ru: "возможных секретов: {0}, записей: {1}"
+    }
+    VerifyMissing {
+        en: "MISSING {0}",
+        ru: "НЕТ ФАЙЛА {0}"
+    }
+    VerifyMismatch {
+        en: "CHANGED {0}",
+        ru: "ИЗМЕНЁН {0}"
+    }
+    VerifySummary {
+        en: "{0} files intact, {1} changed, {2} missing",
+        ru: "файлов без изменений: {0}, изменено: {1}, отсутствует: {2}"
+    }
+    RelayoutSameFolder {
+        en: "The new layout must go to another folder",
+        ru: "Новую структуру нужно создавать в другой папке"
+    }
+    RelayoutEmptyPath {
+        en: "the template gives it no file name",
+        ru: "шаблон не даёт ему имени файла"
+    }
+    RelayoutSummary {
+        en: "{0} files linked and {1} copied into {3}, {2} failed",
+        ru: "в {3} связано файлов: {0}, скопировано: {1}, с ошибками: {2}"
+    }
+    DiffSummary {
+        en: "{0} entries only in old, {1} only in new, {2} changed; response size {3} bytes",
+        ru: "записей только в старом: {0}, только в новом: {1}, изменено: {2}; размер ответов {3} байт"
+    }
+    DiffSummaryHeadersOnly {
+        en: "{0} entries only in old, {1} only in new, {2} changed",
+        ru: "записей только в старом: {0}, только в новом: {1}, изменено: {2}"
+    }
+    ColumnCount {
+        en: "COUNT",
+        ru: "ЧИСЛО"
+    }
+    ColumnBytes {
+        en: "BYTES",
+        ru: "БАЙТ"
+    }
+    ColumnTransferred {
+        en: "TRANSFER",
+        ru: "ПЕРЕДАНО"
+    }
+    ColumnTime {
+        en: "TIME",
+        ru: "ВРЕМЯ"
+    }
+    ColumnWait {
+        en: "WAIT",
+        ru: "ОЖИДАНИЕ"
+    }
+    ColumnName {
+        en: "NAME",
+        ru: "ИМЯ"
+    }
+    StatsByDomain {
+        en: "By domain:",
+        ru: "По доменам:"
+    }
+    StatsByMimeType {
+        en: "By MIME type:",
+        ru: "По MIME-типам:"
+    }
+    StatsByStatus {
+        en: "By status code:",
+        ru: "По кодам ответа:"
+    }
+    StatsLargest {
+        en: "Largest {0} responses:",
+        ru: "Крупнейшие ответы ({0}):"
+    }
+    StatsSlowest {
+        en: "Slowest {0} requests:",
+        ru: "Самые медленные запросы ({0}):"
+    }
+    StatsTotal {
+        en: "Total: {0} entries, {1}",
+        ru: "Итого: {0} записей, {1}"
+    }
+}
+
+pub fn format(msg: Msg, args: &[&dyn Display]) -> String {
+    let mut result = String::new();
+    let mut rest = msg.template(lang());
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find('}').map(|x| start + x);
+        match end.and_then(|end| rest[start + 1..end].parse::<usize>().ok().zip(Some(end))) {
+            Some((index, end)) if index < args.len() => {
+                result.push_str(&args[index].to_string());
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                result.push('{');
+                rest = &rest[start + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Looks up a catalog message in the current language and fills in its placeholders.
+macro_rules! tr {
+    ($key:ident $(, $arg:expr)* $(,)?) => {
+        $crate::i18n::format($crate::i18n::Msg::$key, &[$(&$arg),*])
+    };
+}
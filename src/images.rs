@@ -0,0 +1,129 @@
+use std::{
+    fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+};
+
+use image::{ImageError, ImageFormat};
+use url::Url;
+
+use crate::report::escape_html;
+
+/// A `from=to` pair given to `--convert`.
+#[derive(Clone, Copy, Debug)]
+pub struct Conversion {
+    from: ImageFormat,
+    to: ImageFormat,
+}
+
+fn parse_format(name: &str) -> Result<ImageFormat, String> {
+    ImageFormat::from_extension(name.trim()).ok_or_else(|| format!("unknown image format {name}"))
+}
+
+pub fn parse_conversion(value: &str) -> Result<Conversion, String> {
+    let (from, to) = value
+        .split_once('=')
+        .ok_or("expected a conversion such as webp=png")?;
+    let to = parse_format(to)?;
+    if !to.writing_enabled() {
+        return Err(format!("cannot write {} images", to.extensions_str()[0]));
+    }
+    Ok(Conversion {
+        from: parse_format(from)?,
+        to,
+    })
+}
+
+/// Re-encodes an image if one of `conversions` applies to its MIME type, returning the
+/// new bytes and the file extension (with the dot) they should be saved under.
+pub fn convert(
+    conversions: &[Conversion],
+    essence: &str,
+    bytes: &[u8],
+) -> Option<Result<(Vec<u8>, String), ImageError>> {
+    let format = ImageFormat::from_mime_type(essence)?;
+    let conversion = conversions.iter().find(|x| x.from == format)?;
+    let result = image::load_from_memory_with_format(bytes, format).and_then(|image| {
+        let mut result = Cursor::new(Vec::new());
+        // Formats without an alpha channel (JPEG) reject RGBA input.
+        let image = if conversion.to == ImageFormat::Jpeg {
+            image::DynamicImage::ImageRgb8(image.to_rgb8())
+        } else {
+            image
+        };
+        image.write_to(&mut result, conversion.to)?;
+        Ok((
+            result.into_inner(),
+            format!(".{}", conversion.to.extensions_str()[0]),
+        ))
+    });
+    Some(result)
+}
+
+/// Resized previews of the extracted images and an `index.html` showing them.
+pub struct Gallery {
+    dir: PathBuf,
+    size: u32,
+    /// `(preview src, original file)` in extraction order.
+    items: Vec<(String, PathBuf)>,
+}
+
+impl Gallery {
+    pub fn new(dir: PathBuf, size: u32) -> io::Result<Gallery> {
+        fs::create_dir_all(&dir)?;
+        Ok(Gallery {
+            dir: dir.canonicalize()?,
+            size,
+            items: Vec::new(),
+        })
+    }
+
+    /// Adds an extracted image. SVG is shown as is since browsers render it directly.
+    pub fn add(&mut self, original: &Path, essence: &str, bytes: &[u8]) -> Result<(), ImageError> {
+        let original = original.canonicalize().map_err(ImageError::IoError)?;
+        if essence == "image/svg+xml" {
+            let src = Url::from_file_path(&original)
+                .map(String::from)
+                .unwrap_or_default();
+            self.items.push((src, original));
+            return Ok(());
+        }
+        let thumbnail = image::load_from_memory(bytes)?.thumbnail(self.size, self.size);
+        let name = format!("{:05}.png", self.items.len());
+        thumbnail.save_with_format(self.dir.join(&name), ImageFormat::Png)?;
+        self.items.push((name, original));
+        Ok(())
+    }
+
+    pub fn index(&self) -> PathBuf {
+        self.dir.join("index.html")
+    }
+
+    pub fn finish(&self) -> io::Result<()> {
+        let size = self.size;
+        let mut html = format!(
+            "<!DOCTYPE html>\n<meta charset=\"utf-8\">\n<title>{} images</title>\n<style>\
+             body{{font-family:sans-serif}}\
+             figure{{display:inline-block;margin:8px;width:{size}px;vertical-align:top}}\
+             img{{max-width:{size}px;max-height:{size}px}}\
+             figcaption{{font-size:12px;word-break:break-all}}</style>\n",
+            self.items.len()
+        );
+        for (src, original) in &self.items {
+            let href = Url::from_file_path(original)
+                .map(String::from)
+                .unwrap_or_default();
+            let label = original
+                .file_name()
+                .map(|x| x.to_string_lossy())
+                .unwrap_or_default();
+            html.push_str(&format!(
+                "<figure><a href=\"{}\"><img src=\"{}\" loading=\"lazy\"></a><figcaption>{}</figcaption></figure>\n",
+                escape_html(&href),
+                escape_html(src),
+                escape_html(&label)
+            ));
+        }
+        fs::write(self.index(), html)
+    }
+}
@@ -0,0 +1,131 @@
+use std::{fs, io, path::Path};
+
+use base64::Engine;
+use chrono::{DateTime, FixedOffset, SecondsFormat};
+use serde_json::{json, Value};
+
+use crate::{fiddler, mitmproxy};
+
+/// One request and its response as recorded by a capture tool other than a browser.
+#[derive(Default)]
+pub struct Exchange {
+    pub started: Option<DateTime<FixedOffset>>,
+    /// Milliseconds from sending the request to receiving the whole response.
+    pub time: f64,
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Vec<u8>,
+    pub status: i64,
+    pub status_text: String,
+    pub response_headers: Vec<(String, String)>,
+    /// Body as sent, with any `Content-Encoding` still applied.
+    pub response_body: Vec<u8>,
+    pub server_ip_address: Option<String>,
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(x, _)| x.eq_ignore_ascii_case(name))
+        .map(|(_, x)| x.as_str())
+}
+
+fn har_headers(headers: &[(String, String)]) -> Value {
+    headers
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+impl Exchange {
+    fn to_entry(&self) -> Value {
+        let mut entry = json!({
+            "startedDateTime": self
+                .started
+                .map(|x| x.to_rfc3339_opts(SecondsFormat::Millis, true))
+                .unwrap_or_default(),
+            "time": self.time,
+            "request": {
+                "method": self.method,
+                "url": self.url,
+                "httpVersion": self.http_version,
+                "headers": har_headers(&self.request_headers),
+                "cookies": [],
+                "queryString": [],
+                "headersSize": -1,
+                "bodySize": self.request_body.len(),
+            },
+            "response": {
+                "status": self.status,
+                "statusText": self.status_text,
+                "httpVersion": self.http_version,
+                "headers": har_headers(&self.response_headers),
+                "cookies": [],
+                "content": {
+                    "size": self.response_body.len(),
+                    "mimeType": header(&self.response_headers, "content-type").unwrap_or_default(),
+                    "text": Engine::encode(
+                        &base64::engine::general_purpose::STANDARD,
+                        &self.response_body
+                    ),
+                    "encoding": "base64",
+                },
+                "redirectURL": header(&self.response_headers, "location").unwrap_or_default(),
+                "headersSize": -1,
+                "bodySize": self.response_body.len(),
+            },
+            "cache": {},
+            "timings": { "send": 0, "wait": self.time, "receive": 0 },
+        });
+        if !self.request_body.is_empty() {
+            entry["request"]["postData"] = json!({
+                "mimeType": header(&self.request_headers, "content-type").unwrap_or_default(),
+                "text": String::from_utf8_lossy(&self.request_body),
+            });
+        }
+        if let Some(ip) = &self.server_ip_address {
+            entry["serverIPAddress"] = json!(ip);
+        }
+        entry
+    }
+}
+
+/// A HAR document holding the given exchanges.
+pub fn document(exchanges: &[Exchange]) -> Value {
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+            "entries": exchanges.iter().map(Exchange::to_entry).collect::<Vec<_>>(),
+        }
+    })
+}
+
+pub fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Whether [`read`] takes the file at `path` for a capture to convert rather than a
+/// HAR.
+pub fn converts(path: &Path) -> bool {
+    path.extension().is_some_and(|x| {
+        matches!(
+            x.to_string_lossy().to_lowercase().as_str(),
+            "flows" | "mitm" | "saz"
+        )
+    })
+}
+
+/// Reads a mitmproxy (`.flows`, `.mitm`) or Fiddler (`.saz`) capture as a HAR
+/// document. Returns `None` for other files, which are expected to be HAR.
+pub fn read(path: &Path) -> Option<io::Result<Value>> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    let exchanges = match extension.as_str() {
+        "flows" | "mitm" => fs::read(path).and_then(|x| mitmproxy::parse(&x)),
+        "saz" => fs::File::open(path).and_then(fiddler::parse),
+        _ => return None,
+    };
+    Some(exchanges.map(|x| document(&x)))
+}
@@ -0,0 +1,119 @@
+use std::{fs, path::PathBuf};
+
+use clap::Args;
+use tantivy::{
+    collector::TopDocs,
+    doc,
+    query::QueryParser,
+    schema::{Field, Schema, Value, STORED, STRING, TEXT},
+    Index, IndexWriter, TantivyDocument,
+};
+
+use crate::input;
+
+#[derive(Args)]
+pub struct IndexArgs {
+    input_har: String,
+    /// Folder to create the index in
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Args)]
+pub struct SearchArgs {
+    index_dir: PathBuf,
+    /// Query in tantivy syntax, matched against URLs and decoded text bodies
+    query: String,
+    /// Maximum number of hits to print
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+}
+
+struct Fields {
+    index: Field,
+    url: Field,
+    mime_type: Field,
+    status: Field,
+    body: Field,
+}
+
+fn schema() -> (Schema, Fields) {
+    let mut builder = Schema::builder();
+    let fields = Fields {
+        index: builder.add_u64_field("index", STORED),
+        url: builder.add_text_field("url", TEXT | STORED),
+        mime_type: builder.add_text_field("mime_type", STRING | STORED),
+        status: builder.add_u64_field("status", STORED),
+        body: builder.add_text_field("body", TEXT),
+    };
+    (builder.build(), fields)
+}
+
+pub fn run_index(args: IndexArgs) {
+    let har = input::load(&input::resolve_input(&args.input_har));
+    fs::create_dir_all(&args.output).unwrap_or_else(|_| {
+        pexit!(CannotCreateDirs, args.output.to_string_lossy());
+    });
+    let (schema, fields) = schema();
+    let index = Index::create_in_dir(&args.output, schema)
+        .unwrap_or_else(|err| pexit!(CannotCreateIndex, err));
+    let mut writer: IndexWriter = index
+        .writer(50_000_000)
+        .unwrap_or_else(|err| pexit!(CannotCreateIndex, err));
+    let mut count_bodies = 0;
+    for (i, entry) in har.log.indexed_entries() {
+        let content = &entry.response.content;
+        // Only bodies that decode to valid UTF-8 are indexed; binary assets are still
+        // findable by URL.
+        let body = content
+            .decoded()
+            .and_then(|x| String::from_utf8(x).ok())
+            .unwrap_or_default();
+        if !body.is_empty() {
+            count_bodies += 1;
+        }
+        writer
+            .add_document(doc!(
+                fields.index => i as u64,
+                fields.url => entry.request.url.as_str(),
+                fields.mime_type => content.essence(),
+                fields.status => entry.response.status.max(0) as u64,
+                fields.body => body,
+            ))
+            .unwrap_or_else(|err| pexit!(CannotCreateIndex, err));
+    }
+    writer
+        .commit()
+        .unwrap_or_else(|err| pexit!(CannotCreateIndex, err));
+    eprintln!("{}", tr!(IndexCreated, har.log.entries.len(), count_bodies));
+}
+
+pub fn run_search(args: SearchArgs) {
+    let index =
+        Index::open_in_dir(&args.index_dir).unwrap_or_else(|err| pexit!(CannotOpenIndex, err));
+    let (_, fields) = schema();
+    let reader = index
+        .reader()
+        .unwrap_or_else(|err| pexit!(CannotOpenIndex, err));
+    let searcher = reader.searcher();
+    let query = QueryParser::for_index(&index, vec![fields.url, fields.body])
+        .parse_query(&args.query)
+        .unwrap_or_else(|err| pexit!(InvalidQuery, err));
+    let hits = searcher
+        .search(&query, &TopDocs::with_limit(args.limit).order_by_score())
+        .unwrap_or_else(|err| pexit!(CannotOpenIndex, err));
+    for (_, address) in hits {
+        let document: TantivyDocument = searcher
+            .doc(address)
+            .unwrap_or_else(|err| pexit!(CannotOpenIndex, err));
+        let u64_of = |field| document.get_first(field).and_then(|x| x.as_u64());
+        let str_of = |field| document.get_first(field).and_then(|x| x.as_str());
+        println!(
+            "#{} {} {} {}",
+            u64_of(fields.index).unwrap_or_default(),
+            u64_of(fields.status).unwrap_or_default(),
+            str_of(fields.mime_type).unwrap_or_default(),
+            str_of(fields.url).unwrap_or_default()
+        );
+    }
+}
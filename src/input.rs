@@ -0,0 +1,190 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver},
+        OnceLock,
+    },
+    thread::{self, JoinHandle},
+};
+
+use extract_har::{
+    har::{self, Har, SkippedEntry, StreamedEntry},
+    import,
+    session::AnalysisSession,
+};
+use serde_json::Value;
+
+use crate::exit_code;
+
+static STRICT: OnceLock<bool> = OnceLock::new();
+
+/// Makes every command reject nonstandard documents instead of repairing them.
+pub fn set_strict(strict: bool) {
+    let _ = STRICT.set(strict);
+}
+
+fn is_strict() -> bool {
+    *STRICT.get().unwrap_or(&false)
+}
+
+fn keep_skipped((mut har, skipped): (Har, Vec<SkippedEntry>)) -> Har {
+    har.log.skipped = skipped;
+    har
+}
+
+/// Why [`try_load`] could not load a capture.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read.
+    Open(io::Error),
+    /// The file is not a HAR, nor a capture in a format converted to one.
+    Parse(io::Error),
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(err: serde_json::Error) -> LoadError {
+        LoadError::Parse(err.into())
+    }
+}
+
+/// Resolves the HAR path given on the command line, exiting if it is not a file.
+pub fn resolve_input(input_har: &str) -> PathBuf {
+    try_resolve_input(input_har).unwrap_or_else(|message| {
+        eprintln!("{message}");
+        std::process::exit(1);
+    })
+}
+
+/// Like [`resolve_input`], but returns the message instead of exiting.
+pub fn try_resolve_input(input_har: &str) -> Result<PathBuf, String> {
+    let input_file_path = Path::new(input_har)
+        .canonicalize()
+        .map_err(|_| tr!(CannotParsePath, input_har))?;
+    if !input_file_path.is_file() {
+        return Err(tr!(NotAFile, input_har));
+    }
+    Ok(input_file_path)
+}
+
+pub fn load(input_file_path: &Path) -> Har {
+    let mut har = try_load(input_file_path).unwrap_or_else(|err| match err {
+        LoadError::Open(_) => pexit!(CannotOpenFile),
+        LoadError::Parse(err) => {
+            pexit!(status = exit_code::PARSE_ERROR; CannotParseHar, format!("{err:?}"));
+        }
+    });
+    for entry in har.log.skipped.drain(..) {
+        eprintln!("{}", tr!(EntryUnreadable, entry.index, entry.reason));
+    }
+    har
+}
+
+/// Like [`load`], but leaves reporting a missing or malformed document to the caller.
+/// mitmproxy and Fiddler captures are converted to HAR first. Unless `--strict-har` is
+/// given, nonstandard documents are repaired and unreadable entries are left out, listed
+/// in [`HarLog::skipped`](har::HarLog::skipped).
+pub fn try_load(input_file_path: &Path) -> Result<Har, LoadError> {
+    if let Some(document) = import::read(input_file_path) {
+        let document = document.map_err(LoadError::Parse)?;
+        if is_strict() {
+            return Ok(serde_json::from_value(document)?);
+        }
+        return Ok(keep_skipped(har::from_value_lenient(document)?));
+    }
+    if is_strict() {
+        let input_file = File::open(input_file_path).map_err(LoadError::Open)?;
+        return Ok(har::parse(BufReader::new(input_file))?);
+    }
+    let data = fs::read(input_file_path).map_err(LoadError::Open)?;
+    Ok(keep_skipped(har::parse_lenient(&data)?))
+}
+
+/// The thread started by [`try_stream`], which gives the number of entries it read.
+pub type Parser = JoinHandle<Result<usize, LoadError>>;
+
+/// Like [`try_load`], but reads the entries on a thread of its own and sends them
+/// through a channel holding at most `bound` of them, so that only those are in
+/// memory at once. Captures converted to HAR are not streamed.
+pub fn try_stream(
+    input_file_path: &Path,
+    bound: usize,
+) -> Result<(Receiver<StreamedEntry>, Parser), LoadError> {
+    debug_assert!(!import::converts(input_file_path));
+    let input_file = File::open(input_file_path).map_err(LoadError::Open)?;
+    let (sender, receiver) = mpsc::sync_channel(bound);
+    let parser = thread::spawn(move || {
+        let send = |entry| sender.send(entry).is_ok();
+        Ok(har::stream_entries(input_file, !is_strict(), send)?)
+    });
+    Ok((receiver, parser))
+}
+
+/// Like [`try_load`], but maps the file into memory and parses it with simd-json, which
+/// is several times faster on captures of a gigabyte or more. Documents that need
+/// repairs are read again the usual way unless `--strict-har` is given.
+#[cfg(feature = "fast")]
+pub fn try_load_fast(input_file_path: &Path) -> Result<Har, LoadError> {
+    if import::read(input_file_path).is_some() {
+        return try_load(input_file_path);
+    }
+    let input_file = File::open(input_file_path).map_err(LoadError::Open)?;
+    // SAFETY: the map is private, so simd-json's writes stay in memory. Another program
+    // truncating the file while it is read is not guarded against, as with `less` or
+    // most other tools mapping their input.
+    let mut data = unsafe {
+        memmap2::MmapOptions::new()
+            .map_copy(&input_file)
+            .map_err(LoadError::Open)?
+    };
+    let data = match data.strip_prefix(b"\xEF\xBB\xBF") {
+        Some(_) => &mut data[3..],
+        None => &mut data[..],
+    };
+    match simd_json::serde::from_slice::<Har>(data) {
+        Ok(har) => Ok(har),
+        Err(_) if !is_strict() => try_load(input_file_path),
+        Err(err) => Err(LoadError::Parse(io::Error::other(err))),
+    }
+}
+
+/// Loads the HAR given on the command line for a read-only command.
+pub fn open_session(input_har: &str) -> AnalysisSession {
+    AnalysisSession::new(load(&resolve_input(input_har)))
+}
+
+/// Reads the HAR given on the command line as plain JSON, keeping every field, for
+/// commands that write a modified copy.
+pub fn load_document(input_har: &str) -> Value {
+    let input_file_path = resolve_input(input_har);
+    if let Some(document) = import::read(&input_file_path) {
+        return document.unwrap_or_else(
+            |_| pexit!(status = exit_code::PARSE_ERROR; CannotParseJson, input_har),
+        );
+    }
+    fs::read(input_file_path)
+        .ok()
+        .and_then(|x| {
+            if is_strict() {
+                serde_json::from_slice::<Value>(&x).ok()
+            } else {
+                har::read_json(&x).ok()
+            }
+        })
+        .unwrap_or_else(|| pexit!(status = exit_code::PARSE_ERROR; CannotParseJson, input_har))
+}
+
+pub fn write_document(output_har: &Path, document: &Value) {
+    let output_file = File::create(output_har).unwrap_or_else(|_| {
+        pexit!(CannotWriteFile, output_har.to_string_lossy());
+    });
+    let mut writer = BufWriter::new(output_file);
+    // The last of the document is only written, and can only fail, when flushed.
+    let written = serde_json::to_writer_pretty(&mut writer, document)
+        .map_err(io::Error::from)
+        .and_then(|()| writer.flush());
+    if written.is_err() {
+        pexit!(CannotWriteFile, output_har.to_string_lossy());
+    }
+}
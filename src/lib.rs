@@ -0,0 +1,24 @@
+pub mod beautify;
+pub mod cookies;
+pub mod decompress;
+pub mod extraction;
+pub mod fiddler;
+pub mod har;
+pub mod hooks;
+pub mod import;
+pub mod manifest;
+pub mod metadata;
+pub mod mitmproxy;
+pub mod multipart;
+pub mod playlist;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod session;
+pub mod sink;
+pub mod sourcemap;
+#[cfg(feature = "async")]
+pub mod stream;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod winpath;
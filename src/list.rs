@@ -0,0 +1,46 @@
+use clap::Args;
+
+use crate::{
+    input,
+    stats::{human_duration, human_size, human_transfer, slowest},
+};
+
+#[derive(Args)]
+pub struct ListArgs {
+    input_har: String,
+    /// Also show the total time, the time waiting for the server, and the bytes
+    /// transferred of each request
+    #[arg(short, long)]
+    verbose: bool,
+    /// List only the N slowest requests, slowest first
+    #[arg(long, value_name = "N")]
+    slowest: Option<usize>,
+}
+
+pub fn run(args: ListArgs) {
+    let session = input::open_session(&args.input_har);
+    let mut entries = session.indexed_entries().collect::<Vec<_>>();
+    if let Some(n) = args.slowest {
+        entries = slowest(session.indexed_entries(), n);
+    }
+    for (index, entry) in entries {
+        let timing = if args.verbose {
+            format!(
+                "{:>9} {:>9} {:>10}  ",
+                human_duration(Some(entry.time)),
+                human_duration(entry.wait()),
+                human_transfer(entry.response.transfer_size)
+            )
+        } else {
+            String::new()
+        };
+        println!(
+            "{index:>6}  {:>3}  {:<7} {:<28} {:>10}  {timing}{}",
+            entry.response.status,
+            entry.request.method,
+            entry.response.content.essence(),
+            human_size(entry.response.content.body_size()),
+            entry.request.url
+        );
+    }
+}
@@ -1,201 +1,226 @@
-use std::{
-    collections::HashMap,
-    fs::{self, File},
-    io::Write,
-    path::{Path, PathBuf},
-    process::exit,
-    str::FromStr,
-};
+use std::{env, path::PathBuf};
 
-use base64::Engine;
-use clap::Parser;
-use serde::Deserialize;
-use url::Url;
+use clap::{CommandFactory, Parser, Subcommand};
+use extract_har::decompress;
+
+#[macro_use]
+mod i18n;
+
+use i18n::Lang;
 
 macro_rules! pexit {
+    (status = $status:expr; $($arg:tt)*) => {{
+        eprintln!("{}", tr!($($arg)*));
+        std::process::exit($status);
+    }};
     ($($arg:tt)*) => {{
-        println!($($arg)*);
-        exit(1);
+        eprintln!("{}", tr!($($arg)*));
+        std::process::exit(1);
     }};
 }
 
-#[derive(Deserialize)]
-struct Har {
-    log: HarLog,
-}
-
-#[derive(Deserialize)]
-struct HarLog {
-    entries: Vec<HarLogEntry>,
-}
-
-#[derive(Deserialize)]
-struct HarLogEntry {
-    request: HarLogEntryRequest,
-    response: HarLogEntryResponse,
-}
-
-#[derive(Deserialize)]
-struct HarLogEntryRequest {
-    url: Url,
-}
-
-#[derive(Deserialize)]
-struct HarLogEntryResponse {
-    content: HarLogEntryResponseContent,
-}
-
-#[derive(Deserialize)]
-struct HarLogEntryResponseContent {
-    #[serde(default)]
-    text: String,
-    #[serde(rename = "mimeType")]
-    mime_type: String,
-}
+mod bundle;
+#[cfg(feature = "cdp")]
+mod capture;
+mod cat;
+mod completions;
+mod curl;
+mod diff;
+mod exit_code;
+mod export;
+mod extract;
+#[cfg(feature = "fetch")]
+mod fetch;
+mod filter;
+mod graph;
+mod grep;
+#[cfg(feature = "images")]
+mod images;
+#[cfg(feature = "search-index")]
+mod index;
+mod input;
+mod list;
+mod merge;
+mod mhtml;
+mod pack;
+mod profile;
+#[cfg(feature = "fetch")]
+mod record;
+mod redact;
+mod relayout;
+mod report;
+mod scan;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod stats;
+#[cfg(feature = "interactive")]
+mod tui;
+#[cfg(feature = "s3")]
+mod upload;
+mod validate;
+mod verify;
+#[cfg(feature = "watch")]
+mod watch;
 
 #[derive(Parser)]
+#[command(
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    args_override_self = true,
+    after_help = "Exit status: 0 when done, 1 on usage and other errors, 2 when the capture \
+                  cannot be parsed, 3 when some files could not be written, 4 when no entry \
+                  matched the filters."
+)]
 struct Cli {
-    input_har: String,
-    output_dir: Option<String>,
-    #[arg(long, default_value = None)]
-    output_domain: Option<String>,
-    #[arg(long, default_value = None)]
-    output_path: Option<String>,
-    #[arg(long, default_value_t = 0)]
-    output_path_depth: i32,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    extract: extract::ExtractArgs,
+    /// Extraction settings file; `extracthar.toml` in the current folder or the user's
+    /// configuration folder is used when omitted
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Apply the `[profiles.<name>]` settings of the configuration file
+    #[arg(long)]
+    profile: Option<String>,
+    /// Language of the messages; detected from the locale when omitted
+    #[arg(long, value_enum, global = true)]
+    lang: Option<Lang>,
+    /// Reject captures that do not follow the HAR format instead of repairing them
+    /// and skipping the entries that cannot be read
+    #[arg(long, global = true)]
+    strict_har: bool,
+    /// Leave bodies that would decompress to more than this compressed, e.g. `200mb`,
+    /// so that compression bombs cannot use up memory
+    #[arg(long, global = true, value_parser = filter::parse_size, default_value = "1gb")]
+    max_decompressed_size: u64,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write one entry's decoded response body to stdout
+    Cat(cat::CatArgs),
+    /// Compare two captures entry by entry
+    Diff(diff::DiffArgs),
+    /// List entries with their index, status, type and size
+    List(list::ListArgs),
+    /// Summarize counts and sizes by domain, MIME type and status
+    Stats(stats::StatsArgs),
+    /// Convert the capture to a Postman collection or an OpenAPI skeleton
+    Export(export::ExportArgs),
+    /// Print which request loaded which as a Graphviz or JSON graph
+    Graph(graph::GraphArgs),
+    /// Write a copy of the capture keeping only the matching entries
+    Filter(filter::FilterArgs),
+    /// Combine several captures into one HAR
+    Merge(merge::MergeArgs),
+    /// Rebuild a HAR from an output folder, with the bodies of the files as they are
+    /// now on disk
+    Pack(pack::PackArgs),
+    /// Write a copy of the capture with credentials and bodies removed
+    Redact(redact::RedactArgs),
+    /// Arrange an output folder in a new layout, hard-linking the files listed in its
+    /// manifest
+    Relayout(relayout::RelayoutArgs),
+    /// Search the decoded response bodies for a regular expression, printing the
+    /// matching lines
+    Grep(grep::GrepArgs),
+    /// Search headers, cookies, URLs and bodies for credentials and tokens
+    Scan(scan::ScanArgs),
+    /// Print a shell script replaying the captured requests with curl
+    ToCurl(curl::ToCurlArgs),
+    /// Check the capture against the HAR 1.2 format and report its problems
+    Validate(validate::ValidateArgs),
+    /// Check an output folder against the `SHA256SUMS` list written by `--checksums`
+    Verify(verify::VerifyArgs),
+    /// Print a shell completion script
+    Completions(completions::CompletionsArgs),
+    /// Print the manual page
+    Man(completions::ManArgs),
+    /// Record network traffic from a running Chrome over the DevTools Protocol
+    #[cfg(feature = "cdp")]
+    Capture(capture::CaptureArgs),
+    /// Download a list of URLs into a HAR file
+    #[cfg(feature = "fetch")]
+    Record(record::RecordArgs),
+    /// Build an on-disk full-text index over URLs and text bodies
+    #[cfg(feature = "search-index")]
+    Index(index::IndexArgs),
+    /// Query an index built with `index`
+    #[cfg(feature = "search-index")]
+    Search(index::SearchArgs),
 }
 
-fn get_mimetypes() -> HashMap<&'static str, &'static str> {
-    let mut map = HashMap::new();
-    map.insert("image/webp", ".webp");
-    map.insert("image/jpeg", ".jpeg");
-    map.insert("image/jpeg", ".jpg");
-    map.insert("image/png", ".png");
-    map.insert("image/svg+xml", ".svg");
-    return map;
+/// Parses the command line like [`Parser::parse_from`], but exits with 1 on usage
+/// errors, since clap's own 2 is taken by [`exit_code::PARSE_ERROR`].
+fn parse_cli<I, T>(args: I) -> Cli
+where
+    I: IntoIterator<Item = T>,
+    T: Into<std::ffi::OsString> + Clone,
+{
+    Cli::try_parse_from(args).unwrap_or_else(|err| {
+        let _ = err.print();
+        std::process::exit(if err.use_stderr() { 1 } else { 0 });
+    })
 }
 
 fn main() {
-    let Cli {
-        input_har,
-        output_dir,
-        output_domain,
-        output_path,
-        output_path_depth,
-    } = Cli::parse();
-    let input_file_path = Path::new(&input_har)
-        .canonicalize()
-        .unwrap_or_else(|_| pexit!("Cannot parse path {}", input_har));
-    if !input_file_path.is_file() {
-        pexit!("Specified path ({}) is not a file", input_har);
-    }
-    let folder = if let Some(arg) = output_dir {
-        PathBuf::from_str(&arg).unwrap_or_else(|_| {
-            pexit!("Cannot parse path {}", arg);
-        })
-    } else {
-        input_file_path.with_file_name({
-            let mut without_ext = input_file_path
-                .with_extension("")
-                .file_name()
-                .unwrap()
-                .to_owned();
-            without_ext.push("_extract");
-            without_ext
-        })
-    };
-    if !folder.is_dir() {
-        fs::create_dir_all(&folder).unwrap_or_else(|_| {
-            pexit!("Cannot create dirs at path {}", folder.to_string_lossy());
-        });
-    }
-    println!("Loading file");
-    let input_file = File::open(&input_file_path).unwrap_or_else(|_| pexit!("Cannot open file"));
-    let har = serde_json::from_reader::<_, Har>(input_file).unwrap_or_else(|err| {
-        pexit!("Cannot parse file as json to .har model: {:?}", err);
-    });
-    println!("Extraction output settings:");
-    if output_domain.is_none() && output_path.is_none() {
-        println!(
-            "- do not create any directory structure - extract images directly to base folder"
-        );
-    } else {
-        if output_domain.is_none() {
-            pexit!("--output_domain is required in this context");
-        }
-        println!(
-            "- create subfolders for domain {}",
-            output_domain.as_ref().unwrap()
-        );
-        if let Some(path) = &output_path {
-            println!(
-                " - create subfolders for URL path: {} (only for {} {} parts)",
-                path,
-                if output_path_depth > 0 {
-                    "first"
-                } else {
-                    "last"
-                },
-                output_path_depth.abs()
-            )
-        }
-    }
-    println!("Starting extraction...");
-    let mime_types = get_mimetypes();
-    let mime_type_extensions = mime_types.values().collect::<Vec<_>>();
-    let mut count_total = 0;
-    let mut count_extracted = 0;
-    for entry in har.log.entries {
-        count_total += 1;
-        let mime_type = entry.response.content.mime_type;
-        if let Some(ext) = mime_types.get(mime_type.as_str()) {
-            count_extracted += 1;
-            let url = entry.request.url;
-            let url_host = url.host_str().unwrap();
-            let url_segments = url.path_segments().unwrap().collect::<Vec<_>>();
-            let url_path = &url_segments[..url_segments.len() - 1];
-            let mut url_filename = url_segments[url_segments.len() - 1].to_string();
-            if !mime_type_extensions
-                .iter()
-                .any(|x| url_filename.ends_with(x as &str))
-            {
-                url_filename.push_str(ext);
-            }
-            let path = if output_domain.is_some() && output_path.is_some() {
-                let mut result = PathBuf::from_str(url_host).unwrap();
-                url_path
-                    .into_iter()
-                    .for_each(|x| result.extend(Path::new(x)));
-                Some(result)
-            } else if output_domain.is_some() {
-                Some(PathBuf::from_str(url_host).unwrap())
-            } else if output_path.is_some() {
-                let mut result = PathBuf::new();
-                url_path
-                    .into_iter()
-                    .for_each(|x| result.extend(Path::new(x)));
-                Some(result)
-            } else {
-                None
-            };
-            let sub_folder = if let Some(path) = &path {
-                folder.join(path)
-            } else {
-                folder.clone()
-            };
-            let out_file = sub_folder.join(Path::new(&url_filename));
-            if !sub_folder.is_dir() {
-                fs::create_dir_all(sub_folder).unwrap();
-            }
-            let b64 = entry.response.content.text;
-            let b = Engine::decode(&base64::engine::general_purpose::STANDARD, b64).unwrap();
-            println!(
-                "- {url_filename}: extracted to {} [{} bytes]",
-                path.unwrap_or_else(|| folder.clone()).to_string_lossy(),
-                b.len()
+    let mut cli = parse_cli(env::args_os());
+    // Settings from the file go first so the command line overrides them.
+    if cli.command.is_none() {
+        let settings = profile::args(cli.config.as_deref(), cli.profile.as_deref());
+        if !settings.is_empty() {
+            let mut args = env::args_os();
+            let program = args.next().unwrap_or_default();
+            cli = parse_cli(
+                std::iter::once(program)
+                    .chain(settings.into_iter().map(Into::into))
+                    .chain(args),
             );
-            File::create(out_file).unwrap().write_all(&b).unwrap();
         }
     }
-    println!("Finished extracting {count_extracted} (out of total {count_total}) files.")
+    let Cli {
+        command,
+        extract,
+        lang,
+        strict_har,
+        max_decompressed_size,
+        ..
+    } = cli;
+    i18n::set_lang(lang.unwrap_or_else(Lang::detect));
+    input::set_strict(strict_har);
+    decompress::set_limit(max_decompressed_size);
+    match command {
+        Some(Command::Cat(args)) => cat::run(args),
+        Some(Command::Diff(args)) => diff::run(args),
+        Some(Command::List(args)) => list::run(args),
+        Some(Command::Stats(args)) => stats::run(args),
+        Some(Command::ToCurl(args)) => curl::run(args),
+        Some(Command::Filter(args)) => filter::run(args),
+        Some(Command::Graph(args)) => graph::run(args),
+        Some(Command::Merge(args)) => merge::run(args),
+        Some(Command::Pack(args)) => pack::run(args),
+        Some(Command::Redact(args)) => redact::run(args),
+        Some(Command::Relayout(args)) => relayout::run(args),
+        Some(Command::Grep(args)) => grep::run(args),
+        Some(Command::Scan(args)) => scan::run(args),
+        Some(Command::Export(args)) => export::run(args),
+        Some(Command::Validate(args)) => validate::run(args),
+        Some(Command::Verify(args)) => verify::run(args),
+        Some(Command::Completions(args)) => completions::run_completions(args, Cli::command()),
+        Some(Command::Man(args)) => completions::run_man(args, Cli::command()),
+        #[cfg(feature = "cdp")]
+        Some(Command::Capture(args)) => capture::run(args),
+        #[cfg(feature = "fetch")]
+        Some(Command::Record(args)) => record::run(args),
+        #[cfg(feature = "search-index")]
+        Some(Command::Index(args)) => index::run_index(args),
+        #[cfg(feature = "search-index")]
+        Some(Command::Search(args)) => index::run_search(args),
+        None => match extract::run(extract) {
+            Ok(0) => {}
+            Ok(status) => std::process::exit(status),
+            Err(err) => err.exit(),
+        },
+    }
 }
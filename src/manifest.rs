@@ -0,0 +1,105 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::sink;
+
+/// Name of the manifest inside the output folder. The leading dot keeps it from
+/// clashing with an extracted web app `manifest.json`.
+pub const FILE_NAME: &str = ".extract_har.json";
+
+/// Name of the checksum list written next to the manifest, in the format of `sha256sum`
+/// so `sha256sum -c` can check the folder as well.
+pub const CHECKSUMS_FILE_NAME: &str = "SHA256SUMS";
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect()
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ManifestFile {
+    pub size: u64,
+    pub sha256: String,
+    /// URL the file was extracted from; left out by runs from before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Index in `log.entries` of the entry whose response body this file is; files
+    /// such as request bodies and stream segments leave it out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<usize>,
+    /// URLs that redirected to the one this file was downloaded from, starting with
+    /// the originally requested one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<String>,
+}
+
+/// Files written by earlier runs into an output folder, keyed by relative path.
+#[derive(Default, Deserialize, Serialize)]
+pub struct Manifest {
+    pub files: BTreeMap<String, ManifestFile>,
+}
+
+impl Manifest {
+    /// Reads the manifest of an output folder; a missing or unreadable one is empty.
+    pub fn load(root: &Path) -> Manifest {
+        fs::read(root.join(FILE_NAME))
+            .ok()
+            .and_then(|x| serde_json::from_slice(&x).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether an earlier run left a manifest in the output folder.
+    pub fn exists(root: &Path) -> bool {
+        root.join(FILE_NAME).is_file()
+    }
+
+    pub fn save(&self, root: &Path, durable: bool) -> io::Result<()> {
+        sink::write_atomic(
+            &root.join(FILE_NAME),
+            &serde_json::to_vec_pretty(self).unwrap(),
+            durable,
+        )
+    }
+
+    fn key(path: &Path) -> String {
+        path.to_string_lossy().replace('\\', "/")
+    }
+
+    /// Whether `path` was written with exactly this content and is still on disk with
+    /// the same size.
+    pub fn is_current(&self, root: &Path, path: &Path, size: u64, sha256: &str) -> bool {
+        self.files
+            .get(&Manifest::key(path))
+            .is_some_and(|x| x.size == size && x.sha256 == sha256)
+            && fs::metadata(root.join(path)).is_ok_and(|x| x.len() == size)
+    }
+
+    /// One `<sha256>  <path>` line per file, sorted by path.
+    pub fn checksums(&self) -> String {
+        self.files
+            .iter()
+            .map(|(path, file)| format!("{}  {path}\n", file.sha256))
+            .collect()
+    }
+
+    pub fn record(&mut self, path: &Path, file: ManifestFile) {
+        self.files.insert(Manifest::key(path), file);
+    }
+}
+
+/// Reads the `<sha256>  <path>` lines of a checksum list as (path, hash) pairs, also
+/// accepting the `<sha256> *<path>` form of binary mode and skipping other lines.
+pub fn parse_checksums(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let (hash, path) = line.split_once(' ')?;
+            let path = path.strip_prefix([' ', '*'])?;
+            let valid = hash.len() == 64 && hash.bytes().all(|x| x.is_ascii_hexdigit());
+            (valid && !path.is_empty()).then(|| (path.to_string(), hash.to_ascii_lowercase()))
+        })
+        .collect()
+}
@@ -0,0 +1,183 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, FixedOffset};
+use clap::Args;
+use serde_json::{json, Value};
+
+use crate::input;
+
+#[derive(Args)]
+pub struct MergeArgs {
+    /// Captures to combine, in order
+    #[arg(required = true)]
+    input_hars: Vec<String>,
+    /// HAR file to write the combined capture to
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Keep only the first of several requests with the same method, URL and body
+    #[arg(long)]
+    dedup: bool,
+    /// Re-sort the combined entries by `startedDateTime`
+    #[arg(long)]
+    sort: bool,
+}
+
+fn dedup_key(entry: &Value) -> (String, String, String) {
+    let request = &entry["request"];
+    let field = |x: &Value| x.as_str().unwrap_or_default().to_string();
+    (
+        field(&request["method"]),
+        field(&request["url"]),
+        field(&request["postData"]["text"]),
+    )
+}
+
+fn started(entry: &Value) -> Option<DateTime<FixedOffset>> {
+    entry["startedDateTime"]
+        .as_str()
+        .and_then(|x| DateTime::parse_from_rfc3339(x).ok())
+}
+
+/// An id for a page of capture `input` (counted from 1) whose own id an earlier
+/// capture took, such as `2_page_1`.
+fn unique_id(taken: &mut HashSet<String>, input: usize, id: &str) -> String {
+    let mut candidate = format!("{input}_{id}");
+    let mut n = 1;
+    while !taken.insert(candidate.clone()) {
+        n += 1;
+        candidate = format!("{input}_{id}~{n}");
+    }
+    candidate
+}
+
+/// The pages and entries of all captures. Pages found again unchanged in a later
+/// capture of the same session are kept once; other pages whose id an earlier capture
+/// took get a new id, and the `pageref` of their entries is changed to match.
+fn combine(documents: &[Value]) -> (Vec<Value>, Vec<Value>) {
+    let mut pages = Vec::new();
+    let mut entries = Vec::new();
+    let mut seen = Vec::new();
+    let mut page_ids = HashSet::new();
+    for (input, document) in documents.iter().enumerate() {
+        let log = &document["log"];
+        let mut renamed = HashMap::new();
+        for page in log["pages"].as_array().into_iter().flatten() {
+            if seen.contains(&page) {
+                continue;
+            }
+            seen.push(page);
+            let mut page = page.clone();
+            if let Some(id) = page["id"].as_str().map(str::to_string) {
+                if !page_ids.insert(id.clone()) {
+                    let new_id = unique_id(&mut page_ids, input + 1, &id);
+                    page["id"] = Value::from(new_id.clone());
+                    renamed.insert(id, new_id);
+                }
+            }
+            pages.push(page);
+        }
+        for entry in log["entries"].as_array().into_iter().flatten() {
+            let mut entry = entry.clone();
+            if let Some(new_id) = entry["pageref"].as_str().and_then(|x| renamed.get(x)) {
+                entry["pageref"] = Value::from(new_id.as_str());
+            }
+            entries.push(entry);
+        }
+    }
+    (pages, entries)
+}
+
+pub fn run(args: MergeArgs) {
+    let documents = args
+        .input_hars
+        .iter()
+        .map(|x| input::load_document(x))
+        .collect::<Vec<_>>();
+    // The first capture provides `version`, `creator` and the other log-level fields.
+    let mut log = documents[0]["log"].clone();
+    let (pages, mut entries) = combine(&documents);
+    let total = entries.len();
+    if args.dedup {
+        let mut seen = HashSet::new();
+        entries.retain(|x| seen.insert(dedup_key(x)));
+    }
+    if args.sort {
+        // Stable, so entries without a parseable time keep their relative order.
+        entries.sort_by_key(started);
+    }
+    if !pages.is_empty() {
+        log["pages"] = Value::from(pages);
+    }
+    let count = entries.len();
+    log["entries"] = Value::from(entries);
+    input::write_document(&args.output, &json!({ "log": log }));
+    eprintln!(
+        "{}",
+        tr!(
+            MergeSummary,
+            count,
+            args.input_hars.len(),
+            total - count,
+            args.output.to_string_lossy()
+        )
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(pages: &[(&str, &str)], entries: &[(&str, &str)]) -> Value {
+        let pages = pages
+            .iter()
+            .map(|(id, title)| json!({ "id": id, "title": title }))
+            .collect::<Vec<_>>();
+        let entries = entries
+            .iter()
+            .map(|(url, pageref)| json!({ "pageref": pageref, "request": { "url": url } }))
+            .collect::<Vec<_>>();
+        json!({ "log": { "pages": pages, "entries": entries } })
+    }
+
+    #[test]
+    fn renames_pages_of_other_sessions() {
+        let (pages, entries) = combine(&[
+            capture(&[("page_1", "A")], &[("https://a.com/", "page_1")]),
+            capture(&[("page_1", "B")], &[("https://b.com/", "page_1")]),
+        ]);
+        assert_eq!(pages[0]["id"], "page_1");
+        assert_eq!(pages[1]["id"], "2_page_1");
+        assert_eq!(pages[1]["title"], "B");
+        assert_eq!(entries[0]["pageref"], "page_1");
+        assert_eq!(entries[1]["pageref"], "2_page_1");
+    }
+
+    #[test]
+    fn keeps_pages_of_the_same_session_once() {
+        let (pages, entries) = combine(&[
+            capture(&[("page_1", "A")], &[("https://a.com/", "page_1")]),
+            capture(&[("page_1", "A")], &[("https://a.com/x", "page_1")]),
+        ]);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(entries[1]["pageref"], "page_1");
+    }
+
+    #[test]
+    fn avoids_ids_already_taken() {
+        let (pages, _) = combine(&[
+            capture(&[("page_1", "A"), ("2_page_1", "B")], &[]),
+            capture(&[("page_1", "C")], &[]),
+        ]);
+        assert_eq!(pages[2]["id"], "2_page_1~2");
+    }
+
+    #[test]
+    fn keeps_entries_of_captures_without_pages() {
+        let (pages, entries) = combine(&[json!({ "log": { "entries": [{}, {}] } }), json!({})]);
+        assert!(pages.is_empty());
+        assert_eq!(entries.len(), 2);
+    }
+}
@@ -0,0 +1,278 @@
+/// JPEG segments dropped: APP1 (EXIF and XMP), APP2 (ICC profiles) and APP13
+/// (Photoshop's IPTC records).
+const JPEG_METADATA: &[u8] = &[0xE1, 0xE2, 0xED];
+
+/// PNG chunks dropped: EXIF, ICC profiles, text (where XMP also goes) and the time of
+/// the last edit.
+const PNG_METADATA: &[&[u8; 4]] = &[b"eXIf", b"iCCP", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// WebP chunks dropped, with the bit announcing each in the `VP8X` header.
+const WEBP_METADATA: &[(&[u8; 4], u8)] = &[(b"ICCP", 0x20), (b"EXIF", 0x08), (b"XMP ", 0x04)];
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Removes EXIF, XMP and ICC data from a JPEG, PNG or WebP image, recognized by its
+/// signature rather than its MIME type. Returns `None` for other files, for images
+/// without metadata, and for images too damaged to rewrite safely.
+pub fn strip_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        strip_jpeg(bytes)
+    } else if bytes.starts_with(PNG_SIGNATURE) {
+        strip_png(bytes)
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        strip_webp(bytes)
+    } else {
+        None
+    }
+}
+
+fn strip_jpeg(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut result = bytes[..2].to_vec();
+    let mut position = 2;
+    let mut stripped = false;
+    loop {
+        // Markers may be padded with any number of 0xFF bytes.
+        while bytes.get(position + 1) == Some(&0xFF) {
+            position += 1;
+        }
+        if *bytes.get(position)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(position + 1)?;
+        if marker == 0x01 || (0xD0..=0xD8).contains(&marker) {
+            result.extend_from_slice(&bytes[position..position + 2]);
+            position += 2;
+            continue;
+        }
+        // Metadata precedes the image data, which is copied as is.
+        if marker == 0xDA || marker == 0xD9 {
+            result.extend_from_slice(&bytes[position..]);
+            break;
+        }
+        let length = u16::from_be_bytes([*bytes.get(position + 2)?, *bytes.get(position + 3)?]);
+        // The length counts its own two bytes.
+        if length < 2 {
+            return None;
+        }
+        let end = position + 2 + length as usize;
+        let segment = bytes.get(position..end)?;
+        if JPEG_METADATA.contains(&marker) {
+            stripped = true;
+        } else {
+            result.extend_from_slice(segment);
+        }
+        position = end;
+    }
+    stripped.then_some(result)
+}
+
+fn strip_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut result = PNG_SIGNATURE.to_vec();
+    let mut position = PNG_SIGNATURE.len();
+    let mut stripped = false;
+    while position < bytes.len() {
+        let length = u32::from_be_bytes(bytes.get(position..position + 4)?.try_into().ok()?);
+        // Length, type, data and CRC.
+        let end = position.checked_add(12 + length as usize)?;
+        let chunk = bytes.get(position..end)?;
+        if PNG_METADATA.iter().any(|x| &chunk[4..8] == *x) {
+            stripped = true;
+        } else {
+            result.extend_from_slice(chunk);
+        }
+        position = end;
+    }
+    stripped.then_some(result)
+}
+
+fn strip_webp(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut result = bytes[..12].to_vec();
+    let mut position = 12;
+    let mut stripped = false;
+    let mut header = None;
+    while position < bytes.len() {
+        let fourcc = bytes.get(position..position + 4)?;
+        let size = u32::from_le_bytes(bytes.get(position + 4..position + 8)?.try_into().ok()?);
+        // Chunks are padded to an even size, which the last one may leave out.
+        let end = position.checked_add(8 + size as usize)?;
+        if end > bytes.len() {
+            return None;
+        }
+        let end = (end + (size as usize & 1)).min(bytes.len());
+        let chunk = &bytes[position..end];
+        if let Some((_, flag)) = WEBP_METADATA.iter().find(|(x, _)| fourcc == *x) {
+            stripped = true;
+            if let Some(header) = header {
+                result[header] &= !flag;
+            }
+        } else {
+            if fourcc == b"VP8X" && size > 0 {
+                header = Some(result.len() + 8);
+            }
+            result.extend_from_slice(chunk);
+        }
+        position = end;
+    }
+    if !stripped {
+        return None;
+    }
+    let size = u32::try_from(result.len() - 8).ok()?;
+    result[4..8].copy_from_slice(&size.to_le_bytes());
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpeg_segment(marker: u8, data: &[u8]) -> Vec<u8> {
+        let length = u16::try_from(data.len() + 2).unwrap();
+        [&[0xFF, marker][..], &length.to_be_bytes(), data].concat()
+    }
+
+    fn jpeg(segments: &[Vec<u8>]) -> Vec<u8> {
+        let scan = [0xFF, 0xDA, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9];
+        [&[0xFF, 0xD8][..], &segments.concat(), &scan].concat()
+    }
+
+    fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let length = u32::try_from(data.len()).unwrap();
+        [&length.to_be_bytes()[..], kind, data, &[0; 4]].concat()
+    }
+
+    fn webp_chunk(fourcc: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let size = u32::try_from(data.len()).unwrap();
+        let padding = if data.len() % 2 == 1 { &[0][..] } else { &[] };
+        [&fourcc[..], &size.to_le_bytes(), data, padding].concat()
+    }
+
+    fn webp(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let chunks = chunks.concat();
+        let size = u32::try_from(chunks.len() + 4).unwrap();
+        [&b"RIFF"[..], &size.to_le_bytes(), b"WEBP", &chunks].concat()
+    }
+
+    #[test]
+    fn strips_jpeg_metadata_segments() {
+        let jfif = jpeg_segment(0xE0, b"JFIF\0");
+        let tables = jpeg_segment(0xDB, &[1, 2, 3]);
+        let image = jpeg(&[
+            jfif.clone(),
+            jpeg_segment(0xE1, b"Exif\0\0"),
+            jpeg_segment(0xE2, b"ICC_PROFILE\0"),
+            tables.clone(),
+            jpeg_segment(0xED, b"Photoshop 3.0\0"),
+        ]);
+        assert_eq!(strip_metadata(&image), Some(jpeg(&[jfif, tables])));
+    }
+
+    #[test]
+    fn skips_fill_bytes_between_jpeg_segments() {
+        let jfif = jpeg_segment(0xE0, b"JFIF\0");
+        let exif = [&[0xFF, 0xFF][..], &jpeg_segment(0xE1, b"Exif\0\0")].concat();
+        assert_eq!(
+            strip_metadata(&jpeg(&[jfif.clone(), exif])),
+            Some(jpeg(&[jfif]))
+        );
+    }
+
+    #[test]
+    fn leaves_jpegs_without_metadata_alone() {
+        assert_eq!(
+            strip_metadata(&jpeg(&[jpeg_segment(0xE0, b"JFIF\0")])),
+            None
+        );
+        assert_eq!(strip_metadata(&[0xFF, 0xD8]), None);
+    }
+
+    #[test]
+    fn rejects_truncated_jpeg_segments() {
+        let exif = jpeg_segment(0xE1, b"Exif\0\0");
+        let mut image = [&[0xFF, 0xD8][..], &exif].concat();
+        image.truncate(image.len() - 1);
+        assert_eq!(strip_metadata(&image), None);
+        // Cut off within the length.
+        assert_eq!(strip_metadata(&[0xFF, 0xD8, 0xFF, 0xE1, 0x00]), None);
+        // A length too short to count itself.
+        let image = [&[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x00][..], &exif].concat();
+        assert_eq!(strip_metadata(&image), None);
+        // Bytes between segments that are not a marker.
+        let image = [&[0xFF, 0xD8, 0x00][..], &exif].concat();
+        assert_eq!(strip_metadata(&image), None);
+    }
+
+    #[test]
+    fn strips_png_metadata_chunks() {
+        let header = png_chunk(b"IHDR", &[0; 13]);
+        let data = png_chunk(b"IDAT", &[1, 2, 3]);
+        let end = png_chunk(b"IEND", &[]);
+        let image = [
+            PNG_SIGNATURE,
+            &header,
+            &png_chunk(b"iCCP", b"icc"),
+            &data,
+            &png_chunk(b"tEXt", b"Comment\0hi"),
+            &png_chunk(b"tIME", &[0; 7]),
+            &end,
+        ]
+        .concat();
+        let stripped = [PNG_SIGNATURE, &header, &data, &end].concat();
+        assert_eq!(strip_metadata(&image), Some(stripped.clone()));
+        assert_eq!(strip_metadata(&stripped), None);
+    }
+
+    #[test]
+    fn rejects_truncated_png_chunks() {
+        let image = [PNG_SIGNATURE, &png_chunk(b"tEXt", b"Comment\0hi")].concat();
+        assert_eq!(strip_metadata(&image[..image.len() - 1]), None);
+        assert_eq!(strip_metadata(&image[..PNG_SIGNATURE.len() + 2]), None);
+    }
+
+    #[test]
+    fn strips_webp_metadata_chunks_and_their_flags() {
+        let flags = 0x20 | 0x08 | 0x04 | 0x10;
+        let image = webp(&[
+            webp_chunk(b"VP8X", &[flags, 0, 0, 0, 1, 0, 0, 1, 0, 0]),
+            webp_chunk(b"ICCP", b"icc"),
+            webp_chunk(b"VP8 ", &[1, 2, 3, 4]),
+            webp_chunk(b"EXIF", b"Exif\0"),
+            webp_chunk(b"XMP ", b"<x/>"),
+        ]);
+        let stripped = webp(&[
+            webp_chunk(b"VP8X", &[0x10, 0, 0, 0, 1, 0, 0, 1, 0, 0]),
+            webp_chunk(b"VP8 ", &[1, 2, 3, 4]),
+        ]);
+        assert_eq!(strip_metadata(&image), Some(stripped.clone()));
+        assert_eq!(strip_metadata(&stripped), None);
+    }
+
+    #[test]
+    fn tolerates_missing_padding_at_the_end_of_a_webp() {
+        let image = webp(&[webp_chunk(b"VP8 ", &[1, 2]), webp_chunk(b"EXIF", b"odd")]);
+        let expected = webp(&[webp_chunk(b"VP8 ", &[1, 2])]);
+        assert_eq!(strip_metadata(&image[..image.len() - 1]), Some(expected));
+    }
+
+    #[test]
+    fn rejects_truncated_webp_chunks() {
+        let image = webp(&[
+            webp_chunk(b"EXIF", b"Exif\0\0"),
+            webp_chunk(b"VP8 ", &[1, 2]),
+        ]);
+        assert_eq!(strip_metadata(&image[..image.len() - 1]), None);
+        assert_eq!(strip_metadata(&image[..image.len() - 6]), None);
+        // A VP8X chunk too short to hold its flags.
+        let image = webp(&[webp_chunk(b"VP8X", &[]), webp_chunk(b"EXIF", b"Exif")]);
+        assert_eq!(
+            strip_metadata(&image),
+            Some(webp(&[webp_chunk(b"VP8X", &[])]))
+        );
+    }
+
+    #[test]
+    fn leaves_other_files_alone() {
+        assert_eq!(strip_metadata(b"GIF89a"), None);
+        assert_eq!(strip_metadata(b""), None);
+        assert_eq!(strip_metadata(b"RIFF\0\0\0\0WAVE"), None);
+    }
+}
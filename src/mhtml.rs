@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use base64::Engine;
+use chrono::DateTime;
+use extract_har::{
+    decompress,
+    har::{Har, HarLogEntry},
+    manifest,
+};
+use url::Url;
+
+/// A page's document and the subresources loaded for it, with decoded bodies.
+pub struct Page<'a> {
+    document: &'a HarLogEntry,
+    html: Vec<u8>,
+    /// Subresources by URL, first response wins.
+    resources: Vec<(&'a HarLogEntry, Vec<u8>)>,
+}
+
+fn body(entry: &HarLogEntry) -> Option<Vec<u8>> {
+    let mut body = entry.response.content.decoded()?;
+    if let Some(decompressed) =
+        decompress::decompress(entry.response.header("content-encoding"), &body)
+    {
+        body = decompressed;
+    }
+    (!body.is_empty()).then_some(body)
+}
+
+fn wrap_base64(bytes: &[u8]) -> String {
+    let encoded = Engine::encode(&base64::engine::general_purpose::STANDARD, bytes);
+    let mut result = String::with_capacity(encoded.len() + encoded.len() / 76 * 2 + 2);
+    for line in encoded.as_bytes().chunks(76) {
+        result.push_str(std::str::from_utf8(line).unwrap());
+        result.push_str("\r\n");
+    }
+    result
+}
+
+fn data_uri(mime_type: &str, bytes: &[u8]) -> String {
+    format!(
+        "data:{mime_type};base64,{}",
+        Engine::encode(&base64::engine::general_purpose::STANDARD, bytes)
+    )
+}
+
+/// Replaces the targets of `src`, `href` and CSS `url()` references in `text` by
+/// whatever `replace` returns for their URL resolved against `base`.
+fn rewrite_references(text: &str, base: &Url, replace: &dyn Fn(&Url) -> Option<String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    loop {
+        let next = ["src=", "href=", "url("]
+            .iter()
+            .filter_map(|x| rest.find(x).map(|position| (position, x.len())))
+            .min();
+        let Some((position, length)) = next else {
+            result.push_str(rest);
+            return result;
+        };
+        let (before, after) = rest.split_at(position + length);
+        result.push_str(before);
+        let quote = after.chars().next().filter(|x| *x == '"' || *x == '\'');
+        let value_start = quote.map_or(0, |x| x.len_utf8());
+        let value_end = match quote {
+            Some(quote) => after[value_start..].find(quote).map(|x| x + value_start),
+            None => after.find(|x: char| x.is_whitespace() || x == '>' || x == ')'),
+        }
+        .unwrap_or(after.len());
+        let value = &after[value_start..value_end];
+        match base.join(value.trim()).ok().and_then(|x| replace(&x)) {
+            Some(replacement) => {
+                result.push_str(&after[..value_start]);
+                result.push_str(&replacement);
+            }
+            None => result.push_str(&after[..value_end]),
+        }
+        rest = &after[value_end..];
+    }
+}
+
+impl<'a> Page<'a> {
+    /// The page with this id from `log.pages`, or else the first one. Captures without
+    /// pages are treated as a single page.
+    pub fn select(har: &'a Har, id: Option<&str>) -> Option<Page<'a>> {
+        let pages = &har.log.pages;
+        let page = match id {
+            Some(id) => Some(pages.iter().find(|x| x.id == id)?),
+            None => pages.first(),
+        };
+        let id = page.map(|x| x.id.as_str());
+        let entries = har
+            .log
+            .entries
+            .iter()
+            .filter(|x| id.is_none() || x.pageref.as_deref() == id)
+            .collect::<Vec<_>>();
+        let (document, html) = entries.iter().find_map(|entry| {
+            let is_html = entry.response.content.essence() == "text/html"
+                && (200..300).contains(&entry.response.status);
+            Some((*entry, body(entry).filter(|_| is_html)?))
+        })?;
+        let mut resources = Vec::<(&HarLogEntry, Vec<u8>)>::new();
+        for entry in entries {
+            if std::ptr::eq(entry, document)
+                || !(200..300).contains(&entry.response.status)
+                || resources
+                    .iter()
+                    .any(|(x, _)| x.request.url == entry.request.url)
+            {
+                continue;
+            }
+            if let Some(body) = body(entry) {
+                resources.push((entry, body));
+            }
+        }
+        Some(Page {
+            document,
+            html,
+            resources,
+        })
+    }
+
+    /// The page as a `multipart/related` MHTML archive, as saved by browsers.
+    pub fn mhtml(&self) -> Vec<u8> {
+        let url = self.document.request.url.as_str();
+        let boundary = format!(
+            "----MultipartBoundary--{}----",
+            &manifest::sha256_hex(url.as_bytes())[..32]
+        );
+        let mut result = String::new();
+        result.push_str("From: <Saved by extract_har>\r\n");
+        result.push_str(&format!("Snapshot-Content-Location: {url}\r\n"));
+        if let Ok(date) = DateTime::parse_from_rfc3339(&self.document.started_date_time) {
+            result.push_str(&format!("Date: {}\r\n", date.to_rfc2822()));
+        }
+        result.push_str("MIME-Version: 1.0\r\n");
+        result.push_str(&format!(
+            "Content-Type: multipart/related;\r\n\ttype=\"text/html\";\r\n\tboundary=\"{boundary}\"\r\n\r\n"
+        ));
+        let parts = std::iter::once((self.document, &self.html))
+            .chain(self.resources.iter().map(|(entry, body)| (*entry, body)));
+        for (entry, body) in parts {
+            let mime_type = match entry.response.content.mime_type.as_str() {
+                "" => "application/octet-stream",
+                x => x,
+            };
+            result.push_str(&format!("--{boundary}\r\n"));
+            result.push_str(&format!("Content-Type: {mime_type}\r\n"));
+            result.push_str("Content-Transfer-Encoding: base64\r\n");
+            result.push_str(&format!("Content-Location: {}\r\n\r\n", entry.request.url));
+            result.push_str(&wrap_base64(body));
+            result.push_str("\r\n");
+        }
+        result.push_str(&format!("--{boundary}--\r\n"));
+        result.into_bytes()
+    }
+
+    /// The page's HTML with every captured subresource it references inlined as a
+    /// `data:` URI, stylesheets after inlining their own references.
+    pub fn single_file(&self) -> Vec<u8> {
+        let by_url = self
+            .resources
+            .iter()
+            .map(|(entry, body)| (&entry.request.url, (entry, body)))
+            .collect::<HashMap<_, _>>();
+        let inline = |url: &Url| {
+            let (entry, body) = by_url.get(url)?;
+            let essence = entry.response.content.essence();
+            // Links to other captured pages stay links.
+            if essence == "text/html" {
+                None
+            } else if essence == "text/css" {
+                let css = String::from_utf8_lossy(body);
+                let css = rewrite_references(&css, url, &|x| {
+                    let (entry, body) = by_url.get(x)?;
+                    Some(data_uri(entry.response.content.essence(), body))
+                });
+                Some(data_uri(essence, css.as_bytes()))
+            } else {
+                Some(data_uri(essence, body))
+            }
+        };
+        let html = String::from_utf8_lossy(&self.html);
+        rewrite_references(&html, &self.document.request.url, &inline).into_bytes()
+    }
+}
@@ -0,0 +1,351 @@
+use std::io;
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::import::{invalid_data, Exchange};
+
+/// A value of mitmproxy's flow files, which are a sequence of tnetstrings.
+enum TNetString {
+    Bytes(Vec<u8>),
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+    List(Vec<TNetString>),
+    Dict(Vec<(TNetString, TNetString)>),
+}
+
+/// Deepest nesting of lists and dicts read; flows nest a few levels, and a crafted file
+/// nesting deeper would overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+impl TNetString {
+    /// Parses the value at the start of `data`, returning it and what follows.
+    fn parse(data: &[u8]) -> io::Result<(TNetString, &[u8])> {
+        TNetString::parse_nested(data, 0)
+    }
+
+    fn parse_nested(data: &[u8], depth: usize) -> io::Result<(TNetString, &[u8])> {
+        let colon = data
+            .iter()
+            .position(|x| *x == b':')
+            .filter(|x| *x <= 12)
+            .ok_or_else(|| invalid_data("not a mitmproxy flow file"))?;
+        let length = std::str::from_utf8(&data[..colon])
+            .ok()
+            .and_then(|x| x.parse::<usize>().ok())
+            .ok_or_else(|| invalid_data("bad tnetstring length"))?;
+        let payload = data
+            .get(colon + 1..colon + 1 + length)
+            .ok_or_else(|| invalid_data("truncated tnetstring"))?;
+        let kind = *data
+            .get(colon + 1 + length)
+            .ok_or_else(|| invalid_data("truncated tnetstring"))?;
+        let rest = &data[colon + 2 + length..];
+        let text = || String::from_utf8_lossy(payload).into_owned();
+        let value = match kind {
+            b',' => TNetString::Bytes(payload.to_vec()),
+            b';' => TNetString::Text(text()),
+            b'#' => TNetString::Integer(
+                text()
+                    .parse()
+                    .map_err(|_| invalid_data("bad tnetstring integer"))?,
+            ),
+            b'^' => TNetString::Float(
+                text()
+                    .parse()
+                    .map_err(|_| invalid_data("bad tnetstring float"))?,
+            ),
+            b'!' => TNetString::Bool(payload == b"true"),
+            b'~' => TNetString::Null,
+            b']' | b'}' if depth == MAX_DEPTH => {
+                return Err(invalid_data("tnetstring nested too deeply"));
+            }
+            b']' => {
+                let mut items = Vec::new();
+                let mut payload = payload;
+                while !payload.is_empty() {
+                    let (item, rest) = TNetString::parse_nested(payload, depth + 1)?;
+                    items.push(item);
+                    payload = rest;
+                }
+                TNetString::List(items)
+            }
+            b'}' => {
+                let mut items = Vec::new();
+                let mut payload = payload;
+                while !payload.is_empty() {
+                    let (key, rest) = TNetString::parse_nested(payload, depth + 1)?;
+                    let (value, rest) = TNetString::parse_nested(rest, depth + 1)?;
+                    items.push((key, value));
+                    payload = rest;
+                }
+                TNetString::Dict(items)
+            }
+            _ => return Err(invalid_data("unknown tnetstring type")),
+        };
+        Ok((value, rest))
+    }
+
+    /// Value of a dict key; keys are bytes or text depending on the mitmproxy version.
+    fn get(&self, key: &str) -> Option<&TNetString> {
+        let TNetString::Dict(items) = self else {
+            return None;
+        };
+        items
+            .iter()
+            .find(|(x, _)| x.bytes() == Some(key.as_bytes()))
+            .map(|(_, x)| x)
+    }
+
+    fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            TNetString::Bytes(x) => Some(x),
+            TNetString::Text(x) => Some(x.as_bytes()),
+            _ => None,
+        }
+    }
+
+    fn text(&self) -> Option<String> {
+        self.bytes()
+            .map(|x| String::from_utf8_lossy(x).into_owned())
+    }
+
+    fn number(&self) -> Option<f64> {
+        match self {
+            TNetString::Integer(x) => Some(*x as f64),
+            TNetString::Float(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    fn list(&self) -> &[TNetString] {
+        match self {
+            TNetString::List(x) => x,
+            _ => &[],
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        !matches!(self, TNetString::Null | TNetString::Bool(false))
+    }
+}
+
+fn headers(message: &TNetString) -> Vec<(String, String)> {
+    message
+        .get("headers")
+        .map(TNetString::list)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|x| match x.list() {
+            [name, value] => Some((name.text()?, value.text()?)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn timestamp(seconds: f64) -> Option<DateTime<FixedOffset>> {
+    DateTime::from_timestamp_micros((seconds * 1e6) as i64).map(|x| x.fixed_offset())
+}
+
+fn exchange(flow: &TNetString) -> Option<Exchange> {
+    if flow.get("type").and_then(TNetString::text).as_deref() != Some("http") {
+        return None;
+    }
+    let request = flow.get("request")?;
+    let text = |message: &TNetString, key| message.get(key).and_then(TNetString::text);
+    let scheme = text(request, "scheme").unwrap_or_else(|| "http".to_string());
+    let host = text(request, "host")?;
+    let port = request
+        .get("port")
+        .and_then(TNetString::number)
+        .map(|x| x as u16);
+    let default_port = if scheme == "https" { 443 } else { 80 };
+    let authority = match port {
+        Some(port) if port != default_port => format!("{host}:{port}"),
+        _ => host,
+    };
+    let started = request.get("timestamp_start").and_then(TNetString::number);
+    let mut exchange = Exchange {
+        started: started.and_then(timestamp),
+        method: text(request, "method").unwrap_or_default(),
+        url: format!(
+            "{scheme}://{authority}{}",
+            text(request, "path").unwrap_or_default()
+        ),
+        http_version: text(request, "http_version").unwrap_or_default(),
+        request_headers: headers(request),
+        request_body: request
+            .get("content")
+            .and_then(TNetString::bytes)
+            .unwrap_or_default()
+            .to_vec(),
+        ..Default::default()
+    };
+    if let Some(response) = flow.get("response").filter(|x| x.is_truthy()) {
+        exchange.status = response
+            .get("status_code")
+            .and_then(TNetString::number)
+            .unwrap_or_default() as i64;
+        exchange.status_text = text(response, "reason").unwrap_or_default();
+        exchange.response_headers = headers(response);
+        exchange.response_body = response
+            .get("content")
+            .and_then(TNetString::bytes)
+            .unwrap_or_default()
+            .to_vec();
+        if let (Some(start), Some(end)) = (
+            started,
+            response.get("timestamp_end").and_then(TNetString::number),
+        ) {
+            exchange.time = ((end - start) * 1000.0).max(0.0);
+        }
+    }
+    // `peername` in recent versions, `ip_address` in older ones, both `[ip, port]`.
+    let server = flow.get("server_conn");
+    exchange.server_ip_address = ["peername", "ip_address"]
+        .iter()
+        .find_map(|x| server?.get(x)?.list().first()?.text());
+    Some(exchange)
+}
+
+/// Reads the HTTP flows of a mitmproxy dump, skipping TCP, UDP and DNS flows.
+pub fn parse(data: &[u8]) -> io::Result<Vec<Exchange>> {
+    let mut exchanges = Vec::new();
+    let mut rest = data;
+    while !rest.iter().all(u8::is_ascii_whitespace) {
+        let (flow, next) = TNetString::parse(rest)?;
+        exchanges.extend(exchange(&flow));
+        rest = next;
+    }
+    Ok(exchanges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(kind: u8, payload: &[u8]) -> Vec<u8> {
+        let mut result = format!("{}:", payload.len()).into_bytes();
+        result.extend_from_slice(payload);
+        result.push(kind);
+        result
+    }
+
+    fn bytes(x: &str) -> Vec<u8> {
+        value(b',', x.as_bytes())
+    }
+
+    fn list(items: &[Vec<u8>]) -> Vec<u8> {
+        value(b']', &items.concat())
+    }
+
+    fn dict(items: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let payload = items
+            .iter()
+            .flat_map(|(key, x)| [bytes(key), x.clone()])
+            .collect::<Vec<_>>();
+        value(b'}', &payload.concat())
+    }
+
+    fn flow(kind: &str) -> Vec<u8> {
+        let request = dict(&[
+            ("scheme", bytes("https")),
+            ("host", bytes("a.com")),
+            ("port", value(b'#', b"8443")),
+            ("path", bytes("/x?q=1")),
+            ("method", bytes("POST")),
+            ("http_version", bytes("HTTP/1.1")),
+            ("headers", list(&[list(&[bytes("Host"), bytes("a.com")])])),
+            ("content", bytes("a=1")),
+            ("timestamp_start", value(b'^', b"1700000000.5")),
+        ]);
+        let response = dict(&[
+            ("status_code", value(b'#', b"201")),
+            ("reason", value(b';', b"Created")),
+            ("headers", list(&[])),
+            ("content", bytes("ok")),
+            ("timestamp_end", value(b'^', b"1700000001.25")),
+        ]);
+        let server = dict(&[("peername", list(&[bytes("10.0.0.1"), value(b'#', b"8443")]))]);
+        dict(&[
+            ("type", value(b';', kind.as_bytes())),
+            ("request", request),
+            ("response", response),
+            ("server_conn", server),
+        ])
+    }
+
+    #[test]
+    fn reads_an_http_flow() {
+        let exchanges = parse(&flow("http")).unwrap();
+        let [exchange] = exchanges.as_slice() else {
+            panic!("{} flows", exchanges.len());
+        };
+        assert_eq!(exchange.method, "POST");
+        assert_eq!(exchange.url, "https://a.com:8443/x?q=1");
+        assert_eq!(exchange.http_version, "HTTP/1.1");
+        assert_eq!(exchange.request_headers, [("Host".into(), "a.com".into())]);
+        assert_eq!(exchange.request_body, b"a=1");
+        assert_eq!(exchange.status, 201);
+        assert_eq!(exchange.status_text, "Created");
+        assert_eq!(exchange.response_body, b"ok");
+        assert_eq!(exchange.time, 750.0);
+        assert_eq!(exchange.server_ip_address.as_deref(), Some("10.0.0.1"));
+    }
+
+    #[test]
+    fn skips_other_flows_and_trailing_whitespace() {
+        let mut data = flow("tcp");
+        data.extend(flow("http"));
+        data.extend(b"\n\n");
+        assert_eq!(parse(&data).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn reads_a_flow_without_a_response() {
+        let data = dict(&[
+            ("type", bytes("http")),
+            (
+                "request",
+                dict(&[("host", bytes("a.com")), ("path", bytes("/"))]),
+            ),
+            ("response", value(b'~', b"")),
+        ]);
+        let exchanges = parse(&data).unwrap();
+        assert_eq!(exchanges[0].url, "http://a.com/");
+        assert_eq!(exchanges[0].status, 0);
+    }
+
+    #[test]
+    fn rejects_truncated_and_malformed_values() {
+        assert!(parse(b"10:abc,").is_err());
+        assert!(parse(b"3:abc").is_err());
+        assert!(parse(b"x:abc,").is_err());
+        assert!(parse(b"3:abc?").is_err());
+        assert!(parse(b"3:abc#").is_err());
+        assert!(parse(b"<html>").is_err());
+    }
+
+    #[test]
+    fn rejects_values_nested_too_deeply() {
+        let nested = |depth| (0..depth).fold(value(b'~', b""), |x, _| list(&[x]));
+        assert!(TNetString::parse(&nested(MAX_DEPTH)).is_ok());
+        let too_deep = |data: &[u8]| {
+            TNetString::parse(data).is_err_and(|x| x.to_string().contains("too deeply"))
+        };
+        assert!(too_deep(&nested(MAX_DEPTH + 1)));
+        // Built inside out in one pass, as each level only adds its length and `]`.
+        let (mut length, mut prefixes) = (3, Vec::new());
+        for _ in 0..100_000 {
+            let prefix = format!("{length}:");
+            length += prefix.len() + 1;
+            prefixes.push(prefix);
+        }
+        let mut data = prefixes.into_iter().rev().collect::<String>().into_bytes();
+        data.extend(b"0:~");
+        data.extend(std::iter::repeat_n(b']', 100_000));
+        assert!(too_deep(&data));
+    }
+}
@@ -0,0 +1,52 @@
+pub struct Part<'a> {
+    pub name: Option<String>,
+    pub file_name: Option<String>,
+    pub body: &'a str,
+}
+
+/// Looks up a `key=value` parameter of a header value such as `Content-Type` or
+/// `Content-Disposition`, unquoting it if needed.
+pub fn header_param(header_value: &str, key: &str) -> Option<String> {
+    header_value.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case(key) {
+            return None;
+        }
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|x| x.strip_suffix('"'))
+            .unwrap_or(value);
+        Some(value.to_string())
+    })
+}
+
+/// Splits a `multipart/*` body into its parts, returning `None` when the content type
+/// carries no boundary.
+pub fn split<'a>(content_type: &str, body: &'a str) -> Option<Vec<Part<'a>>> {
+    let boundary = format!("--{}", header_param(content_type, "boundary")?);
+    let mut parts = Vec::new();
+    for chunk in body.split(boundary.as_str()).skip(1) {
+        if chunk.starts_with("--") {
+            break;
+        }
+        let chunk = chunk.strip_prefix("\r\n").unwrap_or(chunk);
+        let (headers, body) = chunk
+            .split_once("\r\n\r\n")
+            .or_else(|| chunk.split_once("\n\n"))
+            .unwrap_or(("", chunk));
+        let body = body.strip_suffix("\r\n").unwrap_or(body);
+        let disposition = headers.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.trim()
+                .eq_ignore_ascii_case("content-disposition")
+                .then_some(value.trim())
+        });
+        parts.push(Part {
+            name: disposition.and_then(|x| header_param(x, "name")),
+            file_name: disposition.and_then(|x| header_param(x, "filename")),
+            body,
+        });
+    }
+    Some(parts)
+}
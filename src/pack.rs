@@ -0,0 +1,326 @@
+use std::{collections::HashMap, fs, path::PathBuf, time::SystemTime};
+
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::{Args, ValueEnum};
+use extract_har::{
+    decompress, extraction,
+    manifest::{self, Manifest},
+};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    extract::{is_textual, Preset},
+    input,
+};
+
+#[derive(Args)]
+pub struct PackArgs {
+    /// Output folder of an earlier extraction, holding its manifest
+    folder: PathBuf,
+    /// HAR file to write
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Capture the folder was extracted from: its entries are written with the bodies
+    /// replaced by the files on disk, instead of entries made up from the manifest
+    #[arg(long)]
+    original: Option<String>,
+}
+
+/// MIME type of a file by its extension, from the types extraction knows of.
+fn mime_type_of(path: &str, types: &HashMap<String, &str>) -> String {
+    let ext = path
+        .rsplit_once('.')
+        .map(|(_, x)| format!(".{}", x.to_ascii_lowercase()))
+        .unwrap_or_default();
+    types
+        .get(&ext)
+        .copied()
+        .unwrap_or("application/octet-stream")
+        .to_string()
+}
+
+/// The `content` of a response with this body, as text when it is text and in base64
+/// otherwise.
+fn content(mime_type: &str, bytes: Vec<u8>) -> Value {
+    let size = bytes.len();
+    match String::from_utf8(bytes) {
+        Ok(text) if is_textual(mime_type) => json!({
+            "size": size,
+            "mimeType": mime_type,
+            "text": text,
+        }),
+        result => {
+            let bytes = result.map_or_else(|x| x.into_bytes(), String::into_bytes);
+            json!({
+                "size": size,
+                "mimeType": mime_type,
+                "text": base64::engine::general_purpose::STANDARD.encode(bytes),
+                "encoding": "base64",
+            })
+        }
+    }
+}
+
+/// The response of entry `index` of the original capture, if the capture has such an
+/// entry and it is of `url`.
+fn original_response<'a>(
+    document: &'a mut Value,
+    index: usize,
+    url: &str,
+) -> Option<&'a mut Map<String, Value>> {
+    let entry = document
+        .get_mut("log")
+        .and_then(|x| x.get_mut("entries"))
+        .and_then(Value::as_array_mut)
+        .and_then(|x| x.get_mut(index))?;
+    if entry["request"]["url"].as_str() != Some(url) {
+        return None;
+    }
+    entry.get_mut("response").and_then(Value::as_object_mut)
+}
+
+/// Puts the body into a response of the original capture. `Content-Encoding` is
+/// dropped unless the file still has it applied, as with `--no-decompress`.
+fn replace_body(response: &mut Map<String, Value>, bytes: Vec<u8>) {
+    let mime_type = response
+        .get("content")
+        .and_then(|x| x["mimeType"].as_str())
+        .unwrap_or_default()
+        .to_string();
+    let size = bytes.len();
+    if let Some(headers) = response.get_mut("headers").and_then(Value::as_array_mut) {
+        let is = |header: &Value, name: &str| {
+            header["name"]
+                .as_str()
+                .is_some_and(|x| x.eq_ignore_ascii_case(name))
+        };
+        let encoding = headers
+            .iter()
+            .find(|x| is(x, "content-encoding"))
+            .and_then(|x| x["value"].as_str());
+        if decompress::decompress(encoding, &bytes).is_none() {
+            headers.retain(|x| !is(x, "content-encoding"));
+        }
+        for header in headers.iter_mut().filter(|x| is(x, "content-length")) {
+            header["value"] = Value::from(size.to_string());
+        }
+    }
+    response.insert("content".to_string(), content(&mime_type, bytes));
+    response.insert("bodySize".to_string(), Value::from(size));
+}
+
+/// An entry made up for a file when there is no original capture: a `GET` of its URL
+/// answered with the file, started when the file was last modified.
+fn made_up_entry(url: &str, mime_type: &str, bytes: Vec<u8>, modified: SystemTime) -> Value {
+    let started = DateTime::<Utc>::from(modified).to_rfc3339_opts(SecondsFormat::Millis, true);
+    let size = bytes.len();
+    json!({
+        "startedDateTime": started,
+        "time": 0,
+        "request": {
+            "method": "GET",
+            "url": url,
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "queryString": [],
+            "headersSize": -1,
+            "bodySize": 0,
+        },
+        "response": {
+            "status": 200,
+            "statusText": "OK",
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [{ "name": "Content-Type", "value": mime_type }],
+            "content": content(mime_type, bytes),
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": size,
+        },
+        "cache": {},
+        "timings": { "send": 0, "wait": 0, "receive": 0 },
+    })
+}
+
+pub fn run(args: PackArgs) {
+    if !Manifest::exists(&args.folder) {
+        pexit!(CannotOpenFile);
+    }
+    let manifest = Manifest::load(&args.folder);
+    let mut types = HashMap::new();
+    for preset in Preset::value_variants() {
+        for (mime_type, ext) in preset.mime_types() {
+            types.entry(ext.to_string()).or_insert(*mime_type);
+        }
+    }
+    for (mime_type, ext) in extraction::default_mime_types() {
+        types.insert(ext.to_string(), mime_type);
+    }
+    let mut document = args.original.as_deref().map(input::load_document);
+    let mut made_up = Vec::new();
+    let (mut packed, mut edited, mut missing) = (0, 0, 0);
+    // Only response bodies go back; request bodies and the like have no place to.
+    for (path, file) in &manifest.files {
+        let (Some(index), Some(url)) = (file.entry, &file.url) else {
+            continue;
+        };
+        let on_disk = args.folder.join(path);
+        let bytes = match fs::read(&on_disk) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                missing += 1;
+                eprintln!("{path}: {err}");
+                continue;
+            }
+        };
+        if manifest::sha256_hex(&bytes) != file.sha256 {
+            edited += 1;
+        }
+        match &mut document {
+            Some(document) => match original_response(document, index, url) {
+                Some(response) => replace_body(response, bytes),
+                None => {
+                    missing += 1;
+                    eprintln!("{}", tr!(PackEntryMismatch, path, index));
+                    continue;
+                }
+            },
+            None => {
+                let modified = fs::metadata(&on_disk)
+                    .and_then(|x| x.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                let mime_type = mime_type_of(path, &types);
+                made_up.push((index, made_up_entry(url, &mime_type, bytes, modified)));
+            }
+        }
+        packed += 1;
+    }
+    let document = document.unwrap_or_else(|| {
+        // In the order of the capture they were extracted from.
+        made_up.sort_by_key(|(index, _)| *index);
+        let entries = made_up.into_iter().map(|(_, x)| x).collect::<Vec<_>>();
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": "extract_har", "version": env!("CARGO_PKG_VERSION") },
+                "entries": entries,
+            }
+        })
+    });
+    if packed + missing == 0 {
+        pexit!(PackNothing, args.folder.to_string_lossy());
+    }
+    input::write_document(&args.output, &document);
+    eprintln!(
+        "{}",
+        tr!(
+            PackSummary,
+            packed,
+            edited,
+            args.output.to_string_lossy(),
+            missing
+        )
+    );
+    if missing > 0 {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    fn capture() -> Value {
+        json!({
+            "log": {
+                "entries": [{
+                    "request": { "url": "https://a.com/app.js" },
+                    "response": {
+                        "headers": [
+                            { "name": "Content-Encoding", "value": "gzip" },
+                            { "name": "content-length", "value": "999" },
+                        ],
+                        "content": { "mimeType": "text/javascript", "text": "old" },
+                    },
+                }],
+            }
+        })
+    }
+
+    #[test]
+    fn finds_the_response_of_the_entry() {
+        let mut document = capture();
+        assert!(original_response(&mut document, 0, "https://a.com/app.js").is_some());
+    }
+
+    #[test]
+    fn refuses_another_url() {
+        let mut document = capture();
+        assert!(original_response(&mut document, 0, "https://a.com/other.js").is_none());
+    }
+
+    // Used to panic when the capture had fewer entries than the manifest refers to.
+    #[test]
+    fn refuses_missing_entries() {
+        let mut document = capture();
+        assert!(original_response(&mut document, 1, "https://a.com/app.js").is_none());
+        let mut empty = json!({ "log": { "entries": [] } });
+        assert!(original_response(&mut empty, 0, "https://a.com/app.js").is_none());
+        for mut document in [json!({}), json!([]), json!({ "log": { "entries": [3] } })] {
+            assert!(original_response(&mut document, 0, "https://a.com/app.js").is_none());
+        }
+    }
+
+    #[test]
+    fn replaces_decoded_bodies_without_their_encoding() {
+        let mut document = capture();
+        let response = original_response(&mut document, 0, "https://a.com/app.js").unwrap();
+        replace_body(response, b"new();".to_vec());
+        let response = &document["log"]["entries"][0]["response"];
+        assert_eq!(
+            response["headers"],
+            json!([{ "name": "content-length", "value": "6" }])
+        );
+        assert_eq!(response["content"]["text"], "new();");
+        assert_eq!(response["content"]["size"], 6);
+        assert_eq!(response["bodySize"], 6);
+    }
+
+    #[test]
+    fn keeps_the_encoding_of_compressed_files() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"new();").unwrap();
+        let gzipped = encoder.finish().unwrap();
+        let mut document = capture();
+        let response = original_response(&mut document, 0, "https://a.com/app.js").unwrap();
+        replace_body(response, gzipped);
+        let response = &document["log"]["entries"][0]["response"];
+        assert_eq!(response["headers"][0]["name"], "Content-Encoding");
+        assert_eq!(response["content"]["encoding"], "base64");
+    }
+
+    #[test]
+    fn writes_text_as_text_and_the_rest_in_base64() {
+        assert_eq!(content("application/json", b"{}".to_vec())["text"], "{}");
+        let binary = content("image/png", b"png".to_vec());
+        assert_eq!(binary["text"], "cG5n");
+        assert_eq!(binary["encoding"], "base64");
+        let invalid = content("text/plain", vec![0xFF]);
+        assert_eq!(invalid["text"], "/w==");
+        assert_eq!(invalid["encoding"], "base64");
+    }
+
+    #[test]
+    fn types_files_by_extension() {
+        let types = HashMap::from([(".png".to_string(), "image/png")]);
+        assert_eq!(mime_type_of("a/b.PNG", &types), "image/png");
+        assert_eq!(mime_type_of("a/b", &types), "application/octet-stream");
+        assert_eq!(mime_type_of("b.bin", &types), "application/octet-stream");
+    }
+}
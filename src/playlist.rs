@@ -0,0 +1,206 @@
+use regex::Regex;
+use roxmltree::{Document, Node};
+use url::Url;
+
+/// A media playlist of an HLS stream.
+pub struct HlsPlaylist {
+    /// `#EXT-X-MEDIA-SEQUENCE`: the number of the first segment, which live playlists
+    /// advance as they drop old segments.
+    pub sequence: u64,
+    /// `#EXT-X-MAP`: the initialization segment of fragmented MP4 streams.
+    pub init: Option<Url>,
+    pub segments: Vec<Url>,
+    /// Segments are encrypted with `#EXT-X-KEY`, so joining them gives nothing playable.
+    pub encrypted: bool,
+}
+
+/// Value of an attribute in a tag such as `#EXT-X-MAP:URI="init.mp4",BYTERANGE=...`.
+fn hls_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let start = attributes.find(&format!("{name}="))? + name.len() + 1;
+    let value = &attributes[start..];
+    match value.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next(),
+        None => value.split(',').next(),
+    }
+}
+
+/// Parses an HLS media playlist loaded from `url`. Master playlists, which only list
+/// the media playlists of each variant, give `None`, as do other documents.
+pub fn parse_hls(url: &Url, text: &str) -> Option<HlsPlaylist> {
+    let mut lines = text.lines().map(str::trim);
+    if lines.next()?.trim_start_matches('\u{feff}') != "#EXTM3U" {
+        return None;
+    }
+    let mut playlist = HlsPlaylist {
+        sequence: 0,
+        init: None,
+        segments: Vec::new(),
+        encrypted: false,
+    };
+    for line in lines {
+        if line.starts_with("#EXT-X-STREAM-INF") {
+            return None;
+        } else if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+            playlist.sequence = value.parse().unwrap_or_default();
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-MAP:") {
+            playlist.init = hls_attribute(attributes, "URI").and_then(|x| url.join(x).ok());
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-KEY:") {
+            playlist.encrypted |= hls_attribute(attributes, "METHOD") != Some("NONE");
+        } else if !line.is_empty() && !line.starts_with('#') {
+            playlist.segments.extend(url.join(line).ok());
+        }
+    }
+    Some(playlist)
+}
+
+/// How the segments of a DASH representation are named.
+pub enum DashSegments {
+    /// `SegmentList`: every segment URL, in order.
+    List(Vec<Url>),
+    /// `SegmentTemplate`: a pattern whose only group is the `$Number$` or `$Time$` of
+    /// the segment, which orders them.
+    Template(Regex),
+}
+
+/// One representation of a DASH manifest, such as the 720p video or the audio track.
+pub struct DashRepresentation {
+    pub id: String,
+    pub mime_type: String,
+    pub init: Option<Url>,
+    pub segments: DashSegments,
+}
+
+impl DashRepresentation {
+    /// Extension of the file its segments join into.
+    pub fn extension(&self) -> &'static str {
+        match self.mime_type.as_str() {
+            "audio/mp4" => ".m4a",
+            "video/webm" => ".webm",
+            "audio/webm" => ".weba",
+            "video/mp2t" => ".ts",
+            _ => ".mp4",
+        }
+    }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|x| x.tag_name().name() == name)
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |x| x.tag_name().name() == name)
+}
+
+/// `base` with the element's `BaseURL`, if it has one, applied.
+fn base_url(node: Node, base: &Url) -> Url {
+    child(node, "BaseURL")
+        .and_then(|x| x.text())
+        .and_then(|x| base.join(x.trim()).ok())
+        .unwrap_or_else(|| base.clone())
+}
+
+/// Marks where the segment number goes while the template is resolved as a URL; it
+/// contains nothing that URL parsing would encode.
+const NUMBER: &str = "__segment_number__";
+
+/// Fills in a `SegmentTemplate` pattern such as `$RepresentationID$/$Number%05d$.m4s`,
+/// leaving [`NUMBER`] for the segment number.
+fn fill_template(template: &str, id: &str, bandwidth: &str) -> String {
+    let mut result = String::new();
+    for (i, part) in template.split('$').enumerate() {
+        if i % 2 == 0 {
+            result.push_str(part);
+            continue;
+        }
+        match part {
+            "" => result.push('$'),
+            "RepresentationID" => result.push_str(id),
+            x if x.starts_with("Bandwidth") => result.push_str(bandwidth),
+            x if x.starts_with("Number") || x.starts_with("Time") => result.push_str(NUMBER),
+            x => result.push_str(x),
+        }
+    }
+    result
+}
+
+/// Parses a DASH manifest loaded from `url`, returning the representations whose
+/// segments are listed or templated. Representations served as one file through
+/// `SegmentBase` have nothing to join and are left out.
+pub fn parse_dash(url: &Url, text: &str) -> Vec<DashRepresentation> {
+    let Ok(document) = Document::parse(text) else {
+        return Vec::new();
+    };
+    let mpd = document.root_element();
+    if mpd.tag_name().name() != "MPD" {
+        return Vec::new();
+    }
+    let mut representations = Vec::new();
+    let mpd_base = base_url(mpd, url);
+    for period in children(mpd, "Period") {
+        let period_base = base_url(period, &mpd_base);
+        for set in children(period, "AdaptationSet") {
+            let set_base = base_url(set, &period_base);
+            for representation in children(set, "Representation") {
+                let base = base_url(representation, &set_base);
+                let attribute = |name| {
+                    representation
+                        .attribute(name)
+                        .or_else(|| set.attribute(name))
+                        .unwrap_or_default()
+                };
+                let id = representation.attribute("id").unwrap_or_default();
+                let bandwidth = representation.attribute("bandwidth").unwrap_or_default();
+                let mime_type = attribute("mimeType").to_string();
+                // Template attributes are inherited one by one from the outer levels.
+                let templates = [representation, set, period]
+                    .into_iter()
+                    .filter_map(|x| child(x, "SegmentTemplate"))
+                    .collect::<Vec<_>>();
+                let template = |name| templates.iter().find_map(|x| x.attribute(name));
+                let list =
+                    child(representation, "SegmentList").or_else(|| child(set, "SegmentList"));
+                let (init, segments) = if let Some(media) = template("media") {
+                    let media = fill_template(media, id, bandwidth);
+                    let Some((resolved, escaped)) = base
+                        .join(&media)
+                        .ok()
+                        .map(|x| (x.to_string(), regex::escape(x.as_str())))
+                        .filter(|(x, _)| x.contains(NUMBER))
+                    else {
+                        continue;
+                    };
+                    // Players may append tokens to templated URLs.
+                    let query = if resolved.contains('?') {
+                        ""
+                    } else {
+                        r"(?:\?.*)?"
+                    };
+                    let pattern = format!("^{}{query}$", escaped.replace(NUMBER, r"(\d+)"));
+                    let init = template("initialization")
+                        .and_then(|x| base.join(&fill_template(x, id, bandwidth)).ok());
+                    (init, DashSegments::Template(Regex::new(&pattern).unwrap()))
+                } else if let Some(list) = list {
+                    let init = child(list, "Initialization")
+                        .and_then(|x| x.attribute("sourceURL"))
+                        .and_then(|x| base.join(x).ok());
+                    let segments = children(list, "SegmentURL")
+                        .filter_map(|x| base.join(x.attribute("media")?).ok())
+                        .collect();
+                    (init, DashSegments::List(segments))
+                } else {
+                    continue;
+                };
+                representations.push(DashRepresentation {
+                    id: id.to_string(),
+                    mime_type,
+                    init,
+                    segments,
+                });
+            }
+        }
+    }
+    representations
+}
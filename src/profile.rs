@@ -0,0 +1,86 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+/// Name of the configuration file looked up in the current folder and then in the
+/// user's configuration folder.
+pub const FILE_NAME: &str = "extracthar.toml";
+
+fn find_config() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|x| Path::new(&x).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from));
+    [
+        Some(PathBuf::from(FILE_NAME)),
+        config_home.map(|x| x.join(FILE_NAME)),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|x| x.is_file())
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(x) => Some(x.clone()),
+        Value::Integer(x) => Some(x.to_string()),
+        Value::Float(x) => Some(x.to_string()),
+        _ => None,
+    }
+}
+
+/// Turns a table of long option names into command-line arguments: `true` is a flag,
+/// arrays repeat the option and tables pass `key=value` pairs.
+fn table_args(table: &Table, args: &mut Vec<String>) {
+    for (name, value) in table {
+        let option = format!("--{name}");
+        match value {
+            Value::Boolean(true) => args.push(option),
+            // Lets a profile switch off a flag set in `[extract]`.
+            Value::Boolean(false) => args.retain(|x| *x != option),
+            Value::Array(items) => {
+                for item in items.iter().filter_map(scalar) {
+                    args.extend([option.clone(), item]);
+                }
+            }
+            Value::Table(pairs) => {
+                for (key, value) in pairs {
+                    if let Some(value) = scalar(value) {
+                        args.extend([option.clone(), format!("{key}={value}")]);
+                    }
+                }
+            }
+            value => args.extend([option].into_iter().chain(scalar(value))),
+        }
+    }
+}
+
+/// Extraction arguments from the configuration file: the `[extract]` table, followed
+/// by the `[profiles.<name>]` table when a profile is selected.
+pub fn args(config: Option<&Path>, profile: Option<&str>) -> Vec<String> {
+    let Some(path) = config.map(Path::to_path_buf).or_else(find_config) else {
+        if let Some(profile) = profile {
+            pexit!(ProfileNotFound, profile, FILE_NAME);
+        }
+        return Vec::new();
+    };
+    let table = fs::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|x| x.parse::<Table>().map_err(|err| err.to_string()))
+        .unwrap_or_else(|err| pexit!(CannotParseConfig, path.to_string_lossy(), err));
+    let mut args = Vec::new();
+    if let Some(Value::Table(defaults)) = table.get("extract") {
+        table_args(defaults, &mut args);
+    }
+    if let Some(profile) = profile {
+        let Some(Value::Table(settings)) = table.get("profiles").and_then(|x| x.get(profile))
+        else {
+            pexit!(ProfileNotFound, profile, path.to_string_lossy());
+        };
+        table_args(settings, &mut args);
+    }
+    args
+}
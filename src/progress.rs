@@ -0,0 +1,93 @@
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+};
+
+use serde::Serialize;
+
+/// Progress of an extraction run, reported to every registered [`Observer`].
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    Started {
+        /// Left out when entries are extracted as they are read, before their number
+        /// is known.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total: Option<usize>,
+    },
+    Extracted {
+        index: usize,
+        url: &'a str,
+        path: &'a str,
+        bytes: usize,
+    },
+    Skipped {
+        index: usize,
+        url: &'a str,
+        reason: &'a str,
+    },
+    Failed {
+        index: usize,
+        url: &'a str,
+        error: &'a str,
+    },
+    Finished {
+        extracted: usize,
+        total: usize,
+    },
+}
+
+pub trait Observer {
+    fn notify(&mut self, event: &Event);
+}
+
+/// Writes every event as one line of JSON to a Unix socket, named pipe or stdout.
+pub struct StreamObserver {
+    stream: Option<Box<dyn Write>>,
+}
+
+impl StreamObserver {
+    /// Unix sockets are connected to; anything else (a FIFO, a Windows `\\.\pipe\`
+    /// path or a plain file) is opened for writing.
+    pub fn open(path: &Path) -> io::Result<StreamObserver> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::{fs::FileTypeExt, net::UnixStream};
+            if path.metadata().is_ok_and(|x| x.file_type().is_socket()) {
+                let stream = UnixStream::connect(path)?;
+                return Ok(StreamObserver {
+                    stream: Some(Box::new(stream)),
+                });
+            }
+        }
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        Ok(StreamObserver {
+            stream: Some(Box::new(file)),
+        })
+    }
+
+    pub fn stdout() -> StreamObserver {
+        StreamObserver {
+            stream: Some(Box::new(io::stdout())),
+        }
+    }
+}
+
+impl Observer for StreamObserver {
+    fn notify(&mut self, event: &Event) {
+        let Some(stream) = &mut self.stream else {
+            return;
+        };
+        let mut line = serde_json::to_vec(event).unwrap();
+        line.push(b'\n');
+        // A listener that went away must not abort the extraction itself.
+        if stream
+            .write_all(&line)
+            .and_then(|_| stream.flush())
+            .is_err()
+        {
+            self.stream = None;
+        }
+    }
+}
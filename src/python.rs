@@ -0,0 +1,241 @@
+//! The `extracthar` Python module, built with `maturin build` (see `pyproject.toml`).
+//!
+//! ```python
+//! import extracthar
+//!
+//! har = extracthar.load_har("capture.har")
+//! for entry in har.filter(mime_type="image/", domain="example.com"):
+//!     print(entry.url, len(entry.body() or b""))
+//! extracthar.Extractor({"image/avif": ".avif"}).extract_to(har, "images")
+//! ```
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use pyo3::{
+    exceptions::{PyOSError, PyValueError},
+    prelude::*,
+    types::{PyBytes, PyDict, PyIterator, PyList},
+};
+
+use crate::{
+    decompress, extraction,
+    har::{self, HarLogEntry},
+    sink::{DirSink, MemorySink},
+};
+
+/// A parsed HAR document. Iterating over it gives its entries.
+#[pyclass(name = "Har", frozen)]
+pub struct PyHar {
+    har: har::Har,
+}
+
+impl PyHar {
+    fn entry_list<'py>(
+        slf: &Bound<'py, PyHar>,
+        keep: impl Fn(&HarLogEntry) -> bool,
+    ) -> PyResult<Bound<'py, PyList>> {
+        let log = &slf.get().har.log;
+        let entries = log.entries.iter().enumerate();
+        let entries = entries.filter(|(_, x)| keep(x)).map(|(position, _)| Entry {
+            har: slf.clone().unbind(),
+            position,
+            index: log.index_of(position),
+        });
+        PyList::new(slf.py(), entries)
+    }
+}
+
+#[pymethods]
+impl PyHar {
+    fn __len__(&self) -> usize {
+        self.har.log.entries.len()
+    }
+
+    fn __iter__<'py>(slf: &Bound<'py, PyHar>) -> PyResult<Bound<'py, PyIterator>> {
+        PyHar::entry_list(slf, |_| true)?.try_iter()
+    }
+
+    /// Entries matching every given criterion. `mime_type` ending with `/` matches the
+    /// whole kind, such as `image/`; `domain` also matches its subdomains.
+    #[pyo3(signature = (mime_type=None, domain=None, status=None, method=None))]
+    fn filter<'py>(
+        slf: &Bound<'py, PyHar>,
+        mime_type: Option<&str>,
+        domain: Option<&str>,
+        status: Option<i32>,
+        method: Option<&str>,
+    ) -> PyResult<Bound<'py, PyList>> {
+        PyHar::entry_list(slf, |entry| {
+            let essence = entry.response.content.essence().to_ascii_lowercase();
+            let host = entry.request.url.host_str().unwrap_or_default();
+            mime_type.is_none_or(|x| {
+                let x = x.to_ascii_lowercase();
+                if x.ends_with('/') {
+                    essence.starts_with(&x)
+                } else {
+                    essence == x
+                }
+            }) && domain.is_none_or(|x| {
+                host.eq_ignore_ascii_case(x)
+                    || host
+                        .to_ascii_lowercase()
+                        .ends_with(&format!(".{}", x.to_ascii_lowercase()))
+            }) && status.is_none_or(|x| entry.response.status == x)
+                && method.is_none_or(|x| entry.request.method.eq_ignore_ascii_case(x))
+        })
+    }
+}
+
+/// One request and its response.
+#[pyclass(frozen)]
+pub struct Entry {
+    har: Py<PyHar>,
+    /// Position among the entries that could be read.
+    position: usize,
+    /// Position in the document's `log.entries`.
+    #[pyo3(get)]
+    index: usize,
+}
+
+impl Entry {
+    fn entry(&self) -> &HarLogEntry {
+        &self.har.get().har.log.entries[self.position]
+    }
+}
+
+#[pymethods]
+impl Entry {
+    #[getter]
+    fn url(&self) -> String {
+        self.entry().request.url.to_string()
+    }
+
+    #[getter]
+    fn method(&self) -> String {
+        self.entry().request.method.clone()
+    }
+
+    #[getter]
+    fn status(&self) -> i32 {
+        self.entry().response.status
+    }
+
+    #[getter]
+    fn mime_type(&self) -> String {
+        self.entry().response.content.mime_type.clone()
+    }
+
+    #[getter]
+    fn started(&self) -> String {
+        self.entry().started_date_time.clone()
+    }
+
+    /// Total time of the request in milliseconds.
+    #[getter]
+    fn time(&self) -> f64 {
+        self.entry().time
+    }
+
+    #[getter]
+    fn pageref(&self) -> Option<String> {
+        self.entry().pageref.clone()
+    }
+
+    /// Value of the first response header with this name, compared case-insensitively.
+    fn header(&self, name: &str) -> Option<String> {
+        self.entry().response.header(name).map(str::to_string)
+    }
+
+    /// The response body, decoded from base64 and decompressed as needed, or `None` if
+    /// it is not valid base64.
+    fn body<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyBytes>> {
+        let response = &self.entry().response;
+        let body = response.content.decoded()?;
+        let body =
+            decompress::decompress(response.header("content-encoding"), &body).unwrap_or(body);
+        Some(PyBytes::new(py, &body))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Entry {} {} {}>", self.index, self.method(), self.url())
+    }
+}
+
+/// Parses a HAR document given as bytes, skipping entries that cannot be read.
+#[pyfunction]
+fn parse_har(data: &[u8]) -> PyResult<PyHar> {
+    let (har, _) =
+        har::parse_lenient(data).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(PyHar { har })
+}
+
+/// Reads and parses a HAR file.
+#[pyfunction]
+fn load_har(path: PathBuf) -> PyResult<PyHar> {
+    parse_har(&fs::read(path)?)
+}
+
+/// Writes out the response bodies of a capture, as the command line tool does without
+/// layout options.
+#[pyclass(frozen)]
+pub struct Extractor {
+    /// Extension of each MIME type to extract.
+    mime_types: HashMap<String, String>,
+}
+
+impl Extractor {
+    fn mime_types(&self) -> HashMap<&str, &str> {
+        self.mime_types
+            .iter()
+            .map(|(x, y)| (x.as_str(), y.as_str()))
+            .collect()
+    }
+}
+
+#[pymethods]
+impl Extractor {
+    /// `mime_types` maps MIME types to the extension of their files, such as
+    /// `{"image/avif": ".avif"}`, and are extracted in addition to images.
+    #[new]
+    #[pyo3(signature = (mime_types=None))]
+    fn new(mime_types: Option<HashMap<String, String>>) -> Extractor {
+        let mut types = extraction::default_mime_types()
+            .into_iter()
+            .map(|(x, y)| (x.to_string(), y.to_string()))
+            .collect::<HashMap<_, _>>();
+        for (mime_type, ext) in mime_types.unwrap_or_default() {
+            let ext = format!(".{}", ext.trim_start_matches('.'));
+            types.insert(mime_type.to_ascii_lowercase(), ext);
+        }
+        Extractor { mime_types: types }
+    }
+
+    /// The extracted files as a dict of paths to their contents.
+    fn extract<'py>(&self, py: Python<'py>, har: &PyHar) -> PyResult<Bound<'py, PyDict>> {
+        let mut sink = MemorySink::default();
+        extraction::extract(&har.har, &self.mime_types(), &mut sink);
+        let files = PyDict::new(py);
+        for (path, bytes) in sink.files {
+            files.set_item(path.to_string_lossy(), PyBytes::new(py, &bytes))?;
+        }
+        Ok(files)
+    }
+
+    /// Writes the extracted files into `folder` and returns how many were written,
+    /// raising `OSError` after the others are written if any failed.
+    fn extract_to(&self, har: &PyHar, folder: PathBuf) -> PyResult<usize> {
+        fs::create_dir_all(&folder)?;
+        let mut sink = DirSink::new(folder);
+        let summary = extraction::extract(&har.har, &self.mime_types(), &mut sink);
+        match summary.failures.first() {
+            Some((index, message)) => Err(PyOSError::new_err(format!("entry {index}: {message}"))),
+            None => Ok(summary.extracted),
+        }
+    }
+}
+
+#[pymodule(name = "extracthar")]
+mod module {
+    #[pymodule_export]
+    use super::{load_har, parse_har, Entry, Extractor, PyHar};
+}
@@ -0,0 +1,170 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use clap::Args;
+use extract_har::import::{self, Exchange};
+use ureq::Agent;
+use url::Url;
+
+use crate::input;
+
+#[derive(Args)]
+pub struct RecordArgs {
+    /// File with one URL per line; blank lines and lines starting with `#` are skipped
+    urls: PathBuf,
+    /// HAR file to write
+    #[arg(short, long)]
+    output: PathBuf,
+    /// Header sent with every request, as `Name: value` (repeatable)
+    #[arg(short = 'H', long = "header", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+    /// Number of downloads running at once
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+    /// Seconds after which a download is abandoned
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+    /// Redirects followed per URL, each recorded as its own entry; 0 records the
+    /// redirect response only
+    #[arg(long, default_value_t = 10)]
+    max_redirects: u32,
+}
+
+fn parse_header(arg: &str) -> Result<(String, String), String> {
+    let (name, value) = arg
+        .split_once(':')
+        .ok_or_else(|| "expected `Name: value`".to_string())?;
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+/// Downloads `url` without following redirects. Failures that left no response are
+/// recorded with status 0, as browsers do.
+fn get(agent: &Agent, url: &Url, headers: &[(String, String)]) -> (Exchange, Option<String>) {
+    let mut exchange = Exchange {
+        started: Some(Utc::now().fixed_offset()),
+        method: "GET".to_string(),
+        url: url.to_string(),
+        http_version: "HTTP/1.1".to_string(),
+        request_headers: headers.to_vec(),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    let mut call = agent.get(url.as_str());
+    for (name, value) in headers {
+        call = call.header(name, value);
+    }
+    let error = match call.call() {
+        Ok(mut response) => {
+            exchange.status = response.status().as_u16().into();
+            exchange.status_text = response
+                .status()
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string();
+            exchange.http_version = format!("{:?}", response.version());
+            exchange.response_headers = response
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    (name.to_string(), value)
+                })
+                .collect();
+            // Kept with its `Content-Encoding` header even when the body was
+            // decompressed on the way, as browsers export it.
+            match response
+                .body_mut()
+                .with_config()
+                .limit(u64::MAX)
+                .read_to_vec()
+            {
+                Ok(body) => {
+                    exchange.response_body = body;
+                    None
+                }
+                Err(err) => Some(err.to_string()),
+            }
+        }
+        Err(err) => Some(err.to_string()),
+    };
+    exchange.time = start.elapsed().as_secs_f64() * 1000.0;
+    (exchange, error)
+}
+
+/// Downloads `url` and the redirects it leads to, one exchange each.
+fn record(agent: &Agent, url: Url, args: &RecordArgs) -> Vec<Exchange> {
+    let mut exchanges = Vec::new();
+    let mut url = url;
+    loop {
+        let (exchange, error) = get(agent, &url, &args.headers);
+        if let Some(error) = error {
+            eprintln!("{}", tr!(CannotRecordUrl, url, error));
+        }
+        let location = (300..400)
+            .contains(&exchange.status)
+            .then(|| {
+                exchange
+                    .response_headers
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case("location"))
+            })
+            .flatten()
+            .and_then(|(_, x)| url.join(x).ok());
+        exchanges.push(exchange);
+        match location {
+            Some(location) if exchanges.len() <= args.max_redirects as usize => url = location,
+            _ => return exchanges,
+        }
+    }
+}
+
+pub fn run(args: RecordArgs) {
+    let list = fs::read_to_string(&args.urls).unwrap_or_else(|_| pexit!(CannotOpenFile));
+    let urls = list
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty() && !x.starts_with('#'))
+        .map(|x| Url::parse(x).unwrap_or_else(|err| pexit!(CannotRecordUrl, x, err)))
+        .collect::<Vec<_>>();
+    let agent: Agent = Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(args.timeout)))
+        .max_redirects(0)
+        .http_status_as_error(false)
+        .build()
+        .into();
+    let queue = Mutex::new(urls.into_iter().enumerate());
+    let recorded = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..args.concurrency.max(1) {
+            scope.spawn(|| loop {
+                let Some((position, url)) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                let exchanges = record(&agent, url, &args);
+                recorded.lock().unwrap().push((position, exchanges));
+            });
+        }
+    });
+    // Entries follow the list, whichever download finished first.
+    let mut recorded = recorded.into_inner().unwrap();
+    recorded.sort_by_key(|(position, _)| *position);
+    let exchanges = recorded
+        .into_iter()
+        .flat_map(|(_, x)| x)
+        .collect::<Vec<_>>();
+    input::write_document(&args.output, &import::document(&exchanges));
+    eprintln!(
+        "{}",
+        tr!(
+            CaptureWritten,
+            exchanges.len(),
+            args.output.to_string_lossy()
+        )
+    );
+}
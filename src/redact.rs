@@ -0,0 +1,273 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use serde_json::Value;
+use url::Url;
+
+use crate::input;
+
+const MASK: &str = "REDACTED";
+
+const DEFAULT_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "x-auth-token",
+    "x-csrf-token",
+];
+
+const DEFAULT_PARAMS: &[&str] = &[
+    "*token*",
+    "*password*",
+    "*secret*",
+    "api_key",
+    "apikey",
+    "key",
+    "sig",
+    "signature",
+    "session*",
+];
+
+#[derive(Args)]
+pub struct RedactArgs {
+    input_har: String,
+    output_har: PathBuf,
+    /// Additional header name to mask (repeatable, case-insensitive)
+    #[arg(long = "header")]
+    headers: Vec<String>,
+    /// Additional query/form parameter pattern to mask, `*` matches anything (repeatable)
+    #[arg(long = "param")]
+    params: Vec<String>,
+    /// Do not add the built-in header and parameter lists
+    #[arg(long)]
+    no_defaults: bool,
+    /// Keep request and response bodies instead of removing them
+    #[arg(long)]
+    keep_bodies: bool,
+}
+
+/// Case-insensitive match of `name` against a pattern where `*` stands for any run of
+/// characters.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let name = name.to_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[derive(Default)]
+struct Counts {
+    headers: usize,
+    cookies: usize,
+    params: usize,
+    bodies: usize,
+}
+
+struct Redactor {
+    headers: Vec<String>,
+    params: Vec<String>,
+    keep_bodies: bool,
+    counts: Counts,
+}
+
+impl Redactor {
+    fn is_secret_param(&self, name: &str) -> bool {
+        self.params.iter().any(|x| wildcard_match(x, name))
+    }
+
+    fn mask_headers(&mut self, headers: Option<&mut Value>) {
+        for header in headers.and_then(|x| x.as_array_mut()).into_iter().flatten() {
+            let name = header["name"].as_str().unwrap_or_default().to_lowercase();
+            if self.headers.contains(&name) {
+                header["value"] = Value::from(MASK);
+                self.counts.headers += 1;
+            }
+        }
+    }
+
+    fn mask_cookies(&mut self, cookies: Option<&mut Value>) {
+        for cookie in cookies.and_then(|x| x.as_array_mut()).into_iter().flatten() {
+            cookie["value"] = Value::from(MASK);
+            self.counts.cookies += 1;
+        }
+    }
+
+    fn mask_params(&mut self, params: Option<&mut Value>) {
+        for param in params.and_then(|x| x.as_array_mut()).into_iter().flatten() {
+            if self.is_secret_param(param["name"].as_str().unwrap_or_default()) {
+                param["value"] = Value::from(MASK);
+                self.counts.params += 1;
+            }
+        }
+    }
+
+    /// Rewrites secret query parameters inside a URL string.
+    fn mask_url(&self, url: Option<&mut Value>) {
+        let Some(url) = url else {
+            return;
+        };
+        let Some(mut parsed) = url.as_str().and_then(|x| Url::parse(x).ok()) else {
+            return;
+        };
+        if !parsed
+            .query_pairs()
+            .any(|(name, _)| self.is_secret_param(&name))
+        {
+            return;
+        }
+        let pairs = parsed
+            .query_pairs()
+            .map(|(name, value)| {
+                let value = if self.is_secret_param(&name) {
+                    MASK.into()
+                } else {
+                    value
+                };
+                (name.into_owned(), value.into_owned())
+            })
+            .collect::<Vec<_>>();
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+        *url = Value::from(parsed.as_str());
+    }
+
+    fn redact_entry(&mut self, entry: &mut Value) {
+        if let Some(request) = entry.get_mut("request") {
+            self.mask_url(request.get_mut("url"));
+            self.mask_headers(request.get_mut("headers"));
+            self.mask_cookies(request.get_mut("cookies"));
+            self.mask_params(request.get_mut("queryString"));
+            if let Some(post_data) = request.get_mut("postData") {
+                if self.keep_bodies {
+                    self.mask_params(post_data.get_mut("params"));
+                } else if let Some(post_data) = post_data.as_object_mut() {
+                    post_data.remove("text");
+                    post_data.remove("params");
+                    self.counts.bodies += 1;
+                }
+            }
+        }
+        if let Some(response) = entry.get_mut("response") {
+            self.mask_url(response.get_mut("redirectURL"));
+            self.mask_headers(response.get_mut("headers"));
+            self.mask_cookies(response.get_mut("cookies"));
+            if !self.keep_bodies {
+                let content = response.get_mut("content").and_then(|x| x.as_object_mut());
+                if let Some(content) = content {
+                    if content.remove("text").is_some() {
+                        content.remove("encoding");
+                        self.counts.bodies += 1;
+                    }
+                }
+            }
+        }
+        if !self.keep_bodies {
+            if let Some(entry) = entry.as_object_mut() {
+                entry.remove("_webSocketMessages");
+            }
+        }
+    }
+}
+
+/// Masks the headers, cookies and parameters `redact` masks by default in an entry,
+/// leaving its bodies as they are.
+pub fn mask_secrets(entry: &mut Value) {
+    let mut redactor = Redactor {
+        headers: DEFAULT_HEADERS.iter().map(|x| x.to_string()).collect(),
+        params: DEFAULT_PARAMS.iter().map(|x| x.to_string()).collect(),
+        keep_bodies: true,
+        counts: Counts::default(),
+    };
+    redactor.redact_entry(entry);
+}
+
+pub fn run(args: RedactArgs) {
+    let mut document = input::load_document(&args.input_har);
+    let mut redactor = Redactor {
+        headers: args.headers.iter().map(|x| x.to_lowercase()).collect(),
+        params: args.params,
+        keep_bodies: args.keep_bodies,
+        counts: Counts::default(),
+    };
+    if !args.no_defaults {
+        redactor
+            .headers
+            .extend(DEFAULT_HEADERS.iter().map(|x| x.to_string()));
+        redactor
+            .params
+            .extend(DEFAULT_PARAMS.iter().map(|x| x.to_string()));
+    }
+    let entries = document
+        .pointer_mut("/log/entries")
+        .and_then(|x| x.as_array_mut());
+    for entry in entries.into_iter().flatten() {
+        redactor.redact_entry(entry);
+    }
+    input::write_document(&args.output_har, &document);
+    let counts = &redactor.counts;
+    eprintln!(
+        "{}",
+        tr!(
+            RedactSummary,
+            counts.headers,
+            counts.cookies,
+            counts.params,
+            counts.bodies,
+            args.output_har.to_string_lossy()
+        )
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_without_wildcards_ignoring_case() {
+        assert!(wildcard_match("authorization", "Authorization"));
+        assert!(wildcard_match("X-API-KEY", "x-api-key"));
+        assert!(!wildcard_match("token", "tokens"));
+        assert!(!wildcard_match("token", "atoken"));
+        assert!(wildcard_match("", ""));
+        assert!(!wildcard_match("", "a"));
+    }
+
+    #[test]
+    fn matches_wildcards_at_the_ends() {
+        assert!(wildcard_match("*token", "access_token"));
+        assert!(wildcard_match("*token", "token"));
+        assert!(!wildcard_match("*token", "token_type"));
+        assert!(wildcard_match("x-*", "X-Csrf-Token"));
+        assert!(wildcard_match("x-*", "x-"));
+        assert!(!wildcard_match("x-*", "ax-b"));
+        assert!(wildcard_match("*secret*", "client_secret_id"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("**", "anything"));
+    }
+
+    #[test]
+    fn matches_wildcards_in_the_middle() {
+        assert!(wildcard_match("x-*-key", "x-api-key"));
+        assert!(wildcard_match("x-*-key", "x--key"));
+        assert!(!wildcard_match("x-*-key", "x-key"));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+        assert!(!wildcard_match("a*b*bc", "abc"));
+        assert!(!wildcard_match("x*x", "x"));
+        assert!(wildcard_match("x*x", "xx"));
+    }
+}
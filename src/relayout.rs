@@ -0,0 +1,159 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use clap::Args;
+use extract_har::{
+    hooks,
+    manifest::{self, Manifest},
+};
+use url::Url;
+
+use crate::extract::NO_HOST_FOLDER;
+
+#[derive(Args)]
+pub struct RelayoutArgs {
+    /// Manifest of an earlier extraction, or the output folder holding it
+    manifest: PathBuf,
+    /// New path of each file, built from `{domain}`, `{path}` (the folders of the URL),
+    /// `{name}`, `{stem}`, `{ext}`, `{hash}` and `{folder}` (where the file is now),
+    /// e.g. `{domain}/{ext}/{name}`
+    #[arg(long)]
+    template: String,
+    /// Folder to build the new layout in
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+/// The relative path `template` gives the file at `path`, with `..` and other
+/// components that would leave the output folder dropped.
+fn new_path(template: &str, path: &str, file: &manifest::ManifestFile) -> PathBuf {
+    let (folder, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, ext),
+        _ => (name, ""),
+    };
+    // Files extracted before URLs were recorded keep their folders.
+    let url = file.url.as_deref().and_then(|x| Url::parse(x).ok());
+    let domain = url
+        .as_ref()
+        .map_or(NO_HOST_FOLDER, |x| x.host_str().unwrap_or(NO_HOST_FOLDER));
+    let url_folder = url
+        .as_ref()
+        .and_then(|x| {
+            let segments = x.path_segments()?.collect::<Vec<_>>();
+            Some(segments[..segments.len() - 1].join("/"))
+        })
+        .unwrap_or_else(|| folder.to_string());
+    let values = [
+        ("{domain}", domain),
+        ("{path}", url_folder.as_str()),
+        ("{name}", name),
+        ("{stem}", stem),
+        ("{ext}", ext),
+        ("{hash}", &file.sha256[..16.min(file.sha256.len())]),
+        ("{folder}", folder),
+    ];
+    let expanded = hooks::expand(template, &values);
+    Path::new(&expanded.replace('\\', "/"))
+        .components()
+        .filter(|x| matches!(x, Component::Normal(_)))
+        .collect()
+}
+
+/// `path`, or a `~2`, `~3`... variant of it that no other file took.
+fn claim(taken: &mut HashSet<PathBuf>, path: PathBuf) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem.to_string(), format!(".{ext}")),
+        _ => (name.to_string(), String::new()),
+    };
+    let mut candidate = path.clone();
+    let mut n = 1;
+    while !taken.insert(candidate.clone()) {
+        n += 1;
+        candidate = path.with_file_name(format!("{stem}~{n}{ext}"));
+    }
+    candidate
+}
+
+/// Hard-links `source` at `target`, replacing what was there, or copies it where
+/// the file system cannot link, such as across drives. Returns whether it copied.
+fn link(source: &Path, target: &Path) -> io::Result<bool> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if target.symlink_metadata().is_ok() {
+        fs::remove_file(target)?;
+    }
+    match fs::hard_link(source, target) {
+        Ok(()) => Ok(false),
+        Err(_) => fs::copy(source, target).map(|_| true),
+    }
+}
+
+pub fn run(args: RelayoutArgs) {
+    let root = if args.manifest.is_dir() {
+        args.manifest.clone()
+    } else {
+        args.manifest
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_path_buf()
+    };
+    if !root.join(manifest::FILE_NAME).is_file() {
+        pexit!(CannotOpenFile);
+    }
+    if fs::canonicalize(&root).ok() == fs::canonicalize(&args.output).ok() {
+        pexit!(RelayoutSameFolder);
+    }
+    fs::create_dir_all(&args.output).unwrap_or_else(|_| {
+        pexit!(CannotCreateDirs, args.output.to_string_lossy());
+    });
+    let old = Manifest::load(&root);
+    let mut new = Manifest::default();
+    let mut taken = HashSet::new();
+    let (mut linked, mut copied, mut failed) = (0, 0, 0);
+    for (path, file) in old.files {
+        let target = new_path(&args.template, &path, &file);
+        if target.file_name().is_none() {
+            failed += 1;
+            eprintln!("{path}: {}", tr!(RelayoutEmptyPath));
+            continue;
+        }
+        let target = claim(&mut taken, target);
+        match link(&root.join(&path), &args.output.join(&target)) {
+            Ok(was_copied) => {
+                if was_copied {
+                    copied += 1;
+                } else {
+                    linked += 1;
+                }
+                new.record(&target, file);
+            }
+            Err(err) => {
+                failed += 1;
+                eprintln!("{path}: {err}");
+            }
+        }
+    }
+    if let Err(err) = new.save(&args.output, false) {
+        failed += 1;
+        eprintln!("{}: {err}", manifest::FILE_NAME);
+    }
+    eprintln!(
+        "{}",
+        tr!(
+            RelayoutSummary,
+            linked,
+            copied,
+            failed,
+            args.output.to_string_lossy()
+        )
+    );
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,210 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use base64::Engine;
+use extract_har::progress::{Event, Observer};
+use url::Url;
+
+use crate::stats::human_size;
+
+/// Images up to this size are embedded in the report as thumbnails; larger ones are
+/// only linked, to keep the report small enough to attach to a ticket.
+const MAX_THUMBNAIL_BYTES: u64 = 256 * 1024;
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// MIME type of an image file the report can show, by its extension.
+fn image_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        _ => return None,
+    })
+}
+
+/// Sorts a table by the column whose header was clicked, by the cells' `data-sort`
+/// values where they have one.
+const SORT_SCRIPT: &str = "document.querySelectorAll('thead th').forEach(th=>th.onclick=()=>{\
+const i=th.cellIndex,b=th.closest('table').tBodies[0],d=th.dataset.d=th.dataset.d==='1'?'-1':'1';\
+const k=r=>{const c=r.cells[i];return c.dataset.sort??c.textContent};\
+[...b.rows].sort((x,y)=>{const a=k(x),c=k(y),n=a-c;\
+return (isNaN(n)?a.localeCompare(c):n)*d}).forEach(r=>b.append(r))})";
+
+/// Collects what a run does and writes it as a single HTML file once it finishes.
+pub struct Report {
+    path: PathBuf,
+    /// Output folder the extracted paths are relative to.
+    root: PathBuf,
+    input: String,
+    total: usize,
+    extracted: Vec<(usize, String, String, usize)>,
+    skipped: Vec<(usize, String, String)>,
+    failed: Vec<(usize, String, String)>,
+}
+
+impl Report {
+    pub fn new(path: PathBuf, root: &Path, input: &Path) -> Report {
+        Report {
+            path,
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            input: input.to_string_lossy().into_owned(),
+            total: 0,
+            extracted: Vec::new(),
+            skipped: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+
+    /// A data URL of an extracted image small enough to embed.
+    fn thumbnail(&self, file: &Path) -> Option<String> {
+        let mime_type = image_type(file)?;
+        if fs::metadata(file).ok()?.len() > MAX_THUMBNAIL_BYTES {
+            return None;
+        }
+        let data = base64::engine::general_purpose::STANDARD.encode(fs::read(file).ok()?);
+        Some(format!("data:{mime_type};base64,{data}"))
+    }
+
+    fn html(&self) -> String {
+        let bytes = self.extracted.iter().map(|x| x.3 as u64).sum::<u64>();
+        let mut reasons = BTreeMap::<&str, usize>::new();
+        for (_, _, reason) in &self.skipped {
+            *reasons.entry(reason).or_default() += 1;
+        }
+        let mut html = format!(
+            "<!DOCTYPE html>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\
+             body{{font-family:sans-serif;margin:16px}}\
+             table{{border-collapse:collapse;margin-bottom:24px}}\
+             th,td{{border:1px solid #ccc;padding:4px 8px;text-align:left;vertical-align:middle}}\
+             th{{background:#f4f4f4;cursor:pointer}}\
+             td{{word-break:break-all}}\
+             img{{max-width:96px;max-height:96px}}</style>\n\
+             <h1>{title}</h1>\n<table>\n\
+             <tr><th>{entries}</th><td>{total}</td></tr>\n\
+             <tr><th>{extracted}</th><td>{count_extracted} ({size})</td></tr>\n\
+             <tr><th>{skipped}</th><td>{count_skipped}</td></tr>\n\
+             <tr><th>{failed}</th><td>{count_failed}</td></tr>\n</table>\n",
+            title = escape_html(&tr!(ReportTitle, self.input)),
+            entries = tr!(ReportEntries),
+            total = self.total,
+            extracted = tr!(ReportExtracted),
+            count_extracted = self.extracted.len(),
+            size = human_size(bytes),
+            skipped = tr!(ReportSkipped),
+            count_skipped = self.skipped.len(),
+            failed = tr!(ReportFailed),
+            count_failed = self.failed.len(),
+        );
+        if !reasons.is_empty() {
+            html.push_str(&format!(
+                "<h2>{}</h2>\n<table>\n",
+                tr!(ReportSkippedByReason)
+            ));
+            for (reason, count) in reasons {
+                html.push_str(&format!(
+                    "<tr><td>{}</td><td>{count}</td></tr>\n",
+                    escape_html(reason)
+                ));
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str(&format!(
+            "<h2>{}</h2>\n<table>\n<thead><tr><th>#</th><th></th><th>{}</th><th>{}</th><th>URL</th></tr></thead>\n<tbody>\n",
+            tr!(ReportExtracted),
+            tr!(ColumnName),
+            tr!(ColumnBytes)
+        ));
+        for (index, url, path, size) in &self.extracted {
+            let file = self.root.join(path);
+            let href = Url::from_file_path(&file)
+                .map(String::from)
+                .unwrap_or_default();
+            let thumbnail = self
+                .thumbnail(&file)
+                .map(|x| format!("<img src=\"{x}\" loading=\"lazy\">"))
+                .unwrap_or_default();
+            html.push_str(&format!(
+                "<tr><td>{index}</td><td>{thumbnail}</td><td><a href=\"{}\">{}</a></td><td data-sort=\"{size}\">{}</td><td>{}</td></tr>\n",
+                escape_html(&href),
+                escape_html(path),
+                human_size(*size as u64),
+                escape_html(url)
+            ));
+        }
+        html.push_str("</tbody>\n</table>\n");
+        for (title, rows) in [
+            (tr!(ReportFailed), &self.failed),
+            (tr!(ReportSkipped), &self.skipped),
+        ] {
+            if rows.is_empty() {
+                continue;
+            }
+            html.push_str(&format!(
+                "<h2>{title}</h2>\n<table>\n<thead><tr><th>#</th><th>URL</th><th>{}</th></tr></thead>\n<tbody>\n",
+                tr!(ReportReason)
+            ));
+            for (index, url, reason) in rows {
+                html.push_str(&format!(
+                    "<tr><td>{index}</td><td>{}</td><td>{}</td></tr>\n",
+                    escape_html(url),
+                    escape_html(reason)
+                ));
+            }
+            html.push_str("</tbody>\n</table>\n");
+        }
+        html.push_str(&format!("<script>{SORT_SCRIPT}</script>\n"));
+        html
+    }
+
+    fn write(&self) -> io::Result<()> {
+        fs::write(&self.path, self.html())
+    }
+}
+
+impl Observer for Report {
+    fn notify(&mut self, event: &Event) {
+        match *event {
+            Event::Started { total } => self.total = total.unwrap_or_default(),
+            Event::Extracted {
+                index,
+                url,
+                path,
+                bytes,
+            } => {
+                let path = path.replace('\\', "/");
+                self.extracted.push((index, url.to_string(), path, bytes));
+            }
+            Event::Skipped { index, url, reason } => {
+                self.skipped
+                    .push((index, url.to_string(), reason.to_string()));
+            }
+            Event::Failed { index, url, error } => {
+                self.failed
+                    .push((index, url.to_string(), error.to_string()));
+            }
+            // The run is over, so problems with the report itself only get a message.
+            Event::Finished { total, .. } => {
+                self.total = total;
+                match self.write() {
+                    Ok(()) => eprintln!("{}", tr!(ReportWritten, self.path.to_string_lossy())),
+                    Err(err) => eprintln!("{}: {err}", self.path.to_string_lossy()),
+                }
+            }
+        }
+    }
+}
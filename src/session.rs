@@ -0,0 +1,31 @@
+use crate::har::{Har, HarLogEntry};
+
+/// A loaded capture for commands that only inspect it, such as `list`, `stats` and
+/// `grep`.
+///
+/// The session owns no [`Sink`](crate::sink::Sink) and offers no way to attach one,
+/// so anything handed an `AnalysisSession` is unable to produce output files through
+/// it. Commands that write files, such as `export` and `index`, take the [`Har`]
+/// itself instead, so that a command using a session is known to only read.
+pub struct AnalysisSession {
+    har: Har,
+}
+
+impl AnalysisSession {
+    pub fn new(har: Har) -> AnalysisSession {
+        AnalysisSession { har }
+    }
+
+    pub fn har(&self) -> &Har {
+        &self.har
+    }
+
+    pub fn entries(&self) -> &[HarLogEntry] {
+        &self.har.log.entries
+    }
+
+    /// The entries with their indices in the document, as shown to users.
+    pub fn indexed_entries(&self) -> impl Iterator<Item = (usize, &HarLogEntry)> {
+        self.har.log.indexed_entries()
+    }
+}
@@ -0,0 +1,178 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
+};
+
+use filetime::FileTime;
+
+/// Destination for extracted files, addressed by paths relative to the output root.
+pub trait Sink {
+    fn write(&mut self, path: &Path, bytes: &[u8]) -> io::Result<()>;
+}
+
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// Replaces `path` with `bytes` through a temporary file in the same folder, so readers
+/// see either the old or the complete new file. With `durable`, the file and folder are
+/// also flushed to disk before returning.
+pub fn write_atomic(path: &Path, bytes: &[u8], durable: bool) -> io::Result<()> {
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    // Unique per call, as threads may write the same path at once.
+    let count = TEMP_FILES.fetch_add(1, Ordering::Relaxed);
+    temp_name.push(format!(".{}.{count}.tmp", std::process::id()));
+    let temp_file = parent.join(temp_name);
+    let result = File::create(&temp_file)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            if durable {
+                file.sync_all()?;
+            }
+            Ok(())
+        })
+        .and_then(|_| fs::rename(&temp_file, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_file);
+        return result;
+    }
+    // Directories cannot be opened for syncing on Windows; the rename is still atomic.
+    #[cfg(unix)]
+    if durable {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Error for a path that would be written outside the output root, through `..`, an
+/// absolute path or a symlink.
+#[derive(Debug)]
+pub struct OutsideRoot;
+
+impl fmt::Display for OutsideRoot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("path leads outside the output folder")
+    }
+}
+
+impl Error for OutsideRoot {}
+
+impl OutsideRoot {
+    pub fn is(err: &io::Error) -> bool {
+        err.get_ref().is_some_and(|x| x.is::<OutsideRoot>())
+    }
+}
+
+fn outside_root() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, OutsideRoot)
+}
+
+/// Writes files under a folder on disk, creating subfolders as needed.
+#[derive(Clone)]
+pub struct DirSink {
+    root: PathBuf,
+    durable: bool,
+}
+
+impl DirSink {
+    pub fn new(root: PathBuf) -> DirSink {
+        DirSink {
+            root,
+            durable: false,
+        }
+    }
+
+    /// Flush every file and its folder to disk before reporting it written.
+    pub fn with_durable(self, durable: bool) -> DirSink {
+        DirSink { durable, ..self }
+    }
+
+    pub fn is_durable(&self) -> bool {
+        self.durable
+    }
+
+    /// Fails unless `path` is relative, has no `..` and does not pass through a symlink
+    /// resolving outside the root. Checked before creating folders, since creating them
+    /// would already follow such a symlink.
+    fn check_inside(&self, path: &Path) -> io::Result<()> {
+        if !path.components().all(|x| matches!(x, Component::Normal(_))) {
+            return Err(outside_root());
+        }
+        let root = self.root.canonicalize()?;
+        let mut current = self.root.clone();
+        for component in path.components() {
+            current.push(component);
+            match fs::symlink_metadata(&current) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    if !current.canonicalize().is_ok_and(|x| x.starts_with(&root)) {
+                        return Err(outside_root());
+                    }
+                }
+                Ok(_) => {}
+                // Nothing below a missing folder exists yet.
+                Err(_) => break,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Sets the modification time of a file written earlier.
+    pub fn set_modified(&self, path: &Path, time: SystemTime) -> io::Result<()> {
+        filetime::set_file_mtime(self.root.join(path), FileTime::from_system_time(time))
+    }
+}
+
+impl Sink for DirSink {
+    fn write(&mut self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        self.write_file(path, bytes)
+    }
+}
+
+impl DirSink {
+    /// Like [`Sink::write`], but through a shared reference, so several threads can
+    /// write different files at once.
+    pub fn write_file(&self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        self.check_inside(path)?;
+        let out_file = self.root.join(path);
+        if let Some(parent) = out_file.parent() {
+            if !parent.is_dir() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        write_atomic(&out_file, bytes, self.durable)
+    }
+}
+
+/// Keeps written files in memory, in the order they were first written, for callers
+/// without a file system such as the WebAssembly build.
+#[derive(Default)]
+pub struct MemorySink {
+    pub files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl Sink for MemorySink {
+    fn write(&mut self, path: &Path, bytes: &[u8]) -> io::Result<()> {
+        if !path.components().all(|x| matches!(x, Component::Normal(_))) {
+            return Err(outside_root());
+        }
+        match self.files.iter_mut().find(|(x, _)| x == path) {
+            Some((_, existing)) => *existing = bytes.to_vec(),
+            None => self.files.push((path.to_path_buf(), bytes.to_vec())),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::{Component, Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::har;
+
+#[derive(Deserialize)]
+struct RawSourceMap {
+    #[serde(rename = "sourceRoot", default)]
+    source_root: Option<String>,
+    #[serde(default)]
+    sources: Vec<Option<String>>,
+    #[serde(rename = "sourcesContent", default)]
+    sources_content: Vec<Option<String>>,
+}
+
+/// Turns a source path such as `webpack:///./src/App.js` into a relative path that
+/// stays inside the folder it is extracted to.
+fn source_path(root: Option<&str>, source: &str) -> Option<PathBuf> {
+    let source = match root {
+        Some(root) if !root.is_empty() => format!("{}/{source}", root.trim_end_matches('/')),
+        _ => source.to_string(),
+    };
+    // Drop `webpack://`, `file://` and similar prefixes and any query (`?abcd`).
+    let source = source.split_once("://").map_or(source.as_str(), |x| x.1);
+    let source = source.split(['?', '#']).next().unwrap_or_default();
+    let path = Path::new(source)
+        .components()
+        .filter_map(|x| match x {
+            Component::Normal(x) => Some(x),
+            _ => None,
+        })
+        .collect::<PathBuf>();
+    (path.components().next().is_some()).then_some(path)
+}
+
+/// Original sources embedded in a source map's `sourcesContent`, as relative paths and
+/// contents. Sources the map only references by name are left out.
+pub fn embedded_sources(map: &[u8]) -> Option<Vec<(PathBuf, String)>> {
+    let map = serde_json::from_slice::<RawSourceMap>(map).ok()?;
+    let sources = map
+        .sources
+        .iter()
+        .zip(map.sources_content)
+        .filter_map(|(source, content)| {
+            let path = source_path(map.source_root.as_deref(), source.as_deref()?)?;
+            Some((path, content?))
+        })
+        .collect();
+    Some(sources)
+}
+
+/// The `sourceMappingURL` of the last `//# sourceMappingURL=` or
+/// `/*# sourceMappingURL= */` comment of a script or stylesheet, if any. Inline maps
+/// make that comment as long as the map itself.
+pub fn mapping_url(code: &str) -> Option<&str> {
+    const KEY: &str = "sourceMappingURL=";
+    let mut end = code.len();
+    while let Some(position) = code[..end].rfind(KEY) {
+        let before = code[..position].trim_end_matches([' ', '\t']);
+        // `@` is the older form of the comment.
+        if ["//#", "//@", "/*#", "/*@"]
+            .iter()
+            .any(|x| before.ends_with(x))
+        {
+            let line = code[position + KEY.len()..].lines().next()?;
+            let url = line.split_whitespace().next()?;
+            return Some(url.strip_suffix("*/").unwrap_or(url));
+        }
+        end = position;
+    }
+    None
+}
+
+/// Decodes a `data:application/json;base64,...` source map URL.
+pub fn decode_inline(url: &str) -> Option<Vec<u8>> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    if header.ends_with(";base64") {
+        har::decode_base64(data).ok()
+    } else {
+        Some(data.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+
+    use super::*;
+
+    #[test]
+    fn finds_the_last_mapping_comment() {
+        assert_eq!(
+            mapping_url("a();\n//# sourceMappingURL=a.js.map\n"),
+            Some("a.js.map")
+        );
+        assert_eq!(
+            mapping_url("a{}/*# sourceMappingURL=a.css.map */"),
+            Some("a.css.map")
+        );
+        assert_eq!(
+            mapping_url("a{}/*# sourceMappingURL=a.css.map*/"),
+            Some("a.css.map")
+        );
+        assert_eq!(
+            mapping_url("a();//@ sourceMappingURL=old.map"),
+            Some("old.map")
+        );
+        let code = "//# sourceMappingURL=first.map\nb();\n//# sourceMappingURL=last.map";
+        assert_eq!(mapping_url(code), Some("last.map"));
+    }
+
+    #[test]
+    fn ignores_mentions_outside_mapping_comments() {
+        let code = "//# sourceMappingURL=a.map\nvar x = \"sourceMappingURL=b.map\";";
+        assert_eq!(mapping_url(code), Some("a.map"));
+        assert_eq!(mapping_url("var x = 'sourceMappingURL=b.map';"), None);
+        assert_eq!(mapping_url("//# sourceMappingURL=\nnext"), None);
+        assert_eq!(mapping_url(""), None);
+    }
+
+    #[test]
+    fn finds_inline_maps_of_any_size() {
+        let map = format!(
+            r#"{{"sources":["a.js"],"sourcesContent":["{}"]}}"#,
+            "x".repeat(8000)
+        );
+        let url = format!(
+            "data:application/json;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(&map)
+        );
+        let code = format!("a();\n//# sourceMappingURL={url}\n");
+        assert_eq!(mapping_url(&code), Some(url.as_str()));
+        let decoded = decode_inline(mapping_url(&code).unwrap()).unwrap();
+        let sources = embedded_sources(&decoded).unwrap();
+        assert_eq!(sources, [(PathBuf::from("a.js"), "x".repeat(8000))]);
+    }
+
+    #[test]
+    fn keeps_source_paths_inside_the_folder() {
+        let path = |root, source| source_path(root, source);
+        assert_eq!(
+            path(None, "webpack:///./src/App.js"),
+            Some(PathBuf::from("src/App.js"))
+        );
+        assert_eq!(
+            path(Some("/root/"), "../../a.js?x"),
+            Some(PathBuf::from("root/a.js"))
+        );
+        assert_eq!(path(None, "webpack:///"), None);
+    }
+}
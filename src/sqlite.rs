@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use extract_har::{
+    decompress,
+    har::{Har, HarCookie, HarHeader},
+};
+use rusqlite::{params, Connection, Transaction};
+
+const SCHEMA: &str = "
+CREATE TABLE entries (
+    id INTEGER PRIMARY KEY,
+    started TEXT NOT NULL,
+    pageref TEXT,
+    method TEXT NOT NULL,
+    url TEXT NOT NULL,
+    host TEXT,
+    status INTEGER NOT NULL,
+    mime_type TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    time REAL NOT NULL,
+    server_ip_address TEXT
+);
+CREATE TABLE headers (
+    entry_id INTEGER NOT NULL REFERENCES entries(id),
+    side TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE cookies (
+    entry_id INTEGER NOT NULL REFERENCES entries(id),
+    side TEXT NOT NULL,
+    name TEXT NOT NULL,
+    value TEXT NOT NULL,
+    domain TEXT,
+    path TEXT,
+    expires TEXT,
+    http_only INTEGER,
+    secure INTEGER
+);
+CREATE TABLE bodies (
+    entry_id INTEGER NOT NULL REFERENCES entries(id),
+    side TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    data BLOB NOT NULL
+);
+CREATE INDEX headers_entry ON headers(entry_id);
+CREATE INDEX cookies_entry ON cookies(entry_id);
+CREATE INDEX bodies_entry ON bodies(entry_id);
+";
+
+fn insert_headers(
+    transaction: &Transaction,
+    id: i64,
+    side: &str,
+    headers: &[HarHeader],
+) -> rusqlite::Result<()> {
+    let mut statement = transaction
+        .prepare_cached("INSERT INTO headers (entry_id, side, name, value) VALUES (?, ?, ?, ?)")?;
+    for header in headers {
+        statement.execute(params![id, side, header.name, header.value])?;
+    }
+    Ok(())
+}
+
+fn insert_cookies(
+    transaction: &Transaction,
+    id: i64,
+    side: &str,
+    cookies: &[HarCookie],
+) -> rusqlite::Result<()> {
+    let mut statement = transaction.prepare_cached(
+        "INSERT INTO cookies (entry_id, side, name, value, domain, path, expires, http_only, secure)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )?;
+    for cookie in cookies {
+        statement.execute(params![
+            id,
+            side,
+            cookie.name,
+            cookie.value,
+            cookie.domain,
+            cookie.path,
+            cookie.expires,
+            cookie.http_only,
+            cookie.secure
+        ])?;
+    }
+    Ok(())
+}
+
+/// Writes every entry of the capture into a new database at `path`. Response bodies
+/// are stored decoded and decompressed.
+pub fn write(har: &Har, path: &Path) -> rusqlite::Result<()> {
+    let mut connection = Connection::open(path)?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+    for (id, entry) in har.log.indexed_entries() {
+        let id = id as i64;
+        let request = &entry.request;
+        let response = &entry.response;
+        transaction
+            .prepare_cached(
+                "INSERT INTO entries (id, started, pageref, method, url, host, status, mime_type,
+                 size, time, server_ip_address) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )?
+            .execute(params![
+                id,
+                entry.started_date_time,
+                entry.pageref,
+                request.method,
+                request.url.as_str(),
+                request.url.host_str(),
+                response.status,
+                response.content.mime_type,
+                response.content.body_size() as i64,
+                entry.time,
+                entry.server_ip_address
+            ])?;
+        insert_headers(&transaction, id, "request", &request.headers)?;
+        insert_headers(&transaction, id, "response", &response.headers)?;
+        insert_cookies(&transaction, id, "request", &request.cookies)?;
+        insert_cookies(&transaction, id, "response", &response.cookies)?;
+        let mut insert_body = transaction.prepare_cached(
+            "INSERT INTO bodies (entry_id, side, mime_type, data) VALUES (?, ?, ?, ?)",
+        )?;
+        if let Some(post_data) = request.post_data.as_ref().filter(|x| !x.text.is_empty()) {
+            insert_body.execute(params![
+                id,
+                "request",
+                post_data.mime_type,
+                post_data.text.as_bytes()
+            ])?;
+        }
+        if let Some(mut body) = response.content.decoded().filter(|x| !x.is_empty()) {
+            if let Some(decompressed) =
+                decompress::decompress(response.header("content-encoding"), &body)
+            {
+                body = decompressed;
+            }
+            insert_body.execute(params![id, "response", response.content.mime_type, body])?;
+        }
+    }
+    transaction.commit()
+}
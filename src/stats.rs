@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use clap::Args;
+use extract_har::har::HarLogEntry;
+
+use crate::input;
+
+#[derive(Args)]
+pub struct StatsArgs {
+    input_har: String,
+    /// How many of the largest responses to list
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+    /// Also list the N slowest requests, with the time spent waiting for the server
+    #[arg(long, value_name = "N")]
+    slowest: Option<usize>,
+}
+
+#[derive(Default)]
+struct Bucket {
+    count: u64,
+    bytes: u64,
+    /// Bytes received over the network, where the exporter recorded it.
+    transferred: u64,
+    /// Milliseconds of all requests together.
+    time: f64,
+}
+
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// `_transferSize` of a response, or `-` for exporters other than Chrome.
+pub fn human_transfer(bytes: Option<i64>) -> String {
+    match bytes {
+        Some(bytes) if bytes >= 0 => human_size(bytes as u64),
+        _ => "-".to_string(),
+    }
+}
+
+/// Milliseconds as `850 ms` or `1.25 s`, or `-` when not recorded.
+pub fn human_duration(ms: Option<f64>) -> String {
+    match ms {
+        Some(ms) if ms >= 1000.0 => format!("{:.2} s", ms / 1000.0),
+        Some(ms) if ms >= 0.0 => format!("{ms:.0} ms"),
+        _ => "-".to_string(),
+    }
+}
+
+/// The `n` entries that took longest, with their index, slowest first.
+pub fn slowest<'a>(
+    entries: impl Iterator<Item = (usize, &'a HarLogEntry)>,
+    n: usize,
+) -> Vec<(usize, &'a HarLogEntry)> {
+    let mut slowest = entries.collect::<Vec<_>>();
+    slowest.sort_by(|a, b| b.1.time.total_cmp(&a.1.time).then(a.0.cmp(&b.0)));
+    slowest.truncate(n);
+    slowest
+}
+
+fn print_table(title: String, groups: HashMap<String, Bucket>) {
+    let mut rows = groups.into_iter().collect::<Vec<_>>();
+    rows.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then_with(|| a.0.cmp(&b.0)));
+    println!("{title}");
+    println!(
+        "{:>8}  {:>10}  {:>10}  {:>9}  {}",
+        tr!(ColumnCount),
+        tr!(ColumnBytes),
+        tr!(ColumnTransferred),
+        tr!(ColumnTime),
+        tr!(ColumnName)
+    );
+    for (name, bucket) in rows {
+        println!(
+            "{:>8}  {:>10}  {:>10}  {:>9}  {name}",
+            bucket.count,
+            human_size(bucket.bytes),
+            human_size(bucket.transferred),
+            human_duration(Some(bucket.time))
+        );
+    }
+    println!();
+}
+
+fn group_by(
+    entries: &[HarLogEntry],
+    key: impl Fn(&HarLogEntry) -> String,
+) -> HashMap<String, Bucket> {
+    let mut groups = HashMap::<String, Bucket>::new();
+    for entry in entries {
+        let bucket = groups.entry(key(entry)).or_default();
+        bucket.count += 1;
+        bucket.bytes += entry.response.content.body_size();
+        bucket.transferred += entry.response.transfer_size.unwrap_or_default().max(0) as u64;
+        bucket.time += entry.time.max(0.0);
+    }
+    groups
+}
+
+pub fn run(args: StatsArgs) {
+    let session = input::open_session(&args.input_har);
+    let entries = session.entries();
+
+    print_table(
+        tr!(StatsByDomain),
+        group_by(entries, |entry| {
+            entry.request.url.host_str().unwrap_or("-").to_string()
+        }),
+    );
+    print_table(
+        tr!(StatsByMimeType),
+        group_by(entries, |entry| {
+            entry.response.content.essence().to_string()
+        }),
+    );
+    print_table(
+        tr!(StatsByStatus),
+        group_by(entries, |entry| entry.response.status.to_string()),
+    );
+
+    let mut largest = entries.iter().collect::<Vec<_>>();
+    largest.sort_by_key(|entry| std::cmp::Reverse(entry.response.content.body_size()));
+    println!("{}", tr!(StatsLargest, args.top.min(largest.len())));
+    for entry in largest.iter().take(args.top) {
+        println!(
+            "{:>10}  {}",
+            human_size(entry.response.content.body_size()),
+            entry.request.url
+        );
+    }
+    println!();
+
+    if let Some(n) = args.slowest {
+        let slowest = slowest(session.indexed_entries(), n);
+        println!("{}", tr!(StatsSlowest, slowest.len()));
+        println!(
+            "{:>6}  {:>9}  {:>9}  {:>10}  URL",
+            "#",
+            tr!(ColumnTime),
+            tr!(ColumnWait),
+            tr!(ColumnBytes)
+        );
+        for (index, entry) in slowest {
+            println!(
+                "{index:>6}  {:>9}  {:>9}  {:>10}  {}",
+                human_duration(Some(entry.time)),
+                human_duration(entry.wait()),
+                human_size(entry.response.content.body_size()),
+                entry.request.url
+            );
+        }
+        println!();
+    }
+
+    let total_bytes = entries
+        .iter()
+        .map(|entry| entry.response.content.body_size())
+        .sum::<u64>();
+    println!(
+        "{}",
+        tr!(StatsTotal, entries.len(), human_size(total_bytes))
+    );
+}
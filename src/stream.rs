@@ -0,0 +1,84 @@
+//! Extraction for async services, such as one that accepts a HAR upload and streams
+//! the files back while the rest are still being decoded.
+//!
+//! ```no_run
+//! # async fn run(har: extract_har::har::Har) {
+//! use extract_har::{extraction, stream::ExtractorStream};
+//!
+//! let mime_types = extraction::default_mime_types();
+//! let mut stream = ExtractorStream::new(har, &mime_types);
+//! while let Some(entry) = stream.next().await {
+//!     match entry {
+//!         Ok(entry) => println!("{} {} bytes", entry.file_name, entry.bytes.len()),
+//!         Err((index, message)) => eprintln!("entry {index}: {message}"),
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+use crate::{
+    extraction::{self, ExtractedEntry},
+    har::Har,
+};
+
+/// How many decoded entries wait for the consumer before decoding pauses, so a slow
+/// client holds back the decoding rather than the memory filling up.
+const BUFFERED_ENTRIES: usize = 8;
+
+/// The entries [`extraction::decode_entry`] gives for a capture, decoded on Tokio's
+/// blocking pool in the order of the capture, with the index and reason of those that
+/// fail. Dropping the stream stops the decoding.
+pub struct ExtractorStream {
+    receiver: mpsc::Receiver<Result<ExtractedEntry, (usize, String)>>,
+}
+
+impl ExtractorStream {
+    /// Starts decoding `har`; must be called from within a Tokio runtime.
+    pub fn new(har: Har, mime_types: &HashMap<&str, &str>) -> ExtractorStream {
+        let mime_types = mime_types
+            .iter()
+            .map(|(x, y)| (x.to_string(), y.to_string()))
+            .collect::<HashMap<_, _>>();
+        let (sender, receiver) = mpsc::channel(BUFFERED_ENTRIES);
+        tokio::task::spawn_blocking(move || {
+            let mime_types = mime_types
+                .iter()
+                .map(|(x, y)| (x.as_str(), y.as_str()))
+                .collect();
+            for (index, entry) in har.log.indexed_entries() {
+                let Some(decoded) = extraction::decode_entry(index, entry, &mime_types) else {
+                    continue;
+                };
+                if sender
+                    .blocking_send(decoded.map_err(|x| (index, x)))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+        ExtractorStream { receiver }
+    }
+
+    /// The next entry, or `None` once every entry was given.
+    pub async fn next(&mut self) -> Option<Result<ExtractedEntry, (usize, String)>> {
+        self.receiver.recv().await
+    }
+}
+
+impl Stream for ExtractorStream {
+    type Item = Result<ExtractedEntry, (usize, String)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
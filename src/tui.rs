@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+
+use extract_har::har::HarLogEntry;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Row, Table, TableState},
+    DefaultTerminal, Frame,
+};
+
+use crate::stats::human_size;
+
+/// Whether every character of `filter` appears in `text` in order.
+fn fuzzy_match(filter: &str, text: &str) -> bool {
+    let mut text = text.chars();
+    filter.chars().all(|x| text.any(|y| y == x))
+}
+
+struct Picker<'a> {
+    entries: &'a [(usize, HarLogEntry)],
+    filter: String,
+    /// Positions in `entries` matching the filter.
+    visible: Vec<usize>,
+    /// HAR indices of the chosen entries.
+    chosen: HashSet<usize>,
+    state: TableState,
+}
+
+impl Picker<'_> {
+    fn refilter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        self.visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, entry))| {
+                let text = format!("{} {}", entry.response.content.essence(), entry.request.url);
+                fuzzy_match(&filter, &text.to_lowercase())
+            })
+            .map(|(position, _)| position)
+            .collect();
+        self.state.select((!self.visible.is_empty()).then_some(0));
+    }
+
+    fn current(&self) -> Option<usize> {
+        let position = self.visible.get(self.state.selected()?)?;
+        Some(self.entries[*position].0)
+    }
+
+    fn toggle(&mut self, index: usize) {
+        if !self.chosen.remove(&index) {
+            self.chosen.insert(index);
+        }
+    }
+
+    fn toggle_visible(&mut self) {
+        let indices = self
+            .visible
+            .iter()
+            .map(|x| self.entries[*x].0)
+            .collect::<Vec<_>>();
+        if indices.iter().all(|x| self.chosen.contains(x)) {
+            indices.iter().for_each(|x| {
+                self.chosen.remove(x);
+            });
+        } else {
+            self.chosen.extend(indices);
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [filter_area, table_area, help_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        frame.render_widget(
+            Line::from(format!("{} {}", tr!(TuiFilter), self.filter)),
+            filter_area,
+        );
+        let rows = self.visible.iter().map(|position| {
+            let (index, entry) = &self.entries[*position];
+            Row::new([
+                if self.chosen.contains(index) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                }
+                .to_string(),
+                index.to_string(),
+                entry.response.status.to_string(),
+                entry.response.content.essence().to_string(),
+                human_size(entry.response.content.body_size()),
+                entry.request.url.to_string(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Length(6),
+                Constraint::Length(3),
+                Constraint::Length(24),
+                Constraint::Length(10),
+                Constraint::Fill(1),
+            ],
+        )
+        .block(Block::bordered().title(tr!(TuiTitle, self.chosen.len(), self.entries.len())))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, table_area, &mut self.state);
+        frame.render_widget(Line::from(tr!(TuiHelp)), help_area);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<bool> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Esc => return Ok(false),
+                KeyCode::Enter => return Ok(true),
+                KeyCode::Up => self.state.select_previous(),
+                KeyCode::Down => self.state.select_next(),
+                KeyCode::PageUp => self.state.scroll_up_by(20),
+                KeyCode::PageDown => self.state.scroll_down_by(20),
+                KeyCode::Char(' ') => {
+                    if let Some(index) = self.current() {
+                        self.toggle(index);
+                        self.state.select_next();
+                    }
+                }
+                KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.toggle_visible()
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(false)
+                }
+                KeyCode::Char(x) => {
+                    self.filter.push(x);
+                    self.refilter();
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.refilter();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Lets the user pick entries in a terminal UI, returning the HAR indices of the
+/// chosen ones, or `None` when the selection was cancelled.
+pub fn select(entries: &[(usize, HarLogEntry)]) -> Option<HashSet<usize>> {
+    let mut picker = Picker {
+        entries,
+        filter: String::new(),
+        visible: Vec::new(),
+        chosen: HashSet::new(),
+        state: TableState::default(),
+    };
+    picker.refilter();
+    let mut terminal = ratatui::init();
+    let result = picker.run(&mut terminal);
+    ratatui::restore();
+    match result {
+        Ok(true) => Some(picker.chosen),
+        _ => None,
+    }
+}
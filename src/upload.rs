@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+};
+
+use chrono::Utc;
+use extract_har::manifest::sha256_hex;
+use sha2::{Digest, Sha256};
+use ureq::Agent;
+use url::Url;
+
+use crate::{bundle::Failure, extract::percent_decode};
+
+/// Where extracted files are uploaded: an S3 bucket, or a bucket of an S3-compatible
+/// service addressed as `https://host/bucket/prefix`.
+pub struct Target {
+    /// URL that object keys are joined to, ending with `/`.
+    base: Url,
+    /// Start of every object key, without slashes around it.
+    prefix: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl Target {
+    /// Parses `s3://bucket/prefix` or `http(s)://host/bucket/prefix`, taking credentials
+    /// and region from the usual `AWS_*` variables. `AWS_ENDPOINT_URL` sends `s3://`
+    /// targets to an S3-compatible service.
+    pub fn parse(target: &str) -> Result<Target, String> {
+        let url = Url::parse(target).map_err(|err| err.to_string())?;
+        let region = env::var("AWS_REGION")
+            .or_else(|_| env::var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|_| "us-east-1".to_string());
+        let decoded = |path: &str| String::from_utf8_lossy(&percent_decode(path)).into_owned();
+        let (base, prefix) = match url.scheme() {
+            "s3" => {
+                let bucket = url.host_str().ok_or("missing bucket")?;
+                let key_prefix = decoded(url.path().trim_matches('/'));
+                match env::var("AWS_ENDPOINT_URL") {
+                    Ok(endpoint) => {
+                        let endpoint = endpoint.trim_end_matches('/');
+                        let base = Url::parse(&format!("{endpoint}/{bucket}/"))
+                            .map_err(|err| err.to_string())?;
+                        (base, key_prefix)
+                    }
+                    // Bucket names with dots do not match the wildcard certificate of
+                    // virtual-hosted addresses.
+                    Err(_) if bucket.contains('.') => {
+                        let base = format!("https://s3.{region}.amazonaws.com/{bucket}/");
+                        (Url::parse(&base).unwrap(), key_prefix)
+                    }
+                    Err(_) => {
+                        let base = format!("https://{bucket}.s3.{region}.amazonaws.com/");
+                        let base = Url::parse(&base).map_err(|err| err.to_string())?;
+                        (base, key_prefix)
+                    }
+                }
+            }
+            "http" | "https" => {
+                let mut segments = url.path().trim_matches('/').splitn(2, '/');
+                let bucket = segments.next().filter(|x| !x.is_empty());
+                let bucket = bucket.ok_or("missing bucket")?;
+                let mut base = url.clone();
+                base.set_path(&format!("/{bucket}/"));
+                base.set_query(None);
+                (base, decoded(segments.next().unwrap_or_default()))
+            }
+            scheme => return Err(format!("unsupported scheme `{scheme}`")),
+        };
+        let access_key =
+            env::var("AWS_ACCESS_KEY_ID").map_err(|_| "AWS_ACCESS_KEY_ID is not set")?;
+        let secret_key =
+            env::var("AWS_SECRET_ACCESS_KEY").map_err(|_| "AWS_SECRET_ACCESS_KEY is not set")?;
+        Ok(Target {
+            base,
+            prefix,
+            region,
+            access_key,
+            secret_key,
+            session_token: env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+
+    fn key(&self, path: &Path) -> String {
+        let path = path.to_string_lossy().replace('\\', "/");
+        if self.prefix.is_empty() {
+            path
+        } else {
+            format!("{}/{path}", self.prefix)
+        }
+    }
+}
+
+fn hmac(key: &[u8], message: &[u8]) -> Vec<u8> {
+    const BLOCK: usize = 64;
+    let mut key = if key.len() > BLOCK {
+        Sha256::digest(key).to_vec()
+    } else {
+        key.to_vec()
+    };
+    key.resize(BLOCK, 0);
+    let pad = |byte: u8| key.iter().map(|x| x ^ byte).collect::<Vec<_>>();
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize()
+        .to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|x| format!("{x:02x}")).collect()
+}
+
+/// Percent-encodes a key as Signature Version 4 expects, keeping `/`.
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|x| match x {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (x as char).to_string()
+            }
+            x => format!("%{x:02X}"),
+        })
+        .collect()
+}
+
+/// Uploads one object with a Signature Version 4 `PUT`.
+fn put(
+    agent: &Agent,
+    target: &Target,
+    key: &str,
+    bytes: &[u8],
+    content_type: &str,
+) -> Result<(), String> {
+    let url = target
+        .base
+        .join(&encode_key(key))
+        .map_err(|err| err.to_string())?;
+    let host = match url.port() {
+        Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+    let now = Utc::now();
+    let date = now.format("%Y%m%d").to_string();
+    let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let payload_hash = sha256_hex(bytes);
+    let mut headers = vec![
+        ("content-type", content_type.to_string()),
+        ("host", host),
+        ("x-amz-content-sha256", payload_hash.clone()),
+        ("x-amz-date", timestamp.clone()),
+    ];
+    if let Some(token) = &target.session_token {
+        headers.push(("x-amz-security-token", token.clone()));
+    }
+    let signed_headers = headers
+        .iter()
+        .map(|(x, _)| *x)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_headers = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect::<String>();
+    let canonical_request = format!(
+        "PUT\n{}\n\n{canonical_headers}\n{signed_headers}\n{payload_hash}",
+        url.path()
+    );
+    let scope = format!("{date}/{}/s3/aws4_request", target.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{timestamp}\n{scope}\n{}",
+        sha256_hex(canonical_request.as_bytes())
+    );
+    let signing_key = [target.region.as_str(), "s3", "aws4_request"].iter().fold(
+        hmac(
+            format!("AWS4{}", target.secret_key).as_bytes(),
+            date.as_bytes(),
+        ),
+        |key, part| hmac(&key, part.as_bytes()),
+    );
+    let signature = hex(&hmac(&signing_key, string_to_sign.as_bytes()));
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        target.access_key
+    );
+    let mut call = agent
+        .put(url.as_str())
+        .header("Authorization", &authorization);
+    for (name, value) in headers.iter().filter(|(x, _)| *x != "host") {
+        call = call.header(*name, value);
+    }
+    call.send(bytes).map(|_| ()).map_err(|err| err.to_string())
+}
+
+/// Files below `folder`, as paths relative to it, leaving out the manifest and other
+/// dot files, which only matter to later runs into the same folder.
+fn files(folder: &Path, relative: &Path, result: &mut Vec<PathBuf>) -> io::Result<()> {
+    for item in fs::read_dir(folder.join(relative))? {
+        let item = item?;
+        if item.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = relative.join(item.file_name());
+        if item.file_type()?.is_dir() {
+            files(folder, &path, result)?;
+        } else {
+            result.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// MIME type of an uploaded file, from the types extracted under its extension.
+fn content_type<'a>(path: &Path, types: &'a HashMap<String, String>) -> &'a str {
+    let ext = path
+        .extension()
+        .map(|x| format!(".{}", x.to_string_lossy().to_lowercase()))
+        .unwrap_or_default();
+    if let Some(mime_type) = types.get(&ext) {
+        return mime_type;
+    }
+    match ext.as_str() {
+        ".json" => "application/json",
+        ".ndjson" => "application/x-ndjson",
+        ".txt" => "text/plain; charset=utf-8",
+        ".html" => "text/html; charset=utf-8",
+        ".css" => "text/css",
+        ".js" => "text/javascript",
+        ".graphql" => "application/graphql",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Uploads every file written into `folder`, `threads` at a time, returning those that
+/// could not be uploaded. `types` maps extensions to the MIME type they were saved for.
+pub fn upload_folder(
+    target: &Target,
+    folder: &Path,
+    types: &HashMap<String, String>,
+    threads: usize,
+) -> (usize, Vec<Failure>) {
+    let mut paths = Vec::new();
+    if let Err(err) = files(folder, Path::new(""), &mut paths) {
+        let message = format!("{}: {err}", folder.to_string_lossy());
+        return (
+            0,
+            vec![Failure {
+                index: None,
+                message,
+            }],
+        );
+    }
+    let count = paths.len();
+    eprintln!("{}", tr!(Uploading, count, target.base));
+    let agent: Agent = Agent::config_builder().build().into();
+    let queue = Mutex::new(paths.into_iter());
+    let failures = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| loop {
+                let Some(path) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                let key = target.key(&path);
+                let result = fs::read(folder.join(&path))
+                    .map_err(|err| err.to_string())
+                    .and_then(|bytes| {
+                        put(&agent, target, &key, &bytes, content_type(&path, types))
+                    });
+                if let Err(err) = result {
+                    let message = format!("{key}: {err}");
+                    failures.lock().unwrap().push(Failure {
+                        index: None,
+                        message,
+                    });
+                }
+            });
+        }
+    });
+    let failures = failures.into_inner().unwrap();
+    (count - failures.len(), failures)
+}
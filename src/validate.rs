@@ -0,0 +1,588 @@
+use std::fs;
+
+use base64::Engine;
+use chrono::DateTime;
+use clap::Args;
+use extract_har::import;
+use serde::Serialize;
+use serde_json::Value;
+use url::Url;
+
+use crate::{exit_code, input};
+
+const BYTE_ORDER_MARK: &[u8] = b"\xEF\xBB\xBF";
+
+#[derive(Args)]
+pub struct ValidateArgs {
+    input_har: String,
+    /// Print the problems as a JSON document
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Severity {
+    /// The document does not follow HAR 1.2.
+    Error,
+    /// Allowed by the format, but likely to trip up tools reading it.
+    Warning,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Issue {
+    Missing,
+    WrongType { expected: &'static str },
+    OutOfRange { value: Value },
+    InvalidDate,
+    InvalidUrl,
+    UnknownEncoding { encoding: String },
+    InvalidBase64,
+    UnknownPage { pageref: String },
+    ByteOrderMark,
+    TrailingData { bytes: usize },
+}
+
+impl Issue {
+    fn message(&self) -> String {
+        match self {
+            Issue::Missing => tr!(ValidateMissing),
+            Issue::WrongType { expected } => tr!(ValidateWrongType, expected),
+            Issue::OutOfRange { value } => tr!(ValidateOutOfRange, value),
+            Issue::InvalidDate => tr!(ValidateInvalidDate),
+            Issue::InvalidUrl => tr!(ValidateInvalidUrl),
+            Issue::UnknownEncoding { encoding } => tr!(ValidateUnknownEncoding, encoding),
+            Issue::InvalidBase64 => tr!(ValidateInvalidBase64),
+            Issue::UnknownPage { pageref } => tr!(ValidateUnknownPage, pageref),
+            Issue::ByteOrderMark => tr!(ValidateByteOrderMark),
+            Issue::TrailingData { bytes } => tr!(ValidateTrailingData, bytes),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Problem {
+    /// JSON pointer to the offending value, such as `/log/entries/3/response/status`.
+    path: String,
+    severity: Severity,
+    #[serde(flatten)]
+    issue: Issue,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    String,
+    Number,
+    Integer,
+    Object,
+    Array,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::String => "string",
+            Kind::Number => "number",
+            Kind::Integer => "integer",
+            Kind::Object => "object",
+            Kind::Array => "array",
+        }
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Kind::String => value.is_string(),
+            Kind::Number => value.is_number(),
+            Kind::Integer => value.is_i64() || value.is_u64(),
+            Kind::Object => value.is_object(),
+            Kind::Array => value.is_array(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Validator {
+    problems: Vec<Problem>,
+    page_ids: Vec<String>,
+}
+
+impl Validator {
+    fn report(&mut self, path: String, severity: Severity, issue: Issue) {
+        self.problems.push(Problem {
+            path,
+            severity,
+            issue,
+        });
+    }
+
+    /// Checks that `object[name]` has the given kind, reporting it missing only when
+    /// the format requires it, and returns it when it does.
+    fn field<'a>(
+        &mut self,
+        object: &'a Value,
+        path: &str,
+        name: &str,
+        kind: Kind,
+        required: bool,
+    ) -> Option<&'a Value> {
+        let path = format!("{path}/{name}");
+        match object.get(name) {
+            None if required => self.report(path, Severity::Error, Issue::Missing),
+            None => {}
+            Some(value) if kind.matches(value) => return Some(value),
+            Some(_) => {
+                let expected = kind.name();
+                self.report(path, Severity::Error, Issue::WrongType { expected });
+            }
+        }
+        None
+    }
+
+    /// A size or duration where `-1` means unknown.
+    fn optional_size(&mut self, object: &Value, path: &str, name: &str, required: bool) {
+        if let Some(value) = self.field(object, path, name, Kind::Number, required) {
+            if value.as_f64().is_some_and(|x| x < 0.0 && x != -1.0) {
+                let path = format!("{path}/{name}");
+                let value = value.clone();
+                self.report(path, Severity::Warning, Issue::OutOfRange { value });
+            }
+        }
+    }
+
+    fn date(&mut self, object: &Value, path: &str, name: &str) {
+        if let Some(value) = self.field(object, path, name, Kind::String, true) {
+            if DateTime::parse_from_rfc3339(value.as_str().unwrap_or_default()).is_err() {
+                self.report(
+                    format!("{path}/{name}"),
+                    Severity::Error,
+                    Issue::InvalidDate,
+                );
+            }
+        }
+    }
+
+    /// Headers, cookies and query parameters, which all need a name and a value.
+    fn name_values(&mut self, object: &Value, path: &str, name: &str) {
+        let Some(items) = self.field(object, path, name, Kind::Array, true) else {
+            return;
+        };
+        for (index, item) in items.as_array().into_iter().flatten().enumerate() {
+            let path = format!("{path}/{name}/{index}");
+            if !item.is_object() {
+                let expected = Kind::Object.name();
+                self.report(path, Severity::Error, Issue::WrongType { expected });
+                continue;
+            }
+            self.field(item, &path, "name", Kind::String, true);
+            self.field(item, &path, "value", Kind::String, true);
+        }
+    }
+
+    fn log(&mut self, document: &Value) {
+        let Some(log) = self.field(document, "", "log", Kind::Object, true) else {
+            return;
+        };
+        let path = "/log";
+        self.field(log, path, "version", Kind::String, true);
+        if let Some(creator) = self.field(log, path, "creator", Kind::Object, true) {
+            self.field(creator, "/log/creator", "name", Kind::String, true);
+            self.field(creator, "/log/creator", "version", Kind::String, true);
+        }
+        if let Some(pages) = self.field(log, path, "pages", Kind::Array, false) {
+            for (index, page) in pages.as_array().into_iter().flatten().enumerate() {
+                self.page(page, &format!("/log/pages/{index}"));
+            }
+        }
+        if let Some(entries) = self.field(log, path, "entries", Kind::Array, true) {
+            for (index, entry) in entries.as_array().into_iter().flatten().enumerate() {
+                self.entry(entry, &format!("/log/entries/{index}"));
+            }
+        }
+    }
+
+    fn page(&mut self, page: &Value, path: &str) {
+        self.date(page, path, "startedDateTime");
+        if let Some(id) = self.field(page, path, "id", Kind::String, true) {
+            self.page_ids
+                .push(id.as_str().unwrap_or_default().to_string());
+        }
+        self.field(page, path, "title", Kind::String, true);
+        self.field(page, path, "pageTimings", Kind::Object, true);
+    }
+
+    fn entry(&mut self, entry: &Value, path: &str) {
+        if let Some(pageref) = self.field(entry, path, "pageref", Kind::String, false) {
+            let pageref = pageref.as_str().unwrap_or_default();
+            if !self.page_ids.iter().any(|x| x == pageref) {
+                let pageref = pageref.to_string();
+                let path = format!("{path}/pageref");
+                self.report(path, Severity::Warning, Issue::UnknownPage { pageref });
+            }
+        }
+        self.date(entry, path, "startedDateTime");
+        if let Some(time) = self.field(entry, path, "time", Kind::Number, true) {
+            if time.as_f64().is_some_and(|x| x < 0.0) {
+                let value = time.clone();
+                let path = format!("{path}/time");
+                self.report(path, Severity::Warning, Issue::OutOfRange { value });
+            }
+        }
+        if let Some(request) = self.field(entry, path, "request", Kind::Object, true) {
+            self.request(request, &format!("{path}/request"));
+        }
+        if let Some(response) = self.field(entry, path, "response", Kind::Object, true) {
+            self.response(response, &format!("{path}/response"));
+        }
+        self.field(entry, path, "cache", Kind::Object, true);
+        if let Some(timings) = self.field(entry, path, "timings", Kind::Object, true) {
+            let path = format!("{path}/timings");
+            for name in ["blocked", "dns", "connect", "ssl"] {
+                self.optional_size(timings, &path, name, false);
+            }
+            // Unlike the other phases, these cannot be left unknown.
+            for name in ["send", "wait", "receive"] {
+                if let Some(value) = self.field(timings, &path, name, Kind::Number, true) {
+                    if value.as_f64().is_some_and(|x| x < 0.0) {
+                        let value = value.clone();
+                        let path = format!("{path}/{name}");
+                        self.report(path, Severity::Warning, Issue::OutOfRange { value });
+                    }
+                }
+            }
+        }
+        self.field(entry, path, "serverIPAddress", Kind::String, false);
+        self.field(entry, path, "connection", Kind::String, false);
+    }
+
+    fn request(&mut self, request: &Value, path: &str) {
+        self.field(request, path, "method", Kind::String, true);
+        if let Some(url) = self.field(request, path, "url", Kind::String, true) {
+            if Url::parse(url.as_str().unwrap_or_default()).is_err() {
+                self.report(format!("{path}/url"), Severity::Error, Issue::InvalidUrl);
+            }
+        }
+        self.field(request, path, "httpVersion", Kind::String, true);
+        self.name_values(request, path, "cookies");
+        self.name_values(request, path, "headers");
+        self.name_values(request, path, "queryString");
+        if let Some(post_data) = self.field(request, path, "postData", Kind::Object, false) {
+            let path = format!("{path}/postData");
+            self.field(post_data, &path, "mimeType", Kind::String, true);
+            self.field(post_data, &path, "text", Kind::String, false);
+            self.field(post_data, &path, "params", Kind::Array, false);
+        }
+        self.optional_size(request, path, "headersSize", true);
+        self.optional_size(request, path, "bodySize", true);
+    }
+
+    fn response(&mut self, response: &Value, path: &str) {
+        if let Some(status) = self.field(response, path, "status", Kind::Integer, true) {
+            // Browsers record 0 for requests that never got a response.
+            if status
+                .as_i64()
+                .is_some_and(|x| x != 0 && !(100..=599).contains(&x))
+            {
+                let value = status.clone();
+                let path = format!("{path}/status");
+                self.report(path, Severity::Warning, Issue::OutOfRange { value });
+            }
+        }
+        self.field(response, path, "statusText", Kind::String, true);
+        self.field(response, path, "httpVersion", Kind::String, true);
+        self.name_values(response, path, "cookies");
+        self.name_values(response, path, "headers");
+        if let Some(content) = self.field(response, path, "content", Kind::Object, true) {
+            self.content(content, &format!("{path}/content"));
+        }
+        self.field(response, path, "redirectURL", Kind::String, true);
+        self.optional_size(response, path, "headersSize", true);
+        self.optional_size(response, path, "bodySize", true);
+    }
+
+    fn content(&mut self, content: &Value, path: &str) {
+        if let Some(size) = self.field(content, path, "size", Kind::Number, true) {
+            if size.as_f64().is_some_and(|x| x < 0.0) {
+                let value = size.clone();
+                let path = format!("{path}/size");
+                self.report(path, Severity::Warning, Issue::OutOfRange { value });
+            }
+        }
+        self.field(content, path, "compression", Kind::Number, false);
+        self.field(content, path, "mimeType", Kind::String, true);
+        let text = self.field(content, path, "text", Kind::String, false);
+        let Some(encoding) = self.field(content, path, "encoding", Kind::String, false) else {
+            return;
+        };
+        let encoding = encoding.as_str().unwrap_or_default();
+        if encoding != "base64" {
+            let encoding = encoding.to_string();
+            let path = format!("{path}/encoding");
+            self.report(path, Severity::Warning, Issue::UnknownEncoding { encoding });
+        } else if let Some(text) = text.and_then(Value::as_str) {
+            if Engine::decode(&base64::engine::general_purpose::STANDARD, text).is_err() {
+                self.report(
+                    format!("{path}/text"),
+                    Severity::Error,
+                    Issue::InvalidBase64,
+                );
+            }
+        }
+    }
+}
+
+/// Reads the capture as JSON allows and no further, reporting the byte order mark and
+/// data after the document that other commands skip, whatever `--strict-har` says.
+/// Captures in other formats are checked as converted.
+fn read_document(validator: &mut Validator, input_har: &str) -> Value {
+    let input_file_path = input::resolve_input(input_har);
+    if import::read(&input_file_path).is_some() {
+        return input::load_document(input_har);
+    }
+    let data = fs::read(&input_file_path)
+        .unwrap_or_else(|_| pexit!(status = exit_code::PARSE_ERROR; CannotParseJson, input_har));
+    parse_document(validator, &data)
+        .unwrap_or_else(|| pexit!(status = exit_code::PARSE_ERROR; CannotParseJson, input_har))
+}
+
+/// Parses the first JSON value of `data`, reporting a byte order mark before it and
+/// anything but whitespace after it.
+fn parse_document(validator: &mut Validator, data: &[u8]) -> Option<Value> {
+    let data = match data.strip_prefix(BYTE_ORDER_MARK) {
+        Some(rest) => {
+            validator.report(String::new(), Severity::Error, Issue::ByteOrderMark);
+            rest
+        }
+        None => data,
+    };
+    let mut values = serde_json::Deserializer::from_slice(data).into_iter::<Value>();
+    let document = values.next()?.ok()?;
+    let rest = &data[values.byte_offset()..];
+    if !rest.trim_ascii().is_empty() {
+        let issue = Issue::TrailingData {
+            bytes: rest.trim_ascii().len(),
+        };
+        validator.report(String::new(), Severity::Error, issue);
+    }
+    Some(document)
+}
+
+pub fn run(args: ValidateArgs) {
+    let mut validator = Validator::default();
+    let document = read_document(&mut validator, &args.input_har);
+    validator.log(&document);
+    let problems = validator.problems;
+    let count = |severity| problems.iter().filter(|x| x.severity == severity).count();
+    let errors = count(Severity::Error);
+    let warnings = count(Severity::Warning);
+    if args.json {
+        let document = serde_json::json!({
+            "problems": problems,
+            "summary": { "errors": errors, "warnings": warnings },
+        });
+        println!("{}", serde_json::to_string_pretty(&document).unwrap());
+    } else {
+        for problem in &problems {
+            let line = match problem.severity {
+                // The document as a whole has an empty JSON pointer.
+                Severity::Error if problem.path.is_empty() => {
+                    tr!(ValidateDocumentError, problem.issue.message())
+                }
+                Severity::Error => tr!(ValidateError, problem.path, problem.issue.message()),
+                Severity::Warning => tr!(ValidateWarning, problem.path, problem.issue.message()),
+            };
+            println!("{line}");
+        }
+        let entries = document["log"]["entries"].as_array().map_or(0, Vec::len);
+        println!("{}", tr!(ValidateSummary, errors, warnings, entries));
+    }
+    if errors > 0 {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn entry() -> Value {
+        json!({
+            "startedDateTime": "2024-01-01T00:00:00.000Z",
+            "time": 10,
+            "request": {
+                "method": "GET",
+                "url": "https://a.com/",
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": [{ "name": "Host", "value": "a.com" }],
+                "queryString": [],
+                "headersSize": -1,
+                "bodySize": -1
+            },
+            "response": {
+                "status": 200,
+                "statusText": "OK",
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": [],
+                "content": { "size": 3, "mimeType": "text/plain", "text": "abc" },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": 3
+            },
+            "cache": {},
+            "timings": { "blocked": -1, "dns": -1, "connect": -1, "ssl": -1, "send": 0, "wait": 5, "receive": 5 }
+        })
+    }
+
+    fn document(entries: Vec<Value>) -> Value {
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": { "name": "test", "version": "1" },
+                "pages": [{
+                    "startedDateTime": "2024-01-01T00:00:00.000Z",
+                    "id": "page_1",
+                    "title": "A",
+                    "pageTimings": {}
+                }],
+                "entries": entries
+            }
+        })
+    }
+
+    /// The problems as `<severity> <path> <kind>` lines.
+    fn problems(validator: Validator) -> Vec<String> {
+        validator
+            .problems
+            .iter()
+            .map(|x| {
+                let x = serde_json::to_value(x).unwrap();
+                format!(
+                    "{} {} {}",
+                    x["severity"].as_str().unwrap(),
+                    x["path"].as_str().unwrap(),
+                    x["kind"].as_str().unwrap()
+                )
+            })
+            .collect()
+    }
+
+    fn check(document: &Value) -> Vec<String> {
+        let mut validator = Validator::default();
+        validator.log(document);
+        problems(validator)
+    }
+
+    #[test]
+    fn accepts_unknown_sizes_and_timings() {
+        assert!(check(&document(vec![entry()])).is_empty());
+    }
+
+    #[test]
+    fn warns_about_negative_sizes_other_than_unknown() {
+        let mut entry = entry();
+        entry["request"]["bodySize"] = json!(-2);
+        entry["timings"]["dns"] = json!(-5);
+        entry["timings"]["wait"] = json!(-1);
+        entry["response"]["content"]["size"] = json!(-1);
+        assert_eq!(
+            check(&document(vec![entry])),
+            [
+                "warning /log/entries/0/request/bodySize out_of_range",
+                "warning /log/entries/0/response/content/size out_of_range",
+                "warning /log/entries/0/timings/dns out_of_range",
+                "warning /log/entries/0/timings/wait out_of_range",
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_status_0_of_requests_without_a_response() {
+        let mut aborted = entry();
+        aborted["response"]["status"] = json!(0);
+        let mut invalid = entry();
+        invalid["response"]["status"] = json!(600);
+        let mut fractional = entry();
+        fractional["response"]["status"] = json!(200.5);
+        assert_eq!(
+            check(&document(vec![aborted, invalid, fractional])),
+            [
+                "warning /log/entries/1/response/status out_of_range",
+                "error /log/entries/2/response/status wrong_type",
+            ]
+        );
+    }
+
+    #[test]
+    fn warns_about_entries_of_unknown_pages() {
+        let mut known = entry();
+        known["pageref"] = json!("page_1");
+        let mut unknown = entry();
+        unknown["pageref"] = json!("page_2");
+        assert_eq!(
+            check(&document(vec![known, unknown])),
+            ["warning /log/entries/1/pageref unknown_page"]
+        );
+    }
+
+    #[test]
+    fn checks_base64_bodies() {
+        let mut valid = entry();
+        valid["response"]["content"]["text"] = json!("YWJj");
+        valid["response"]["content"]["encoding"] = json!("base64");
+        let mut invalid = entry();
+        invalid["response"]["content"]["text"] = json!("not base64!");
+        invalid["response"]["content"]["encoding"] = json!("base64");
+        let mut unknown = entry();
+        unknown["response"]["content"]["encoding"] = json!("quoted-printable");
+        assert_eq!(
+            check(&document(vec![valid, invalid, unknown])),
+            [
+                "error /log/entries/1/response/content/text invalid_base64",
+                "warning /log/entries/2/response/content/encoding unknown_encoding",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_missing_fields_and_wrong_types() {
+        let mut entry = entry();
+        entry["request"].as_object_mut().unwrap().remove("method");
+        entry["request"]["url"] = json!("not a url");
+        entry["response"]["headers"] = json!([{ "name": "A" }, "B"]);
+        assert_eq!(
+            check(&document(vec![entry])),
+            [
+                "error /log/entries/0/request/method missing",
+                "error /log/entries/0/request/url invalid_url",
+                "error /log/entries/0/response/headers/0/value missing",
+                "error /log/entries/0/response/headers/1 wrong_type",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_a_byte_order_mark() {
+        let mut validator = Validator::default();
+        let data = [BYTE_ORDER_MARK, b"{\"log\":{}}"].concat();
+        let document = parse_document(&mut validator, &data).unwrap();
+        assert_eq!(document, json!({ "log": {} }));
+        assert_eq!(problems(validator), ["error  byte_order_mark"]);
+    }
+
+    #[test]
+    fn reports_data_after_the_document() {
+        let mut validator = Validator::default();
+        let document = parse_document(&mut validator, b"{\"log\":{}}\n").unwrap();
+        assert_eq!(document, json!({ "log": {} }));
+        assert!(validator.problems.is_empty());
+        parse_document(&mut validator, b"{\"log\":{}} \n{\"log\":{}}\n").unwrap();
+        let issue = serde_json::to_value(&validator.problems[0]).unwrap();
+        assert_eq!(issue["kind"], "trailing_data");
+        assert_eq!(issue["bytes"], 10);
+        assert!(parse_document(&mut Validator::default(), b"{\"log\":").is_none());
+    }
+}
@@ -0,0 +1,35 @@
+use std::{fs, path::PathBuf};
+
+use clap::Args;
+use extract_har::manifest::{self, sha256_hex};
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Output folder of an extraction run with `--checksums`
+    dir: PathBuf,
+}
+
+pub fn run(args: VerifyArgs) {
+    let list = args.dir.join(manifest::CHECKSUMS_FILE_NAME);
+    let Ok(text) = fs::read_to_string(list) else {
+        pexit!(CannotOpenFile);
+    };
+    let (mut intact, mut changed, mut missing) = (0, 0, 0);
+    for (path, sha256) in manifest::parse_checksums(&text) {
+        match fs::read(args.dir.join(&path)) {
+            Ok(bytes) if sha256_hex(&bytes) == sha256 => intact += 1,
+            Ok(_) => {
+                changed += 1;
+                println!("{}", tr!(VerifyMismatch, path));
+            }
+            Err(_) => {
+                missing += 1;
+                println!("{}", tr!(VerifyMissing, path));
+            }
+        }
+    }
+    eprintln!("{}", tr!(VerifySummary, intact, changed, missing));
+    if changed + missing > 0 {
+        std::process::exit(1);
+    }
+}
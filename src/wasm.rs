@@ -0,0 +1,65 @@
+//! JavaScript bindings for extracting a HAR file in the browser, built with
+//! `wasm-pack build --target web --features wasm`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{extraction, har, sink::MemorySink};
+
+/// A file extracted from the capture.
+#[wasm_bindgen]
+pub struct ExtractedFile {
+    path: String,
+    bytes: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl ExtractedFile {
+    /// Path the file would have been written to, relative to the output folder.
+    #[wasm_bindgen(getter)]
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    /// Contents of the file, as a `Uint8Array`.
+    #[wasm_bindgen(getter)]
+    pub fn bytes(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+}
+
+/// Extracts the images in the HAR document `har`, plus the types given in `mimeTypes`
+/// as `image/avif=.avif`. Entries that cannot be decoded are left out.
+#[wasm_bindgen]
+pub fn extract(
+    har: &[u8],
+    #[wasm_bindgen(js_name = mimeTypes)] mime_types: Option<Vec<String>>,
+) -> Result<Vec<ExtractedFile>, JsError> {
+    let (har, _) = har::parse_lenient(har)?;
+    let extra = mime_types
+        .unwrap_or_default()
+        .iter()
+        .map(|x| {
+            let (mime_type, ext) = x
+                .split_once('=')
+                .ok_or_else(|| JsError::new(&format!("expected `type=.ext`, got `{x}`")))?;
+            Ok((
+                mime_type.to_string(),
+                format!(".{}", ext.trim_start_matches('.')),
+            ))
+        })
+        .collect::<Result<Vec<_>, JsError>>()?;
+    let mut types = extraction::default_mime_types();
+    for (mime_type, ext) in &extra {
+        types.insert(mime_type, ext);
+    }
+    let mut sink = MemorySink::default();
+    extraction::extract(&har, &types, &mut sink);
+    Ok(sink
+        .files
+        .into_iter()
+        .map(|(path, bytes)| ExtractedFile {
+            path: path.to_string_lossy().into_owned(),
+            bytes,
+        })
+        .collect())
+}
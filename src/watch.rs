@@ -0,0 +1,86 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    time::{Duration, Instant, SystemTime},
+};
+
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::de::IgnoredAny;
+
+use crate::extract::{self, ExtractArgs, RunError};
+
+/// How long a file must go without changes before it is taken as fully written.
+const SETTLE: Duration = Duration::from_secs(2);
+
+fn is_har(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|x| x.eq_ignore_ascii_case("har"))
+}
+
+/// Whether the file holds a whole JSON document, rather than the start of one still
+/// being downloaded.
+fn is_complete(path: &Path) -> bool {
+    let Ok(data) = fs::read(path) else {
+        return false;
+    };
+    let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(&data);
+    serde_json::Deserializer::from_slice(data)
+        .into_iter::<IgnoredAny>()
+        .next()
+        .is_some_and(|x| x.is_ok())
+}
+
+/// Extracts one file; one that cannot be extracted is reported and the folder is
+/// watched on.
+fn extract(path: &Path, args: &ExtractArgs) {
+    eprintln!("{}", tr!(WatchFound, path.to_string_lossy()));
+    if let Err(err) = extract::run(args.with_input(path.to_string_lossy().into_owned())) {
+        eprintln!("{}", tr!(WatchFailed, path.to_string_lossy(), err.message));
+    }
+}
+
+/// Extracts the HAR files created in or moved into `folder`, once each has stopped
+/// changing for [`SETTLE`]. Files already there are left alone.
+pub fn run(folder: &Path, args: &ExtractArgs) -> Result<(), RunError> {
+    let cannot_watch = |err| RunError::new(tr!(CannotWatchFolder, folder.to_string_lossy(), err));
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(cannot_watch)?;
+    watcher
+        .watch(folder, RecursiveMode::NonRecursive)
+        .map_err(cannot_watch)?;
+    eprintln!("{}", tr!(WatchingFolder, folder.to_string_lossy()));
+    let mut pending = HashMap::<PathBuf, Instant>::new();
+    // Modification time each file was extracted at, so touching it does not extract
+    // it again.
+    let mut extracted = HashMap::<PathBuf, SystemTime>::new();
+    loop {
+        match receiver.recv_timeout(SETTLE) {
+            Ok(Ok(event)) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                for path in event.paths.into_iter().filter(|x| is_har(x)) {
+                    pending.insert(path, Instant::now());
+                }
+            }
+            Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+            Ok(Err(err)) => eprintln!("{}", tr!(CannotWatchFolder, folder.to_string_lossy(), err)),
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        let settled = pending
+            .iter()
+            .filter(|(_, changed)| changed.elapsed() >= SETTLE)
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+        for path in settled {
+            pending.remove(&path);
+            let Ok(modified) = fs::metadata(&path).and_then(|x| x.modified()) else {
+                continue;
+            };
+            if extracted.get(&path) == Some(&modified) || !is_complete(&path) {
+                continue;
+            }
+            extract(&path, args);
+            extracted.insert(path, modified);
+        }
+    }
+}
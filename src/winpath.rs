@@ -0,0 +1,203 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::manifest::sha256_hex;
+
+/// Longest path most Windows programs can open without the `\\?\` prefix.
+pub const MAX_PATH: usize = 259;
+
+const RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Makes one path component valid on Windows: forbidden characters become `_`,
+/// trailing dots and spaces are replaced, and device names such as `CON` or `aux.js`
+/// get a leading `_`.
+pub fn sanitize_component(name: &str) -> String {
+    let mut result = name
+        .chars()
+        .map(|x| {
+            if x.is_control() || r#"<>:"/\|?*"#.contains(x) {
+                '_'
+            } else {
+                x
+            }
+        })
+        .collect::<String>();
+    let kept = result.trim_end_matches(['.', ' ']).len();
+    if kept < result.len() {
+        let removed = result.len() - kept;
+        result.truncate(kept);
+        result.extend(std::iter::repeat_n('_', removed));
+    }
+    let stem = result.split('.').next().unwrap_or_default();
+    if RESERVED
+        .iter()
+        .any(|x| x.eq_ignore_ascii_case(stem.trim_end()))
+    {
+        result.insert(0, '_');
+    }
+    result
+}
+
+pub fn sanitize(path: &Path) -> PathBuf {
+    path.iter()
+        .map(|x| sanitize_component(&x.to_string_lossy()))
+        .collect()
+}
+
+/// Inserts `suffix` between a file name's stem and extension.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let name = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem}{suffix}.{ext}"),
+        _ => format!("{name}{suffix}"),
+    };
+    path.with_file_name(name)
+}
+
+/// Shortens the file name so `root` joined with `path` stays within [`MAX_PATH`],
+/// keeping the extension and a hash of the full name so shortened names stay unique.
+/// Paths whose folders alone are too long are returned unchanged.
+pub fn fit_length(root: &Path, path: &Path) -> PathBuf {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let length = root.join(path).to_string_lossy().chars().count();
+    if length <= MAX_PATH {
+        return path.to_path_buf();
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && ext.len() <= 16 => (stem, format!(".{ext}")),
+        _ => (name.as_ref(), String::new()),
+    };
+    let hash = format!("~{}", &sha256_hex(name.as_bytes())[..8]);
+    let excess = length - MAX_PATH;
+    let Some(kept) = (stem.chars().count())
+        .checked_sub(excess + hash.len())
+        .filter(|x| *x > 0)
+    else {
+        return path.to_path_buf();
+    };
+    let stem = stem.chars().take(kept).collect::<String>();
+    path.with_file_name(format!("{stem}{hash}{ext}"))
+}
+
+/// Paths written so far, to tell apart files whose names differ only in case, which
+/// would overwrite each other on case-insensitive file systems.
+#[derive(Default)]
+pub struct CaseFolder {
+    seen: HashMap<String, PathBuf>,
+}
+
+impl CaseFolder {
+    /// Returns `path`, or a `~2`, `~3`... variant of it if another file already took
+    /// the same name in a different case.
+    pub fn claim(&mut self, path: &Path) -> PathBuf {
+        let mut candidate = path.to_path_buf();
+        let mut n = 1;
+        loop {
+            let key = candidate.to_string_lossy().to_lowercase();
+            match self.seen.get(&key) {
+                Some(existing) if *existing != candidate => {
+                    n += 1;
+                    candidate = with_suffix(path, &format!("~{n}"));
+                }
+                _ => {
+                    self.seen.insert(key, candidate.clone());
+                    return candidate;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_reserved_device_names() {
+        assert_eq!(sanitize_component("aux.js"), "_aux.js");
+        assert_eq!(sanitize_component("CON"), "_CON");
+        assert_eq!(sanitize_component("com1.tar.gz"), "_com1.tar.gz");
+        assert_eq!(sanitize_component("nul .txt"), "_nul .txt");
+        assert_eq!(sanitize_component("console.js"), "console.js");
+        assert_eq!(sanitize_component("lpt10"), "lpt10");
+    }
+
+    #[test]
+    fn replaces_forbidden_characters_and_trailing_dots() {
+        assert_eq!(sanitize_component("a. "), "a__");
+        assert_eq!(sanitize_component("name..."), "name___");
+        assert_eq!(sanitize_component("a:b?c*d\"e<f>g|h"), "a_b_c_d_e_f_g_h");
+        assert_eq!(sanitize_component("tab\there"), "tab_here");
+        // Once its dot is replaced, the name no longer names the device.
+        assert_eq!(sanitize_component("aux."), "aux_");
+        assert_eq!(
+            sanitize(Path::new("a:b/con/c.")),
+            Path::new("a_b").join("_con").join("c_")
+        );
+    }
+
+    #[test]
+    fn keeps_paths_that_fit() {
+        let path = Path::new("a.com/logo.png");
+        assert_eq!(fit_length(Path::new("/out"), path), path);
+    }
+
+    #[test]
+    fn shortens_long_file_names_keeping_the_extension() {
+        let root = Path::new("/out");
+        let long =
+            |x: char| PathBuf::from("a.com").join(format!("{}.js", x.to_string().repeat(300)));
+        let fitted = fit_length(root, &long('x'));
+        assert_eq!(
+            root.join(&fitted).to_string_lossy().chars().count(),
+            MAX_PATH
+        );
+        assert_eq!(fitted.parent(), Some(Path::new("a.com")));
+        let name = fitted.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("xxx"), "{name}");
+        assert!(name.ends_with(".js"), "{name}");
+        let hash = &sha256_hex(long('x').file_name().unwrap().as_encoded_bytes())[..8];
+        assert!(name.contains(&format!("~{hash}.")), "{name}");
+        assert_ne!(fit_length(root, &long('y')), fitted);
+    }
+
+    #[test]
+    fn leaves_paths_whose_folders_are_too_long() {
+        let path = Path::new(&"d".repeat(300)).join("a.js");
+        assert_eq!(fit_length(Path::new("/out"), &path), path);
+    }
+
+    #[test]
+    fn renames_names_that_differ_only_in_case() {
+        let mut folder = CaseFolder::default();
+        assert_eq!(
+            folder.claim(Path::new("a/Logo.png")),
+            Path::new("a/Logo.png")
+        );
+        assert_eq!(
+            folder.claim(Path::new("a/logo.png")),
+            Path::new("a/logo~2.png")
+        );
+        assert_eq!(
+            folder.claim(Path::new("a/LOGO.png")),
+            Path::new("a/LOGO~3.png")
+        );
+        // The same path again is the same file.
+        assert_eq!(
+            folder.claim(Path::new("a/Logo.png")),
+            Path::new("a/Logo.png")
+        );
+        assert_eq!(
+            folder.claim(Path::new("A/logo.png")),
+            Path::new("A/logo~4.png")
+        );
+        assert_eq!(folder.claim(Path::new("a/README")), Path::new("a/README"));
+        assert_eq!(folder.claim(Path::new("a/readme")), Path::new("a/readme~2"));
+    }
+}